@@ -0,0 +1,354 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Checks locked dependencies against an advisory database: a directory,
+//! typically a git checkout, of TOML files modelled on RustSec's.
+//!
+//! ```toml
+//! [advisory]
+//! id = "DUB-2024-0001"
+//! package = "vibe-d"
+//! date = "2024-01-10"
+//! title = "HTTP request smuggling through chunked encoding"
+//! url = "https://github.com/vibe-d/vibe.d/issues/1234"
+//! # informational = "unmaintained"  # or "unsound", "notice", "yanked"
+//!
+//! [versions]
+//! patched = [">=0.9.8"]
+//! unaffected = ["<0.8.0"]
+//! ```
+
+use crate::selections::{base_name, Selection, Selections};
+use crate::version::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Toml(PathBuf, toml::de::Error),
+    /// A version range in an advisory does not parse.
+    Range {
+        path: PathBuf,
+        range: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Failed to read '{}': {e}", path.display()),
+            Error::Toml(path, e) => write!(f, "Invalid advisory '{}': {e}", path.display()),
+            Error::Range { path, range } => write!(
+                f,
+                "Invalid advisory '{}': bad version range '{range}'",
+                path.display()
+            ),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Advisories that are not vulnerabilities; reported as warnings.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Informational {
+    Unmaintained,
+    Unsound,
+    Notice,
+    /// The release was withdrawn by its authors.
+    Yanked,
+}
+
+impl fmt::Display for Informational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Informational::Unmaintained => "unmaintained",
+            Informational::Unsound => "unsound",
+            Informational::Notice => "notice",
+            Informational::Yanked => "yanked",
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Advisory {
+    pub id: String,
+    pub package: String,
+    #[serde(default)]
+    pub date: String,
+    pub title: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub aliases: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub informational: Option<Informational>,
+}
+
+/// Version ranges of an advisory, in DUB's syntax. Versions matching
+/// neither list are affected.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Versions {
+    pub patched: Vec<String>,
+    pub unaffected: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct AdvisoryFile {
+    advisory: Advisory,
+    #[serde(default)]
+    versions: Versions,
+}
+
+/// An advisory together with its parsed ranges.
+#[derive(Clone, Debug)]
+pub struct Entry {
+    pub advisory: Advisory,
+    pub versions: Versions,
+    safe: Vec<VersionReq>,
+}
+
+impl Entry {
+    pub fn affects(&self, version: &Version) -> bool {
+        !self.safe.iter().any(|range| range.matches(version))
+    }
+}
+
+/// Every advisory in a database directory.
+#[derive(Clone, Debug, Default)]
+pub struct Database {
+    pub entries: Vec<Entry>,
+}
+
+impl Database {
+    /// Loads all `*.toml` files below `dir`, skipping hidden directories
+    /// such as `.git`.
+    pub fn open(dir: &Path) -> Result<Self> {
+        let mut files = Vec::new();
+        let mut pending = vec![dir.to_path_buf()];
+        while let Some(current) = pending.pop() {
+            let entries = fs::read_dir(&current).map_err(|e| Error::Io(current.clone(), e))?;
+            for entry in entries {
+                let path = entry.map_err(|e| Error::Io(current.clone(), e))?.path();
+                let hidden = path
+                    .file_name()
+                    .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+                if path.is_dir() && !hidden {
+                    pending.push(path);
+                } else if path.extension().is_some_and(|ext| ext == "toml") {
+                    files.push(path);
+                }
+            }
+        }
+        files.sort();
+
+        let mut entries = Vec::new();
+        for path in files {
+            let text = fs::read_to_string(&path).map_err(|e| Error::Io(path.clone(), e))?;
+            let file: AdvisoryFile =
+                toml::from_str(&text).map_err(|e| Error::Toml(path.clone(), e))?;
+            let safe = file
+                .versions
+                .patched
+                .iter()
+                .chain(&file.versions.unaffected)
+                .map(|range| {
+                    range.parse().map_err(|_| Error::Range {
+                        path: path.clone(),
+                        range: range.clone(),
+                    })
+                })
+                .collect::<Result<_>>()?;
+            entries.push(Entry {
+                advisory: file.advisory,
+                versions: file.versions,
+                safe,
+            });
+        }
+        Ok(Self { entries })
+    }
+
+    /// Advisories affecting the registry versions locked in `selections`.
+    pub fn audit(&self, selections: &Selections) -> Vec<Finding> {
+        let mut findings = Vec::new();
+        for (name, selection) in &selections.versions {
+            let Selection::Version(locked) = selection else {
+                continue;
+            };
+            let Ok(version) = locked.parse::<Version>() else {
+                continue;
+            };
+            for entry in &self.entries {
+                if base_name(&entry.advisory.package) == name && entry.affects(&version) {
+                    findings.push(Finding {
+                        package: name.clone(),
+                        version: locked.clone(),
+                        advisory: entry.advisory.clone(),
+                        versions: entry.versions.clone(),
+                    });
+                }
+            }
+        }
+        findings
+    }
+}
+
+/// A locked package version affected by an advisory.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Finding {
+    pub package: String,
+    pub version: String,
+    pub advisory: Advisory,
+    pub versions: Versions,
+}
+
+impl Finding {
+    /// Vulnerabilities fail the audit; informational advisories warn.
+    pub fn is_warning(&self) -> bool {
+        self.advisory.informational.is_some()
+    }
+
+    /// What to do about it, as `cargo audit` words it.
+    pub fn solution(&self) -> String {
+        if self.versions.patched.is_empty() {
+            "No fixed upgrade is available!".to_string()
+        } else {
+            format!("Upgrade to {}", self.versions.patched.join(" OR "))
+        }
+    }
+}
+
+impl fmt::Display for Finding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let advisory = &self.advisory;
+        let kind = match advisory.informational {
+            Some(informational) => format!("Warning:   {informational}\n"),
+            None => String::new(),
+        };
+        write!(
+            f,
+            "{kind}Package:   {}\nVersion:   {}\nTitle:     {}\n",
+            self.package, self.version, advisory.title
+        )?;
+        if !advisory.date.is_empty() {
+            writeln!(f, "Date:      {}", advisory.date)?;
+        }
+        writeln!(f, "ID:        {}", advisory.id)?;
+        if !advisory.aliases.is_empty() {
+            writeln!(f, "Aliases:   {}", advisory.aliases.join(", "))?;
+        }
+        if let Some(url) = &advisory.url {
+            writeln!(f, "URL:       {url}")?;
+        }
+        writeln!(f, "Solution:  {}", self.solution())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn database() -> (TempDir, Database) {
+        let temp = TempDir::new().unwrap();
+        let packages = temp.path().join("packages");
+        fs::create_dir_all(packages.join("vibe-d")).unwrap();
+        fs::create_dir_all(packages.join("silly")).unwrap();
+        fs::create_dir_all(temp.path().join(".git")).unwrap();
+        fs::write(temp.path().join(".git/config.toml"), "not an advisory").unwrap();
+        fs::write(
+            packages.join("vibe-d/DUB-2024-0001.toml"),
+            r#"
+            [advisory]
+            id = "DUB-2024-0001"
+            package = "vibe-d"
+            date = "2024-01-10"
+            title = "HTTP request smuggling"
+            url = "https://example.com/DUB-2024-0001"
+            aliases = ["CVE-2024-0001"]
+
+            [versions]
+            patched = [">=0.9.8"]
+            unaffected = ["<0.8.0"]
+            "#,
+        )
+        .unwrap();
+        fs::write(
+            packages.join("silly/DUB-2024-0002.toml"),
+            r#"
+            [advisory]
+            id = "DUB-2024-0002"
+            package = "silly"
+            title = "silly is unmaintained"
+            informational = "unmaintained"
+            "#,
+        )
+        .unwrap();
+        let db = Database::open(temp.path()).unwrap();
+        (temp, db)
+    }
+
+    #[test]
+    fn test_audit() {
+        let (_temp, db) = database();
+        assert_eq!(db.entries.len(), 2);
+        let selections = Selections::from_json_str(
+            r#"{"fileVersion": 1, "versions": {"vibe-d": "0.9.5", "silly": "1.1.1",
+                "dlib": {"path": "../dlib"}}}"#,
+        )
+        .unwrap();
+        let findings = db.audit(&selections);
+        let ids: Vec<(&str, bool)> = findings
+            .iter()
+            .map(|f| (f.advisory.id.as_str(), f.is_warning()))
+            .collect();
+        assert_eq!(ids, [("DUB-2024-0002", true), ("DUB-2024-0001", false)]);
+        assert_eq!(
+            findings[1].to_string(),
+            "Package:   vibe-d\n\
+             Version:   0.9.5\n\
+             Title:     HTTP request smuggling\n\
+             Date:      2024-01-10\n\
+             ID:        DUB-2024-0001\n\
+             Aliases:   CVE-2024-0001\n\
+             URL:       https://example.com/DUB-2024-0001\n\
+             Solution:  Upgrade to >=0.9.8\n"
+        );
+        assert_eq!(findings[0].solution(), "No fixed upgrade is available!");
+
+        for safe in ["0.9.8", "0.7.0"] {
+            let selections = Selections::from_json_str(&format!(
+                r#"{{"fileVersion": 1, "versions": {{"vibe-d": "{safe}"}}}}"#
+            ))
+            .unwrap();
+            assert!(db.audit(&selections).is_empty(), "{safe}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_advisory() {
+        let temp = TempDir::new().unwrap();
+        let path = temp.path().join("bad.toml");
+        fs::write(
+            &path,
+            "[advisory]\nid = \"X\"\npackage = \"x\"\ntitle = \"x\"\n[versions]\npatched = [\"later\"]\n",
+        )
+        .unwrap();
+        assert_eq!(
+            Database::open(temp.path()).unwrap_err().to_string(),
+            format!(
+                "Invalid advisory '{}': bad version range 'later'",
+                path.display()
+            )
+        );
+    }
+}
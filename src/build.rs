@@ -0,0 +1,575 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Building DUB packages from Cargo build scripts.
+//!
+//! ```no_run
+//! // in build.rs `main`
+//! cargo_dub::build::Build::new("dlib")
+//!     .compiler("ldc2")
+//!     .compile();
+//! ```
+//!
+//! The package is built as a static (or dynamic) library in a single compiler
+//! run (`dub build --combined`), so its dependencies end up in the same
+//! artifact. The artifact is copied to `OUT_DIR` and the `cargo:` directives
+//! needed to link it, the libraries it declares and the D runtime of the
+//! selected compiler are printed to stdout.
+
+use crate::describe::{Description, Package};
+use crate::manifest::{self, Manifest, TargetType};
+use crate::target::{self, Target};
+use crate::toolchain::{find_executable, Family};
+use std::ffi::OsStr;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::{env, fs, io};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),
+    Manifest(manifest::Error),
+    Target(target::Error),
+    Describe(serde_json::Error),
+    /// DUB exited unsuccessfully; holds the command that failed.
+    Dub(String),
+    Config(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "{e}"),
+            Error::Manifest(e) => write!(f, "{e}"),
+            Error::Target(e) => write!(f, "{e}"),
+            Error::Describe(e) => write!(f, "Invalid `dub describe` output: {e}"),
+            Error::Dub(cmd) => write!(f, "`{cmd}` failed"),
+            Error::Config(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+impl From<manifest::Error> for Error {
+    fn from(e: manifest::Error) -> Self {
+        Error::Manifest(e)
+    }
+}
+
+impl From<target::Error> for Error {
+    fn from(e: target::Error) -> Self {
+        Error::Target(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Kind of library to build and link.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LibraryKind {
+    Static,
+    Dynamic,
+}
+
+impl LibraryKind {
+    fn accepts(self, target_type: Option<TargetType>) -> bool {
+        match self {
+            LibraryKind::Static => matches!(
+                target_type,
+                Some(TargetType::StaticLibrary | TargetType::Library)
+            ),
+            LibraryKind::Dynamic => target_type == Some(TargetType::DynamicLibrary),
+        }
+    }
+
+    fn link_kind(self) -> &'static str {
+        match self {
+            LibraryKind::Static => "static",
+            LibraryKind::Dynamic => "dylib",
+        }
+    }
+}
+
+/// Builder for a DUB package linked into the crate being built.
+#[derive(Clone, Debug)]
+pub struct Build {
+    package: PathBuf,
+    dub: Option<String>,
+    compiler: Option<String>,
+    build_type: Option<String>,
+    config: Option<String>,
+    arch: Option<String>,
+    target: Option<String>,
+    kind: LibraryKind,
+    link_runtime: Option<bool>,
+    args: Vec<String>,
+}
+
+/// The library produced by [`Build::compile`].
+#[derive(Clone, Debug)]
+pub struct Artifact {
+    /// Location of the linked copy of the library.
+    pub path: PathBuf,
+    /// Name passed to `cargo:rustc-link-lib`.
+    pub link_name: String,
+    pub kind: LibraryKind,
+    pub description: Description,
+}
+
+impl Build {
+    /// Starts a build of the DUB package in `package`.
+    pub fn new(package: impl AsRef<Path>) -> Self {
+        Self {
+            package: package.as_ref().to_path_buf(),
+            dub: None,
+            compiler: None,
+            build_type: None,
+            config: None,
+            arch: None,
+            target: None,
+            kind: LibraryKind::Static,
+            link_runtime: None,
+            args: Vec::new(),
+        }
+    }
+
+    /// DUB executable to use [default: `$DUB` or `dub`].
+    pub fn dub(&mut self, dub: impl Into<String>) -> &mut Self {
+        self.dub = Some(dub.into());
+        self
+    }
+
+    /// D compiler to use [default: `$DC` or DUB's choice].
+    pub fn compiler(&mut self, compiler: impl Into<String>) -> &mut Self {
+        self.compiler = Some(compiler.into());
+        self
+    }
+
+    /// DUB build type [default: `release` for release profiles, `debug` otherwise].
+    pub fn build_type(&mut self, build_type: impl Into<String>) -> &mut Self {
+        self.build_type = Some(build_type.into());
+        self
+    }
+
+    /// Configuration to build [default: the first one producing the requested library kind].
+    pub fn config(&mut self, config: impl Into<String>) -> &mut Self {
+        self.config = Some(config.into());
+        self
+    }
+
+    pub fn arch(&mut self, arch: impl Into<String>) -> &mut Self {
+        self.arch = Some(arch.into());
+        self
+    }
+
+    /// Rust target triple to build for, mapped to `--arch` for the compiler
+    /// in use [default: `$TARGET` when it differs from `$HOST`]. An explicit
+    /// [`arch`](Self::arch) wins.
+    pub fn target(&mut self, triple: impl Into<String>) -> &mut Self {
+        self.target = Some(triple.into());
+        self
+    }
+
+    pub fn static_library(&mut self) -> &mut Self {
+        self.kind = LibraryKind::Static;
+        self
+    }
+
+    pub fn dynamic_library(&mut self) -> &mut Self {
+        self.kind = LibraryKind::Dynamic;
+        self
+    }
+
+    /// Whether to link druntime and phobos [default: only for static libraries].
+    pub fn link_runtime(&mut self, link: bool) -> &mut Self {
+        self.link_runtime = Some(link);
+        self
+    }
+
+    /// Extra argument passed to both `dub build` and `dub describe`.
+    pub fn arg(&mut self, arg: impl Into<String>) -> &mut Self {
+        self.args.push(arg.into());
+        self
+    }
+
+    /// Builds and links the package, panicking with a readable message on failure.
+    pub fn compile(&self) -> Artifact {
+        match self.try_compile() {
+            Ok(artifact) => artifact,
+            Err(e) => panic!(
+                "failed to build D package '{}': {e}",
+                self.package.display()
+            ),
+        }
+    }
+
+    pub fn try_compile(&self) -> Result<Artifact> {
+        let recipe = Manifest::find(&self.package).ok_or_else(|| {
+            Error::Config(format!("no DUB recipe in '{}'", self.package.display()))
+        })?;
+        let manifest = Manifest::from_path(&recipe)?;
+        let config = match &self.config {
+            Some(config) => Some(config.clone()),
+            None => select_configuration(&manifest, self.kind)?,
+        };
+        let args = self.dub_args(config.as_deref())?;
+
+        self.run_dub("build", &args, true)?;
+        let stdout = self.run_dub("describe", &args, false)?;
+        let description = Description::from_json(&stdout).map_err(Error::Describe)?;
+        let root = description.root().ok_or_else(|| {
+            Error::Config(format!(
+                "`dub describe` did not list root package '{}'",
+                description.root_package
+            ))
+        })?;
+        let built = root.artifact_path().ok_or_else(|| {
+            Error::Config(format!("package '{}' produces no artifact", root.name))
+        })?;
+        let file_name = built.file_name().unwrap_or_default();
+        let link_name = link_name(&file_name.to_string_lossy());
+
+        let path = match env::var_os("OUT_DIR") {
+            Some(out_dir) => {
+                let copy = PathBuf::from(out_dir).join(file_name);
+                fs::copy(&built, &copy)?;
+                copy
+            }
+            None => built,
+        };
+
+        let link_runtime = self
+            .link_runtime
+            .unwrap_or(self.kind == LibraryKind::Static);
+        let runtime = if link_runtime {
+            let compiler = self.compiler.clone().or_else(|| env::var("DC").ok());
+            Runtime::detect(
+                description.compiler.as_deref().unwrap_or_default(),
+                compiler.as_deref(),
+            )
+        } else {
+            Runtime::default()
+        };
+
+        let search = path.parent().unwrap_or(Path::new("."));
+        for line in link_directives(&description, search, &link_name, self.kind, &runtime) {
+            println!("{line}");
+        }
+        println!("cargo:rerun-if-changed={}", recipe.display());
+        let selections = self.package.join("dub.selections.json");
+        if selections.exists() {
+            println!("cargo:rerun-if-changed={}", selections.display());
+        }
+        println!("cargo:rerun-if-env-changed=DC");
+
+        Ok(Artifact {
+            path,
+            link_name,
+            kind: self.kind,
+            description,
+        })
+    }
+
+    fn dub_args(&self, config: Option<&str>) -> Result<Vec<String>> {
+        let mut args = Vec::new();
+        if let Some(compiler) = self.compiler.clone().or_else(|| env::var("DC").ok()) {
+            args.push(format!("--compiler={compiler}"));
+        }
+        let build_type = self.build_type.clone().unwrap_or_else(|| {
+            match env::var("PROFILE").as_deref() {
+                Ok("release") => "release",
+                _ => "debug",
+            }
+            .to_string()
+        });
+        args.push(format!("--build={build_type}"));
+        if let Some(config) = config {
+            args.push(format!("--config={config}"));
+        }
+        if let Some(arch) = self.resolve_arch()? {
+            args.push(format!("--arch={arch}"));
+        }
+        args.extend(self.args.iter().cloned());
+        Ok(args)
+    }
+
+    fn resolve_arch(&self) -> Result<Option<String>> {
+        if self.arch.is_some() {
+            return Ok(self.arch.clone());
+        }
+        let target = self.target.clone().or_else(|| {
+            let target = env::var("TARGET").ok()?;
+            (env::var("HOST").ok()? != target).then_some(target)
+        });
+        let Some(triple) = target else {
+            return Ok(None);
+        };
+        let compiler = self.compiler.clone().or_else(|| env::var("DC").ok());
+        let family = compiler.as_deref().and_then(Family::of);
+        Ok(Target::parse(&triple)?.dub_arch(family)?)
+    }
+
+    fn run_dub(&self, subcommand: &str, args: &[String], build: bool) -> Result<String> {
+        let dub = self
+            .dub
+            .clone()
+            .or_else(|| env::var("DUB").ok())
+            .unwrap_or_else(|| "dub".to_string());
+        let mut cmd = Command::new(&dub);
+        cmd.arg(subcommand).args(args).current_dir(&self.package);
+        if build {
+            cmd.arg("--combined");
+        }
+        let rendered = format!("{dub} {subcommand} {}", args.join(" "));
+        // Build scripts talk to Cargo through stdout, so DUB's chatter goes to stderr.
+        let output = cmd.stderr(Stdio::inherit()).output()?;
+        if !output.status.success() {
+            return Err(Error::Dub(rendered));
+        }
+        if build {
+            eprint!("{}", String::from_utf8_lossy(&output.stdout));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+}
+
+/// Picks the configuration that produces the requested library kind, or
+/// `None` when the package default already does.
+pub fn select_configuration(manifest: &Manifest, kind: LibraryKind) -> Result<Option<String>> {
+    let root = manifest.settings.target_type;
+    let default_ok = match manifest.configurations.first() {
+        Some(config) => kind.accepts(config.settings.target_type.or(root)),
+        None => kind.accepts(root.or(Some(TargetType::Library))),
+    };
+    if default_ok {
+        return Ok(None);
+    }
+    manifest
+        .configurations
+        .iter()
+        .find(|c| kind.accepts(c.settings.target_type.or(root)))
+        .map(|c| Some(c.name.clone()))
+        .ok_or_else(|| {
+            Error::Config(format!(
+                "package '{}' has no {} configuration",
+                manifest.name,
+                match kind {
+                    LibraryKind::Static => "staticLibrary",
+                    LibraryKind::Dynamic => "dynamicLibrary",
+                }
+            ))
+        })
+}
+
+/// Turns `libfoo.a`, `foo.lib` or `libfoo.so` into `foo`.
+pub fn link_name(file_name: &str) -> String {
+    let stem = Path::new(file_name)
+        .file_stem()
+        .and_then(OsStr::to_str)
+        .unwrap_or(file_name);
+    let stem = stem.split(".so").next().unwrap_or(stem);
+    if file_name.ends_with(".lib") || file_name.ends_with(".dll") {
+        stem.to_string()
+    } else {
+        stem.strip_prefix("lib").unwrap_or(stem).to_string()
+    }
+}
+
+/// The D runtime libraries of a compiler and where to find them.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Runtime {
+    pub libs: Vec<String>,
+    pub search_paths: Vec<PathBuf>,
+}
+
+impl Runtime {
+    /// Describes the runtime for a compiler family (`dmd`, `ldc` or `gdc`, as
+    /// reported by `dub describe`) and an optional compiler binary.
+    pub fn detect(family: &str, compiler: Option<&str>) -> Self {
+        let (libs, default_binary): (&[&str], &str) = match family {
+            "ldc" => (&["phobos2-ldc", "druntime-ldc"], "ldc2"),
+            "gdc" => (&["gphobos", "gdruntime"], "gdc"),
+            _ => (&["phobos2"], "dmd"),
+        };
+        let binary = find_executable(compiler.unwrap_or(default_binary));
+        let mut search_paths = Vec::new();
+        if family == "gdc" {
+            let library = binary.as_deref().and_then(|gdc| {
+                let output = Command::new(gdc)
+                    .arg("-print-file-name=libgphobos.a")
+                    .output()
+                    .ok()?;
+                Some(PathBuf::from(
+                    String::from_utf8_lossy(&output.stdout).trim(),
+                ))
+            });
+            if let Some(dir) = library
+                .filter(|lib| lib.is_absolute())
+                .and_then(|lib| lib.parent().map(Path::to_path_buf))
+            {
+                search_paths.push(dir);
+            }
+        } else if let Some(prefix) = binary
+            .as_deref()
+            .and_then(Path::parent)
+            .and_then(Path::parent)
+        {
+            search_paths.extend(
+                ["lib", "lib64", "lib32"]
+                    .iter()
+                    .map(|dir| prefix.join(dir))
+                    .filter(|dir| dir.is_dir()),
+            );
+        }
+        Self {
+            libs: libs.iter().map(|l| l.to_string()).collect(),
+            search_paths,
+        }
+    }
+}
+
+/// The `cargo:` lines linking an artifact, its declared libraries and the runtime.
+pub fn link_directives(
+    description: &Description,
+    search: &Path,
+    link_name: &str,
+    kind: LibraryKind,
+    runtime: &Runtime,
+) -> Vec<String> {
+    let mut lines = vec![
+        format!("cargo:rustc-link-search=native={}", search.display()),
+        format!("cargo:rustc-link-lib={}={link_name}", kind.link_kind()),
+    ];
+    let packages: Vec<&Package> = description.active_packages().collect();
+    for package in &packages {
+        for lib in &package.libs {
+            let line = format!("cargo:rustc-link-lib={lib}");
+            if !lines.contains(&line) {
+                lines.push(line);
+            }
+        }
+    }
+    for dir in &runtime.search_paths {
+        lines.push(format!("cargo:rustc-link-search=native={}", dir.display()));
+    }
+    for lib in &runtime.libs {
+        lines.push(format!("cargo:rustc-link-lib=static={lib}"));
+    }
+    for package in &packages {
+        for file in package.input_files() {
+            lines.push(format!("cargo:rerun-if-changed={}", file.display()));
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::describe::tests::DESCRIBE;
+
+    #[test]
+    fn test_target_arch() {
+        let mut build = Build::new("dlib");
+        build.compiler("ldc2").target("aarch64-unknown-linux-gnu");
+        assert_eq!(
+            build.resolve_arch().unwrap().as_deref(),
+            Some("aarch64-unknown-linux-gnu")
+        );
+        build.arch("x86");
+        assert_eq!(build.resolve_arch().unwrap().as_deref(), Some("x86"));
+
+        let mut build = Build::new("dlib");
+        build.compiler("dmd").target("wasm32-unknown-unknown");
+        assert!(matches!(build.resolve_arch(), Err(Error::Target(_))));
+    }
+
+    #[test]
+    fn test_link_name() {
+        assert_eq!(link_name("libdlib.a"), "dlib");
+        assert_eq!(link_name("dlib.lib"), "dlib");
+        assert_eq!(link_name("libdlib.so"), "dlib");
+        assert_eq!(link_name("libdlib.so.1.2"), "dlib");
+        assert_eq!(link_name("libdlib.dylib"), "dlib");
+    }
+
+    #[test]
+    fn test_select_configuration() {
+        let manifest = Manifest::from_sdl_str(
+            "name \"dlib\"\nconfiguration \"app\" {\n\ttargetType \"executable\"\n}\nconfiguration \"static\" {\n\ttargetType \"staticLibrary\"\n}\nconfiguration \"shared\" {\n\ttargetType \"dynamicLibrary\"\n}\n",
+        )
+        .unwrap();
+        assert_eq!(
+            select_configuration(&manifest, LibraryKind::Static).unwrap(),
+            Some("static".to_string())
+        );
+        assert_eq!(
+            select_configuration(&manifest, LibraryKind::Dynamic).unwrap(),
+            Some("shared".to_string())
+        );
+
+        let library = Manifest::from_sdl_str("name \"dlib\"").unwrap();
+        assert_eq!(
+            select_configuration(&library, LibraryKind::Static).unwrap(),
+            None
+        );
+        assert_eq!(
+            select_configuration(&library, LibraryKind::Dynamic)
+                .unwrap_err()
+                .to_string(),
+            "package 'dlib' has no dynamicLibrary configuration"
+        );
+    }
+
+    #[test]
+    fn test_link_directives() {
+        let description = Description::from_json(DESCRIBE).unwrap();
+        let runtime = Runtime {
+            libs: vec!["phobos2-ldc".into(), "druntime-ldc".into()],
+            search_paths: vec![PathBuf::from("/opt/ldc/lib")],
+        };
+        let lines = link_directives(
+            &description,
+            Path::new("/out"),
+            "dlib",
+            LibraryKind::Static,
+            &runtime,
+        );
+        assert_eq!(
+            lines,
+            vec![
+                "cargo:rustc-link-search=native=/out",
+                "cargo:rustc-link-lib=static=dlib",
+                "cargo:rustc-link-lib=z",
+                "cargo:rustc-link-search=native=/opt/ldc/lib",
+                "cargo:rustc-link-lib=static=phobos2-ldc",
+                "cargo:rustc-link-lib=static=druntime-ldc",
+                "cargo:rerun-if-changed=/work/dlib/source/dlib/package.d",
+                "cargo:rerun-if-changed=/home/u/.dub/packages/mir-core/1.7.1/mir-core/source/mir/utility.d",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_runtime_libs() {
+        assert_eq!(
+            Runtime::detect("gdc", Some("/nonexistent/gdc")).libs,
+            vec!["gphobos", "gdruntime"]
+        );
+        assert_eq!(
+            Runtime::detect("dmd", Some("/nonexistent/dmd")),
+            Runtime {
+                libs: vec!["phobos2".into()],
+                search_paths: Vec::new(),
+            }
+        );
+    }
+}
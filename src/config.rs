@@ -0,0 +1,214 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Defaults read from `[package.metadata.dub]` and `[workspace.metadata.dub]`.
+//!
+//! ```toml
+//! [package.metadata.dub]
+//! path = "dlib"
+//! compiler = "ldc2"
+//! build-type = "release"
+//! d-versions = ["UseRust"]
+//! ```
+
+use serde::Deserialize;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, std::io::Error),
+    Toml(PathBuf, toml::de::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Failed to read '{}': {e}", path.display()),
+            Error::Toml(path, e) => write!(f, "Invalid '{}': {e}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// The `metadata.dub` table.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct DubConfig {
+    pub compiler: Option<String>,
+    #[serde(alias = "build")]
+    pub build_type: Option<String>,
+    pub config: Option<String>,
+    pub arch: Option<String>,
+    pub d_versions: Vec<String>,
+    pub debug: Vec<String>,
+    /// Registry URL passed as `--registry`, e.g. a local mirror.
+    pub registry: Option<String>,
+    /// Directory of the D package, relative to the Cargo.toml declaring it.
+    pub path: Option<PathBuf>,
+}
+
+impl DubConfig {
+    /// Fills unset fields from `fallback`.
+    fn or(self, fallback: DubConfig) -> DubConfig {
+        DubConfig {
+            compiler: self.compiler.or(fallback.compiler),
+            build_type: self.build_type.or(fallback.build_type),
+            config: self.config.or(fallback.config),
+            arch: self.arch.or(fallback.arch),
+            d_versions: non_empty_or(self.d_versions, fallback.d_versions),
+            debug: non_empty_or(self.debug, fallback.debug),
+            registry: self.registry.or(fallback.registry),
+            path: self.path.or(fallback.path),
+        }
+    }
+
+    /// Makes `path` absolute against the directory of its Cargo.toml.
+    fn rebase(mut self, dir: &Path) -> DubConfig {
+        self.path = self.path.map(|path| dir.join(path));
+        self
+    }
+}
+
+fn non_empty_or(values: Vec<String>, fallback: Vec<String>) -> Vec<String> {
+    if values.is_empty() {
+        fallback
+    } else {
+        values
+    }
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct CargoToml {
+    package: Option<Section>,
+    workspace: Option<Section>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Section {
+    metadata: Option<Metadata>,
+}
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct Metadata {
+    dub: Option<DubConfig>,
+}
+
+impl Section {
+    fn dub(self) -> Option<DubConfig> {
+        self.metadata.and_then(|m| m.dub)
+    }
+}
+
+fn read(path: &Path) -> Result<CargoToml> {
+    let text = std::fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    toml::from_str(&text).map_err(|e| Error::Toml(path.to_path_buf(), e))
+}
+
+/// Loads the defaults for `start`: the nearest Cargo.toml's package metadata,
+/// falling back to the metadata of the workspace it belongs to.
+pub fn load(start: &Path) -> Result<DubConfig> {
+    let mut manifests = start
+        .ancestors()
+        .map(|dir| dir.join("Cargo.toml"))
+        .filter(|path| path.is_file());
+    let Some(nearest) = manifests.next() else {
+        return Ok(DubConfig::default());
+    };
+    let nearest_dir = nearest.parent().unwrap_or(Path::new("."));
+    let cargo = read(&nearest)?;
+    let package = cargo
+        .package
+        .and_then(Section::dub)
+        .map(|c| c.rebase(nearest_dir));
+
+    let workspace = match cargo.workspace {
+        Some(workspace) => workspace.dub().map(|c| c.rebase(nearest_dir)),
+        None => {
+            let mut found = None;
+            for path in manifests {
+                if let Some(workspace) = read(&path)?.workspace {
+                    let dir = path.parent().unwrap_or(Path::new("."));
+                    found = workspace.dub().map(|c| c.rebase(dir));
+                    break;
+                }
+            }
+            found
+        }
+    };
+
+    Ok(package
+        .unwrap_or_default()
+        .or(workspace.unwrap_or_default()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_load_package_and_workspace() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path();
+        fs::write(
+            root.join("Cargo.toml"),
+            r#"[workspace]
+members = ["app"]
+
+[workspace.metadata.dub]
+compiler = "ldc2"
+build-type = "release"
+d-versions = ["Shared"]
+"#,
+        )
+        .unwrap();
+        fs::create_dir_all(root.join("app/src")).unwrap();
+        fs::write(
+            root.join("app/Cargo.toml"),
+            r#"[package]
+name = "app"
+
+[package.metadata.dub]
+path = "dlib"
+build = "debug"
+"#,
+        )
+        .unwrap();
+
+        let config = load(&root.join("app/src")).unwrap();
+        assert_eq!(
+            config,
+            DubConfig {
+                compiler: Some("ldc2".into()),
+                build_type: Some("debug".into()),
+                d_versions: vec!["Shared".into()],
+                path: Some(root.join("app/dlib")),
+                ..Default::default()
+            }
+        );
+        assert_eq!(load(root).unwrap().build_type.as_deref(), Some("release"));
+    }
+
+    #[test]
+    fn test_load_without_metadata() {
+        let temp = TempDir::new().unwrap();
+        assert_eq!(load(temp.path()).unwrap(), DubConfig::default());
+        fs::write(
+            temp.path().join("Cargo.toml"),
+            "[package]\nname = \"x\"\n[package.metadata.dub]\ncompilr = \"dmd\"\n",
+        )
+        .unwrap();
+        assert!(load(temp.path())
+            .unwrap_err()
+            .to_string()
+            .contains("unknown field `compilr`"));
+    }
+}
@@ -0,0 +1,611 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Native dub.json <-> dub.sdl conversion.
+//!
+//! Conversion works on the raw documents rather than the typed [`Manifest`] so
+//! that key order and comments survive. SDL comments become `"//"` keys in
+//! JSON: `"//name"` for a comment above the tag producing `name`, a plain
+//! `"//"` for comments heading a configuration, build type or inline sub
+//! package, and `"//end"` for comments after the last tag of a block or the
+//! recipe. A comment at the end of a tag's line goes in `"//name//"` right
+//! after the key the tag produces, or in `"//{"` inside the object of a
+//! block opened on that line. Converting back turns them into end-of-line
+//! comments again and every other `"//"`-prefixed key into `//` lines.
+//!
+//! Settings the typed model has no field for, such as `environments`, use
+//! the mapping of [`manifest::setting_from_sdl`] and
+//! [`manifest::setting_to_sdl`].
+
+use crate::manifest::{
+    self, as_array, as_object, json_value_to_sdl, sdl_value_to_json, split_platform, string_tag,
+    Format, Manifest, LIST_SETTINGS, STRING_SETTINGS,
+};
+use crate::sdl::{self, Tag};
+use serde_json::{Map, Value};
+
+/// JSON key holding the comments after the last tag of a block.
+const TRAILING_COMMENT: &str = "//end";
+
+/// JSON key holding the comment on the line opening a block.
+const OPENING_COMMENT: &str = "//{";
+
+/// The key holding the end-of-line comment of the tag producing `key`.
+fn inline_key(key: &str) -> String {
+    format!("//{key}//")
+}
+
+fn is_inline_key(key: &str) -> bool {
+    key.len() > 4 && key.starts_with("//") && key.ends_with("//")
+}
+
+/// Converts recipe text from one format to the other.
+pub fn convert(text: &str, from: Format, to: Format) -> manifest::Result<String> {
+    match (from, to) {
+        (Format::Sdl, Format::Json) => Ok(to_json_string(&sdl_to_json(&sdl::parse(text)?)?)),
+        (Format::Json, Format::Sdl) => {
+            Ok(sdl::to_string(&json_to_sdl(&serde_json::from_str(text)?)?))
+        }
+        _ => Ok(text.to_string()),
+    }
+}
+
+/// Reports whether two recipes describe the same package, ignoring comments and layout.
+pub fn equivalent(a: &str, a_format: Format, b: &str, b_format: Format) -> manifest::Result<bool> {
+    Ok(Manifest::parse(a, a_format)? == Manifest::parse(b, b_format)?)
+}
+
+/// Pretty-prints JSON with tab indentation, the way DUB writes recipes.
+pub fn to_json_string(value: &Value) -> String {
+    use serde::Serialize;
+    let mut out = Vec::new();
+    let formatter = serde_json::ser::PrettyFormatter::with_indent(b"\t");
+    let mut serializer = serde_json::Serializer::with_formatter(&mut out, formatter);
+    value
+        .serialize(&mut serializer)
+        .expect("serializing a JSON value cannot fail");
+    let mut text = String::from_utf8(out).expect("serde_json emits UTF-8");
+    text.push('\n');
+    text
+}
+
+/// Converts an SDL document to a JSON recipe.
+pub fn sdl_to_json(doc: &sdl::Document) -> manifest::Result<Value> {
+    Ok(Value::Object(tags_to_object(
+        &doc.tags,
+        &doc.trailing_comments,
+        Map::new(),
+    )?))
+}
+
+fn insert_comment(object: &mut Map<String, Value>, key: String, comments: &[String]) {
+    if comments.is_empty() {
+        return;
+    }
+    let text = comments.join("\n");
+    match object.get_mut(&key) {
+        Some(Value::String(existing)) => {
+            existing.push('\n');
+            existing.push_str(&text);
+        }
+        _ => {
+            object.insert(key, Value::String(text));
+        }
+    }
+}
+
+fn first_string(tag: &Tag) -> String {
+    tag.values
+        .first()
+        .and_then(sdl::Value::as_str)
+        .unwrap_or_default()
+        .to_string()
+}
+
+/// Object with leading `"//"` and `"//{"` keys holding the tag's own
+/// comments.
+fn element_object(tag: &Tag) -> Map<String, Value> {
+    let mut object = Map::new();
+    insert_comment(&mut object, "//".into(), &tag.comments);
+    insert_comment(
+        &mut object,
+        OPENING_COMMENT.into(),
+        tag.inline_comment.as_slice(),
+    );
+    object
+}
+
+fn tags_to_object(
+    tags: &[Tag],
+    trailing: &[String],
+    mut object: Map<String, Value>,
+) -> manifest::Result<Map<String, Value>> {
+    for tag in tags {
+        let platform = tag.attribute("platform").and_then(sdl::Value::as_str);
+        match tag.name.as_str() {
+            "dependency" if platform.is_none() => {
+                let name = first_string(tag);
+                let deps = object
+                    .entry("dependencies")
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if let Value::Object(deps) = deps {
+                    insert_comment(deps, format!("//{name}"), &tag.comments);
+                    let spec = match tag.attributes.as_slice() {
+                        [(key, sdl::Value::String(version))] if key == "version" => {
+                            Value::String(version.clone())
+                        }
+                        attrs => Value::Object(
+                            attrs
+                                .iter()
+                                .map(|(k, v)| (k.clone(), sdl_value_to_json(v)))
+                                .collect(),
+                        ),
+                    };
+                    let inline = inline_key(&name);
+                    deps.insert(name, spec);
+                    insert_comment(deps, inline, tag.inline_comment.as_slice());
+                }
+            }
+            "configuration" => {
+                let mut config = element_object(tag);
+                config.insert("name".into(), Value::String(first_string(tag)));
+                let config = tags_to_object(&tag.children, &tag.trailing_comments, config)?;
+                push_array(&mut object, "configurations", Value::Object(config));
+            }
+            "buildType" => {
+                let build_type =
+                    tags_to_object(&tag.children, &tag.trailing_comments, element_object(tag))?;
+                let types = object
+                    .entry("buildTypes")
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if let Value::Object(types) = types {
+                    types.insert(first_string(tag), Value::Object(build_type));
+                }
+            }
+            "subPackage" if tag.children.is_empty() => {
+                insert_comment(&mut object, "//subPackages".into(), &tag.comments);
+                push_array(&mut object, "subPackages", Value::String(first_string(tag)));
+                let inline = inline_key("subPackages");
+                insert_comment(&mut object, inline, tag.inline_comment.as_slice());
+            }
+            "subPackage" => {
+                let sub =
+                    tags_to_object(&tag.children, &tag.trailing_comments, element_object(tag))?;
+                push_array(&mut object, "subPackages", Value::Object(sub));
+            }
+            "subConfiguration" => {
+                insert_comment(&mut object, "//subConfigurations".into(), &tag.comments);
+                let subs = object
+                    .entry("subConfigurations")
+                    .or_insert_with(|| Value::Object(Map::new()));
+                if let (Value::Object(subs), [dep, config]) = (subs, tag.values.as_slice()) {
+                    let dep = dep.as_str().unwrap_or_default();
+                    subs.insert(dep.to_string(), sdl_value_to_json(config));
+                    insert_comment(subs, inline_key(dep), tag.inline_comment.as_slice());
+                }
+            }
+            "toolchainRequirements" => {
+                insert_comment(&mut object, "//toolchainRequirements".into(), &tag.comments);
+                object.insert(
+                    "toolchainRequirements".into(),
+                    Value::Object(
+                        tag.attributes
+                            .iter()
+                            .map(|(k, v)| (k.clone(), sdl_value_to_json(v)))
+                            .collect(),
+                    ),
+                );
+                let inline = inline_key("toolchainRequirements");
+                insert_comment(&mut object, inline, tag.inline_comment.as_slice());
+            }
+            name if name == "authors" || name == "platforms" || LIST_SETTINGS.contains(&name) => {
+                let key = match platform {
+                    Some(platform) => format!("{name}-{platform}"),
+                    None => name.to_string(),
+                };
+                insert_comment(&mut object, format!("//{key}"), &tag.comments);
+                for value in &tag.values {
+                    push_array(&mut object, &key, sdl_value_to_json(value));
+                }
+                object
+                    .entry(key.clone())
+                    .or_insert_with(|| Value::Array(Vec::new()));
+                insert_comment(&mut object, inline_key(&key), tag.inline_comment.as_slice());
+            }
+            name => {
+                let base = if name == "dependency" {
+                    "dependencies"
+                } else {
+                    name
+                };
+                let key = match platform {
+                    Some(platform) => format!("{base}-{platform}"),
+                    None => base.to_string(),
+                };
+                insert_comment(&mut object, format!("//{key}"), &tag.comments);
+                if tag.children.is_empty() {
+                    manifest::setting_from_sdl(tag, &mut object)?;
+                    insert_comment(&mut object, inline_key(&key), tag.inline_comment.as_slice());
+                } else {
+                    // Blocks keep the comments inside them.
+                    let mut opening = Map::new();
+                    insert_comment(
+                        &mut opening,
+                        OPENING_COMMENT.into(),
+                        tag.inline_comment.as_slice(),
+                    );
+                    let value = tags_to_object(&tag.children, &tag.trailing_comments, opening)?;
+                    object.insert(key, Value::Object(value));
+                }
+            }
+        }
+    }
+    insert_comment(&mut object, TRAILING_COMMENT.into(), trailing);
+    Ok(object)
+}
+
+fn push_array(object: &mut Map<String, Value>, key: &str, value: Value) {
+    match object
+        .entry(key)
+        .or_insert_with(|| Value::Array(Vec::new()))
+    {
+        Value::Array(items) => items.push(value),
+        other => *other = Value::Array(vec![value]),
+    }
+}
+
+/// Converts a JSON recipe to an SDL document.
+pub fn json_to_sdl(value: &Value) -> manifest::Result<sdl::Document> {
+    let object = value
+        .as_object()
+        .ok_or_else(|| manifest::Error::Invalid("recipe must be a JSON object".into()))?;
+    let (tags, trailing_comments) = object_to_tags(object.iter())?;
+    Ok(sdl::Document {
+        tags,
+        trailing_comments,
+    })
+}
+
+fn comment_lines(value: &Value) -> Vec<String> {
+    match value {
+        Value::String(s) => s.lines().map(str::to_string).collect(),
+        Value::Array(items) => items.iter().flat_map(comment_lines).collect(),
+        other => vec![other.to_string()],
+    }
+}
+
+/// Builds a block tag from an element object, lifting its leading `"//"` keys
+/// into the tag's own comments.
+fn element_tag<'a>(
+    mut tag: Tag,
+    mut entries: impl Iterator<Item = (&'a String, &'a Value)> + Clone,
+    skip_name: bool,
+) -> manifest::Result<Tag> {
+    while let Some((key, value)) = entries.clone().next() {
+        match key.as_str() {
+            "//" => tag.comments.extend(comment_lines(value)),
+            OPENING_COMMENT => tag.inline_comment = Some(comment_lines(value).join(" ")),
+            _ => break,
+        }
+        entries.next();
+    }
+    let rest = entries.filter(|(key, _)| !(skip_name && key.as_str() == "name"));
+    let (children, trailing) = object_to_tags(rest)?;
+    tag.children = children;
+    tag.trailing_comments = trailing;
+    Ok(tag)
+}
+
+fn object_to_tags<'a>(
+    entries: impl Iterator<Item = (&'a String, &'a Value)>,
+) -> manifest::Result<(Vec<Tag>, Vec<String>)> {
+    let mut tags: Vec<Tag> = Vec::new();
+    let mut pending = Vec::new();
+    let emit = |tags: &mut Vec<Tag>, mut tag: Tag, pending: &mut Vec<String>| {
+        let mut comments = std::mem::take(pending);
+        comments.append(&mut tag.comments);
+        tag.comments = comments;
+        tags.push(tag);
+    };
+    // `"//name//"` keys follow the entry whose last tag they end.
+    let comment =
+        |tags: &mut Vec<Tag>, key: &str, value: &Value, pending: &mut Vec<String>| match tags
+            .last_mut()
+        {
+            Some(tag) if is_inline_key(key) => {
+                tag.inline_comment = Some(comment_lines(value).join(" "))
+            }
+            _ => pending.extend(comment_lines(value)),
+        };
+
+    for (key, value) in entries {
+        if key.starts_with("//") {
+            comment(&mut tags, key, value, &mut pending);
+            continue;
+        }
+        match key.as_str() {
+            "dependencies" => {
+                for (name, spec) in as_object(value, key)? {
+                    if name.starts_with("//") {
+                        comment(&mut tags, name, spec, &mut pending);
+                        continue;
+                    }
+                    let mut tag = string_tag("dependency", name);
+                    match spec {
+                        Value::Object(attrs) => {
+                            for (attr, value) in attrs {
+                                tag.attributes
+                                    .push((attr.clone(), json_value_to_sdl(value, key)?));
+                            }
+                        }
+                        version => tag
+                            .attributes
+                            .push(("version".into(), json_value_to_sdl(version, key)?)),
+                    }
+                    emit(&mut tags, tag, &mut pending);
+                }
+            }
+            "configurations" => {
+                for config in as_array(value, key)? {
+                    let object = as_object(config, key)?;
+                    let name = object
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default();
+                    let tag = element_tag(string_tag("configuration", name), object.iter(), true)?;
+                    emit(&mut tags, tag, &mut pending);
+                }
+            }
+            "buildTypes" => {
+                for (name, settings) in as_object(value, key)? {
+                    if name.starts_with("//") {
+                        comment(&mut tags, name, settings, &mut pending);
+                        continue;
+                    }
+                    let object = as_object(settings, name)?;
+                    let tag = element_tag(string_tag("buildType", name), object.iter(), false)?;
+                    emit(&mut tags, tag, &mut pending);
+                }
+            }
+            "subPackages" => {
+                for sub in as_array(value, key)? {
+                    let tag = match sub {
+                        Value::String(path) => string_tag("subPackage", path),
+                        Value::Object(object) => {
+                            element_tag(Tag::new("subPackage"), object.iter(), false)?
+                        }
+                        _ => {
+                            return Err(manifest::Error::Invalid(
+                                "subPackages entries must be paths or objects".into(),
+                            ))
+                        }
+                    };
+                    emit(&mut tags, tag, &mut pending);
+                }
+            }
+            "subConfigurations" => {
+                for (dep, config) in as_object(value, key)? {
+                    if dep.starts_with("//") {
+                        comment(&mut tags, dep, config, &mut pending);
+                        continue;
+                    }
+                    let mut tag = string_tag("subConfiguration", dep);
+                    tag.values.push(json_value_to_sdl(config, key)?);
+                    emit(&mut tags, tag, &mut pending);
+                }
+            }
+            "toolchainRequirements" => {
+                let mut tag = Tag::new("toolchainRequirements");
+                for (name, requirement) in as_object(value, key)? {
+                    tag.attributes
+                        .push((name.clone(), json_value_to_sdl(requirement, key)?));
+                }
+                emit(&mut tags, tag, &mut pending);
+            }
+            _ => {
+                let (base, platform) = split_platform(key);
+                let mapped =
+                    manifest::ENVIRONMENT_SETTINGS.contains(&base) || base == "dependencies";
+                if let (Value::Object(object), false) = (value, mapped) {
+                    // Blocks keep the comments inside them.
+                    let tag = element_tag(Tag::new(key.as_str()), object.iter(), false)?;
+                    emit(&mut tags, tag, &mut pending);
+                    continue;
+                }
+                if !LIST_SETTINGS.contains(&base) && !STRING_SETTINGS.contains(&base) {
+                    let mut settings = manifest::setting_to_sdl(key, value)?.into_iter();
+                    if let Some(first) = settings.next() {
+                        emit(&mut tags, first, &mut pending);
+                    }
+                    for tag in settings {
+                        emit(&mut tags, tag, &mut Vec::new());
+                    }
+                    continue;
+                }
+                let mut tag = Tag::new(base);
+                match value {
+                    Value::Array(items) => {
+                        for item in items {
+                            tag.values.push(json_value_to_sdl(item, key)?);
+                        }
+                    }
+                    scalar => tag.values.push(json_value_to_sdl(scalar, key)?),
+                }
+                if let Some(platform) = platform {
+                    tag.attributes
+                        .push(("platform".into(), sdl::Value::String(platform.into())));
+                }
+                emit(&mut tags, tag, &mut pending);
+            }
+        }
+    }
+    Ok((tags, pending))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SDL: &str = r#"// The application
+name "app"
+authors "Jane Doe" "John Doe"
+// web framework
+dependency "vibe-d" version="~>0.9.5"
+dependency "local" path="../local" optional=true
+dflags "-mcpu=native" platform="ldc"
+// compiler for C sources
+preBuildEnvironments "CC" "clang"
+preBuildEnvironments "CFLAGS" "-O2"
+x:ddoxTool "scod"
+// test setup
+configuration "unittest" {
+	targetType "executable"
+	// extra versions
+	versions "Testing"
+	// keep last
+}
+buildType "profile-gc" {
+	buildOptions "debugInfo"
+}
+subConfiguration "vibe-d" "core"
+// done
+"#;
+
+    #[test]
+    fn test_sdl_to_json_keeps_order_and_comments() {
+        let json = convert(SDL, Format::Sdl, Format::Json).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let keys: Vec<&str> = value
+            .as_object()
+            .unwrap()
+            .keys()
+            .map(|k| k.as_str())
+            .collect();
+        assert_eq!(
+            keys,
+            vec![
+                "//name",
+                "name",
+                "authors",
+                "dependencies",
+                "dflags-ldc",
+                "//preBuildEnvironments",
+                "preBuildEnvironments",
+                "x:ddoxTool",
+                "configurations",
+                "buildTypes",
+                "subConfigurations",
+                "//end"
+            ]
+        );
+        assert_eq!(
+            value["preBuildEnvironments"],
+            serde_json::json!({"CC": "clang", "CFLAGS": "-O2"})
+        );
+        assert_eq!(value["dependencies"]["//vibe-d"], "web framework");
+        assert_eq!(value["dependencies"]["vibe-d"], "~>0.9.5");
+        assert_eq!(value["dependencies"]["local"]["optional"], true);
+        assert_eq!(value["configurations"][0]["//"], "test setup");
+        assert_eq!(value["configurations"][0]["//versions"], "extra versions");
+        assert_eq!(value["configurations"][0]["//end"], "keep last");
+        assert!(json.contains("\n\t\"name\": \"app\""));
+    }
+
+    #[test]
+    fn test_round_trip_sdl() {
+        let json = convert(SDL, Format::Sdl, Format::Json).unwrap();
+        let sdl = convert(&json, Format::Json, Format::Sdl).unwrap();
+        assert_eq!(sdl, SDL);
+    }
+
+    #[test]
+    fn test_json_to_sdl() {
+        let json = r#"{
+    "name": "app",
+    "//": "global note",
+    "sourcePaths": ["source"],
+    "libs-posix": ["ssl", "crypto"],
+    "subPackages": ["./sub", {"name": "inline"}]
+}"#;
+        let sdl = convert(json, Format::Json, Format::Sdl).unwrap();
+        assert_eq!(
+            sdl,
+            "name \"app\"\n// global note\nsourcePaths \"source\"\nlibs \"ssl\" \"crypto\" platform=\"posix\"\nsubPackage \"./sub\"\nsubPackage {\n\tname \"inline\"\n}\n"
+        );
+        assert!(equivalent(json, Format::Json, &sdl, Format::Sdl).unwrap());
+    }
+
+    #[test]
+    fn test_round_trip_block_comments() {
+        let sdl = "name \"app\"\n// heading\nconfiguration \"ci\" {\n\t// before\n\tversions \"CI\"\n\t// after\n}\n";
+        let json = convert(sdl, Format::Sdl, Format::Json).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        let config = value["configurations"][0].as_object().unwrap();
+        assert_eq!(
+            config.iter().collect::<Vec<_>>(),
+            [
+                (&"//".to_string(), &Value::from("heading")),
+                (&"name".to_string(), &Value::from("ci")),
+                (&"//versions".to_string(), &Value::from("before")),
+                (&"versions".to_string(), &serde_json::json!(["CI"])),
+                (&"//end".to_string(), &Value::from("after")),
+            ]
+        );
+        assert_eq!(convert(&json, Format::Json, Format::Sdl).unwrap(), sdl);
+    }
+
+    #[test]
+    fn test_round_trip_inline_comments() {
+        let sdl = "name \"app\" // the name\n\
+                   dependency \"vibe-d\" version=\"~>0.9.5\" // web\n\
+                   dflags \"-O\" // note\n\
+                   x:ddoxTool \"scod\" // docs\n\
+                   configuration \"ci\" { // continuous integration\n\
+                   \tversions \"CI\" // ci only\n\
+                   }\n";
+        let json = convert(sdl, Format::Sdl, Format::Json).unwrap();
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["//dflags//"], "note");
+        assert_eq!(value["dependencies"]["//vibe-d//"], "web");
+        assert_eq!(value["configurations"][0]["//{"], "continuous integration");
+        assert!(value.get(TRAILING_COMMENT).is_none());
+        assert_eq!(convert(&json, Format::Json, Format::Sdl).unwrap(), sdl);
+        assert!(equivalent(sdl, Format::Sdl, &json, Format::Json).unwrap());
+    }
+
+    #[test]
+    fn test_json_environments_to_sdl() {
+        let json = r#"{
+    "name": "app",
+    "environments-posix": {"LANG": "C", "TZ": "UTC"},
+    "-ddoxFilterArgs": ["--min-protection=Public"],
+    "dependencies-windows": {"winapi": "~>1.0"}
+}"#;
+        let sdl = convert(json, Format::Json, Format::Sdl).unwrap();
+        assert_eq!(
+            sdl,
+            "name \"app\"\n\
+             environments \"LANG\" \"C\" platform=\"posix\"\n\
+             environments \"TZ\" \"UTC\" platform=\"posix\"\n\
+             x:ddoxFilterArgs \"--min-protection=Public\"\n\
+             dependency \"winapi\" version=\"~>1.0\" platform=\"windows\"\n"
+        );
+        assert!(equivalent(json, Format::Json, &sdl, Format::Sdl).unwrap());
+
+        let nested = r#"{"name": "app", "environments": {"PATH": ["a", "b"]}}"#;
+        assert_eq!(
+            convert(nested, Format::Json, Format::Sdl)
+                .unwrap_err()
+                .to_string(),
+            "'environments': variable 'PATH' must be a string"
+        );
+    }
+
+    #[test]
+    fn test_equivalent_detects_differences() {
+        let json = convert(SDL, Format::Sdl, Format::Json).unwrap();
+        assert!(equivalent(SDL, Format::Sdl, &json, Format::Json).unwrap());
+        let changed = SDL.replace("~>0.9.5", "~>0.9.6");
+        assert!(!equivalent(&changed, Format::Sdl, &json, Format::Json).unwrap());
+    }
+}
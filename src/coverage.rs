@@ -0,0 +1,379 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Line coverage from the `.lst` files D compilers write for `-cov` builds,
+//! exported as lcov tracefiles, Cobertura XML or a terminal summary.
+
+use crate::report::escape;
+use crate::unittest::module_of;
+use std::collections::BTreeMap;
+use std::fmt::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fs, io};
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    /// A `.lst` file without the `<file> is N% covered` trailer.
+    Malformed(PathBuf),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Failed to read '{}': {e}", path.display()),
+            Error::Malformed(path) => {
+                write!(f, "'{}' is not a coverage listing", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CoverageFormat {
+    Lcov,
+    Cobertura,
+}
+
+/// A coverage `--report` argument: `FORMAT` (written to stdout) or `FORMAT=FILE`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CoverageReport {
+    pub format: CoverageFormat,
+    pub path: Option<PathBuf>,
+}
+
+impl FromStr for CoverageReport {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, String> {
+        let (format, path) = match s.split_once('=') {
+            Some((format, path)) => (format, Some(PathBuf::from(path))),
+            None => (s, None),
+        };
+        let format = match format {
+            "lcov" => CoverageFormat::Lcov,
+            "cobertura" => CoverageFormat::Cobertura,
+            _ => {
+                return Err(format!(
+                    "unknown coverage format '{format}' (expected lcov or cobertura)"
+                ))
+            }
+        };
+        Ok(Self { format, path })
+    }
+}
+
+/// Hit counts of the executable lines of one source file.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FileCoverage {
+    pub lines: BTreeMap<u32, u64>,
+}
+
+impl FileCoverage {
+    pub fn valid(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn covered(&self) -> usize {
+        self.lines.values().filter(|&&hits| hits > 0).count()
+    }
+
+    fn merge(&mut self, other: FileCoverage) {
+        for (line, hits) in other.lines {
+            *self.lines.entry(line).or_default() += hits;
+        }
+    }
+}
+
+/// Coverage of a package, keyed by source path as the compiler saw it.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Coverage {
+    pub files: BTreeMap<String, FileCoverage>,
+}
+
+/// Parses one listing into the source path it covers and its line hits.
+///
+/// Each line is `<count>|<source>`: a blank count marks a line without
+/// code and `0000000` one that never ran.
+pub fn parse_lst(text: &str) -> Option<(String, FileCoverage)> {
+    let mut coverage = FileCoverage::default();
+    let mut source = None;
+    for (index, line) in text.lines().enumerate() {
+        match line.split_once('|') {
+            Some((count, _)) if source.is_none() => {
+                let count = count.trim();
+                if !count.is_empty() {
+                    coverage.lines.insert(index as u32 + 1, count.parse().ok()?);
+                }
+            }
+            _ if line.trim().is_empty() => {}
+            _ => {
+                let path = line
+                    .rsplit_once(" is ")
+                    .or_else(|| line.rsplit_once(" has no code"))?
+                    .0;
+                source = Some(path.to_string());
+            }
+        }
+    }
+    Some((source?, coverage))
+}
+
+impl Coverage {
+    /// Reads the listings in `dir`, merging the ones covering the same
+    /// file. Sources outside the package, given as absolute or `..` paths
+    /// such as those of dependencies, are left out.
+    pub fn collect(dir: &Path) -> Result<Self> {
+        let mut coverage = Self::default();
+        for path in listings(dir)? {
+            let text = fs::read_to_string(&path).map_err(|e| Error::Io(path.clone(), e))?;
+            let (source, file) = parse_lst(&text).ok_or(Error::Malformed(path))?;
+            if Path::new(&source).is_absolute() || source.starts_with("..") {
+                continue;
+            }
+            coverage.add(source, file);
+        }
+        Ok(coverage)
+    }
+
+    pub fn add(&mut self, source: String, file: FileCoverage) {
+        self.files.entry(source).or_default().merge(file);
+    }
+
+    pub fn valid(&self) -> usize {
+        self.files.values().map(FileCoverage::valid).sum()
+    }
+
+    pub fn covered(&self) -> usize {
+        self.files.values().map(FileCoverage::covered).sum()
+    }
+
+    /// Percentage of executable lines that ran; 100 when there are none.
+    pub fn percent(&self) -> f64 {
+        percent(self.covered(), self.valid())
+    }
+
+    /// Per-file table with a total, like `coverage report`.
+    pub fn summary(&self) -> String {
+        let width = self
+            .files
+            .keys()
+            .map(String::len)
+            .chain([8])
+            .max()
+            .unwrap_or_default();
+        let mut out = format!(
+            "{:<width$}  {:>6}  {:>6}  {:>7}\n",
+            "Filename", "Lines", "Missed", "Cover"
+        );
+        let mut row = |name: &str, covered: usize, valid: usize| {
+            let _ = writeln!(
+                out,
+                "{name:<width$}  {valid:>6}  {:>6}  {:>6.2}%",
+                valid - covered,
+                percent(covered, valid)
+            );
+        };
+        for (name, file) in &self.files {
+            row(name, file.covered(), file.valid());
+        }
+        row("TOTAL", self.covered(), self.valid());
+        out
+    }
+
+    /// An lcov tracefile for `genhtml` and coverage services.
+    pub fn lcov(&self) -> String {
+        let mut out = String::new();
+        for (name, file) in &self.files {
+            let _ = writeln!(out, "TN:\nSF:{name}");
+            for (line, hits) in &file.lines {
+                let _ = writeln!(out, "DA:{line},{hits}");
+            }
+            let _ = writeln!(
+                out,
+                "LF:{}\nLH:{}\nend_of_record",
+                file.valid(),
+                file.covered()
+            );
+        }
+        out
+    }
+
+    /// Cobertura XML, with one package per source directory and one class
+    /// per module. `base` is the directory the paths are relative to.
+    pub fn cobertura(&self, base: &Path, timestamp: u64) -> String {
+        let mut packages: BTreeMap<&str, Vec<(&String, &FileCoverage)>> = BTreeMap::new();
+        for (name, file) in &self.files {
+            let dir = name.rsplit_once('/').map_or("", |(dir, _)| dir);
+            packages.entry(dir).or_default().push((name, file));
+        }
+
+        let mut out = String::from("<?xml version=\"1.0\" ?>\n");
+        out.push_str(
+            "<!DOCTYPE coverage SYSTEM \"http://cobertura.sourceforge.net/xml/coverage-04.dtd\">\n",
+        );
+        let _ = writeln!(
+            out,
+            "<coverage line-rate=\"{:.4}\" branch-rate=\"0\" lines-covered=\"{}\" \
+             lines-valid=\"{}\" branches-covered=\"0\" branches-valid=\"0\" complexity=\"0\" \
+             version=\"cargo-dub {}\" timestamp=\"{timestamp}\">",
+            self.percent() / 100.0,
+            self.covered(),
+            self.valid(),
+            env!("CARGO_PKG_VERSION")
+        );
+        let _ = writeln!(
+            out,
+            "  <sources>\n    <source>{}</source>\n  </sources>\n  <packages>",
+            escape(&base.display().to_string())
+        );
+        for (dir, files) in packages {
+            let covered = files.iter().map(|(_, f)| f.covered()).sum();
+            let valid = files.iter().map(|(_, f)| f.valid()).sum();
+            let _ = writeln!(
+                out,
+                "    <package name=\"{}\" line-rate=\"{:.4}\" branch-rate=\"0\" complexity=\"0\">\n      <classes>",
+                escape(&dir.replace('/', ".")),
+                percent(covered, valid) / 100.0
+            );
+            for (name, file) in files {
+                let _ = writeln!(
+                    out,
+                    "        <class name=\"{}\" filename=\"{}\" line-rate=\"{:.4}\" branch-rate=\"0\" complexity=\"0\">\n          <methods/>\n          <lines>",
+                    escape(&module_of(name)),
+                    escape(name),
+                    percent(file.covered(), file.valid()) / 100.0
+                );
+                for (line, hits) in &file.lines {
+                    let _ = writeln!(out, "            <line number=\"{line}\" hits=\"{hits}\"/>");
+                }
+                out.push_str("          </lines>\n        </class>\n");
+            }
+            out.push_str("      </classes>\n    </package>\n");
+        }
+        out.push_str("  </packages>\n</coverage>\n");
+        out
+    }
+}
+
+/// The `.lst` files directly in `dir`, sorted.
+pub fn listings(dir: &Path) -> Result<Vec<PathBuf>> {
+    let entries = fs::read_dir(dir).map_err(|e| Error::Io(dir.to_path_buf(), e))?;
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|path| path.is_file() && path.extension().is_some_and(|e| e == "lst"))
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+fn percent(covered: usize, valid: usize) -> f64 {
+    if valid == 0 {
+        100.0
+    } else {
+        covered as f64 * 100.0 / valid as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const MATH_LST: &str = "       |module app.math;
+       |
+       |int add(int a, int b)
+       |{
+      3|    return a + b;
+       |}
+       |int div(int a, int b)
+       |{
+0000000|    return a / b;
+       |}
+source/app/math.d is 50% covered
+";
+
+    #[test]
+    fn test_parse_lst() {
+        let (source, file) = parse_lst(MATH_LST).unwrap();
+        assert_eq!(source, "source/app/math.d");
+        assert_eq!(file.lines, BTreeMap::from([(5, 3), (9, 0)]));
+        assert_eq!((file.covered(), file.valid()), (1, 2));
+
+        let (source, file) = parse_lst("       |module app;\nsource/app.d has no code\n").unwrap();
+        assert_eq!(source, "source/app.d");
+        assert!(file.lines.is_empty());
+        assert_eq!(parse_lst("      3|x;\n"), None);
+    }
+
+    #[test]
+    fn test_collect_merges() {
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("source-app-math.lst"), MATH_LST).unwrap();
+        fs::write(
+            temp.path().join("source-app-math-2.lst"),
+            MATH_LST.replace("0000000|", "      1|"),
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("..-dep-source-dep.lst"),
+            "      1|x;\n../dep/source/dep.d is 100% covered\n",
+        )
+        .unwrap();
+        let coverage = Coverage::collect(temp.path()).unwrap();
+        assert_eq!(coverage.files.len(), 1);
+        assert_eq!(
+            coverage.files["source/app/math.d"].lines,
+            BTreeMap::from([(5, 6), (9, 1)])
+        );
+        assert_eq!(coverage.percent(), 100.0);
+
+        fs::write(temp.path().join("broken.lst"), "      1|x;\n").unwrap();
+        assert!(matches!(
+            Coverage::collect(temp.path()),
+            Err(Error::Malformed(_))
+        ));
+    }
+
+    #[test]
+    fn test_exports() {
+        let mut coverage = Coverage::default();
+        let (source, file) = parse_lst(MATH_LST).unwrap();
+        coverage.add(source, file);
+        assert_eq!(
+            coverage.lcov(),
+            "TN:\nSF:source/app/math.d\nDA:5,3\nDA:9,0\nLF:2\nLH:1\nend_of_record\n"
+        );
+        assert_eq!(
+            coverage.summary(),
+            "Filename            Lines  Missed    Cover\n\
+             source/app/math.d       2       1   50.00%\n\
+             TOTAL                   2       1   50.00%\n"
+        );
+        let xml = coverage.cobertura(Path::new("/work/app"), 1700000000);
+        assert!(xml.contains("lines-covered=\"1\" lines-valid=\"2\""));
+        assert!(xml.contains("<source>/work/app</source>"));
+        assert!(xml.contains("<package name=\"source.app\" line-rate=\"0.5000\""));
+        assert!(xml.contains(
+            "<class name=\"app.math\" filename=\"source/app/math.d\" line-rate=\"0.5000\""
+        ));
+        assert!(xml.contains("<line number=\"9\" hits=\"0\"/>"));
+    }
+
+    #[test]
+    fn test_report_spec() {
+        assert_eq!(
+            "lcov=lcov.info".parse::<CoverageReport>().unwrap(),
+            CoverageReport {
+                format: CoverageFormat::Lcov,
+                path: Some(PathBuf::from("lcov.info")),
+            }
+        );
+        assert!("html".parse::<CoverageReport>().is_err());
+    }
+}
@@ -0,0 +1,455 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Typed view of `dub describe` output.
+
+use serde::de::{self, Deserializer};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Write as _};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Top-level `dub describe` document.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Description {
+    pub root_package: String,
+    pub configuration: Option<String>,
+    pub build_type: Option<String>,
+    pub compiler: Option<String>,
+    pub architecture: Vec<String>,
+    pub platform: Vec<String>,
+    pub packages: Vec<Package>,
+    pub targets: Vec<Target>,
+}
+
+/// A package taking part in the build, as resolved by DUB.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub path: PathBuf,
+    pub active: bool,
+    pub configuration: Option<String>,
+    pub dependencies: Vec<String>,
+    #[serde(deserialize_with = "target_type")]
+    pub target_type: String,
+    pub target_path: String,
+    pub target_name: String,
+    pub target_file_name: String,
+    pub main_source_file: String,
+    pub libs: Vec<String>,
+    pub lflags: Vec<String>,
+    pub import_paths: Vec<String>,
+    pub string_import_paths: Vec<String>,
+    pub files: Vec<File>,
+}
+
+/// A file belonging to a package, tagged with its role (`source`, `import`, ...).
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(default)]
+pub struct File {
+    pub role: String,
+    pub path: String,
+}
+
+/// A binary DUB builds: a root package together with the packages compiled
+/// into it, whose settings are merged into `build_settings`.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct Target {
+    pub root_package: String,
+    pub packages: Vec<String>,
+    pub root_configuration: String,
+    pub build_settings: BuildSettings,
+    pub dependencies: Vec<String>,
+    pub link_dependencies: Vec<String>,
+}
+
+/// Resolved settings of a target, with paths made absolute by DUB.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BuildSettings {
+    #[serde(deserialize_with = "target_type")]
+    pub target_type: String,
+    pub target_path: String,
+    pub target_name: String,
+    pub working_directory: String,
+    pub main_source_file: String,
+    pub dflags: Vec<String>,
+    pub lflags: Vec<String>,
+    pub libs: Vec<String>,
+    pub linker_files: Vec<String>,
+    pub source_files: Vec<String>,
+    pub copy_files: Vec<String>,
+    pub extra_dependency_files: Vec<String>,
+    pub versions: Vec<String>,
+    pub debug_versions: Vec<String>,
+    pub version_filters: Vec<String>,
+    pub debug_version_filters: Vec<String>,
+    pub import_paths: Vec<String>,
+    pub string_import_paths: Vec<String>,
+    pub import_files: Vec<String>,
+    pub string_import_files: Vec<String>,
+    pub pre_generate_commands: Vec<String>,
+    pub post_generate_commands: Vec<String>,
+    pub pre_build_commands: Vec<String>,
+    pub post_build_commands: Vec<String>,
+    pub pre_run_commands: Vec<String>,
+    pub post_run_commands: Vec<String>,
+}
+
+/// DUB's `TargetType` members, in declaration order.
+const TARGET_TYPES: [&str; 8] = [
+    "autodetect",
+    "none",
+    "executable",
+    "library",
+    "sourceLibrary",
+    "dynamicLibrary",
+    "staticLibrary",
+    "object",
+];
+
+/// Reads a target type written by name, as in `packages`, or by its
+/// position in DUB's enum, as in targets' `buildSettings`.
+fn target_type<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Raw {
+        Index(usize),
+        Name(String),
+    }
+    match Raw::deserialize(deserializer)? {
+        Raw::Name(name) => Ok(name),
+        Raw::Index(index) => TARGET_TYPES
+            .get(index)
+            .map(|name| name.to_string())
+            .ok_or_else(|| de::Error::custom(format!("unknown target type {index}"))),
+    }
+}
+
+/// Tabular views of a description, for `cargo dub describe --table`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Table {
+    Packages,
+    Targets,
+    ImportPaths,
+    StringImportPaths,
+    Versions,
+    Libs,
+    SourceFiles,
+}
+
+impl FromStr for Table {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        Ok(match s {
+            "packages" => Table::Packages,
+            "targets" => Table::Targets,
+            "import-paths" => Table::ImportPaths,
+            "string-import-paths" => Table::StringImportPaths,
+            "versions" => Table::Versions,
+            "libs" => Table::Libs,
+            "source-files" => Table::SourceFiles,
+            _ => {
+                return Err(format!(
+                    "unknown table '{s}' (expected packages, targets, import-paths, \
+                     string-import-paths, versions, libs or source-files)"
+                ))
+            }
+        })
+    }
+}
+
+impl fmt::Display for Table {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Table::Packages => "packages",
+            Table::Targets => "targets",
+            Table::ImportPaths => "import-paths",
+            Table::StringImportPaths => "string-import-paths",
+            Table::Versions => "versions",
+            Table::Libs => "libs",
+            Table::SourceFiles => "source-files",
+        })
+    }
+}
+
+impl Description {
+    pub fn from_json(text: &str) -> serde_json::Result<Self> {
+        serde_json::from_str(text)
+    }
+
+    /// Reads a description already parsed as JSON.
+    pub fn from_value(value: &serde_json::Value) -> serde_json::Result<Self> {
+        Self::deserialize(value)
+    }
+
+    pub fn root(&self) -> Option<&Package> {
+        self.package(&self.root_package)
+    }
+
+    pub fn package(&self, name: &str) -> Option<&Package> {
+        self.packages.iter().find(|p| p.name == name)
+    }
+
+    /// The package whose directory contains `file`. Relative paths, as the
+    /// compiler prints them for the root package, belong to the root.
+    pub fn package_for_file(&self, file: impl AsRef<Path>) -> Option<&Package> {
+        let file = file.as_ref();
+        if file.is_relative() {
+            return self.root();
+        }
+        self.packages
+            .iter()
+            .filter(|p| file.starts_with(&p.path))
+            .max_by_key(|p| p.path.as_os_str().len())
+            .or_else(|| self.root())
+    }
+
+    /// Packages that are part of the selected configuration.
+    pub fn active_packages(&self) -> impl Iterator<Item = &Package> {
+        self.packages.iter().filter(|p| p.active)
+    }
+
+    /// The target built for the package `name`.
+    pub fn target(&self, name: &str) -> Option<&Target> {
+        self.targets.iter().find(|t| t.root_package == name)
+    }
+
+    /// Renders `table` with aligned columns. Views of a build setting list
+    /// one row per target and value.
+    pub fn table(&self, table: Table) -> String {
+        match table {
+            Table::Packages => {
+                let rows = self.packages.iter().map(|p| {
+                    [
+                        p.name.clone(),
+                        p.version.clone(),
+                        p.target_type.clone(),
+                        if p.active { "yes" } else { "no" }.to_string(),
+                        p.path.display().to_string(),
+                    ]
+                });
+                render(["Package", "Version", "Type", "Active", "Path"], rows)
+            }
+            Table::Targets => {
+                let rows = self.targets.iter().map(|t| {
+                    [
+                        t.root_package.clone(),
+                        t.build_settings.target_type.clone(),
+                        t.root_configuration.clone(),
+                        t.dependencies.join(", "),
+                    ]
+                });
+                render(["Target", "Type", "Configuration", "Dependencies"], rows)
+            }
+            Table::ImportPaths => self.settings("Import path", |s| &s.import_paths),
+            Table::StringImportPaths => {
+                self.settings("String import path", |s| &s.string_import_paths)
+            }
+            Table::Versions => self.settings("Version", |s| &s.versions),
+            Table::Libs => self.settings("Library", |s| &s.libs),
+            Table::SourceFiles => self.settings("Source file", |s| &s.source_files),
+        }
+    }
+
+    fn settings(&self, column: &str, values: impl Fn(&BuildSettings) -> &Vec<String>) -> String {
+        let rows = self.targets.iter().flat_map(|t| {
+            values(&t.build_settings)
+                .iter()
+                .map(|value| [t.root_package.clone(), value.clone()])
+        });
+        render(["Target", column], rows)
+    }
+}
+
+/// Left-aligned columns separated by two spaces.
+pub(crate) fn render<const N: usize>(
+    header: [&str; N],
+    rows: impl IntoIterator<Item = [String; N]>,
+) -> String {
+    let rows: Vec<[String; N]> = rows.into_iter().collect();
+    let mut widths = header.map(str::len);
+    for row in &rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let mut out = String::new();
+    for row in std::iter::once(header.map(str::to_string)).chain(rows) {
+        let mut line = String::new();
+        for (cell, width) in row.iter().zip(widths) {
+            let _ = write!(line, "{cell:<width$}  ");
+        }
+        let _ = writeln!(out, "{}", line.trim_end());
+    }
+    out
+}
+
+impl Package {
+    /// Full path of the build artifact, if the package produces one.
+    pub fn artifact_path(&self) -> Option<PathBuf> {
+        if self.target_file_name.is_empty() {
+            return None;
+        }
+        Some(
+            self.path
+                .join(&self.target_path)
+                .join(&self.target_file_name),
+        )
+    }
+
+    /// Source, import and string-import files with absolute paths.
+    pub fn input_files(&self) -> impl Iterator<Item = PathBuf> + '_ {
+        self.files
+            .iter()
+            .filter(|f| matches!(f.role.as_str(), "source" | "import" | "stringImport"))
+            .map(|f| self.resolve(&f.path))
+    }
+
+    /// Resolves a path from the description relative to the package directory.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> PathBuf {
+        self.path.join(path)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    pub(crate) const DESCRIBE: &str = r#"{
+        "rootPackage": "dlib",
+        "configuration": "library",
+        "buildType": "release",
+        "compiler": "ldc",
+        "architecture": ["x86_64"],
+        "platform": ["linux", "posix"],
+        "packages": [
+            {
+                "path": "/work/dlib/",
+                "name": "dlib",
+                "version": "~master",
+                "dependencies": ["mir-core"],
+                "active": true,
+                "configuration": "library",
+                "targetType": "staticLibrary",
+                "targetPath": "lib",
+                "targetName": "dlib",
+                "targetFileName": "libdlib.a",
+                "libs": ["z"],
+                "importPaths": ["source/"],
+                "files": [
+                    {"role": "source", "path": "source/dlib/package.d"},
+                    {"role": "unusedSource", "path": "source/dlib/old.d"}
+                ]
+            },
+            {
+                "path": "/home/u/.dub/packages/mir-core/1.7.1/mir-core/",
+                "name": "mir-core",
+                "version": "1.7.1",
+                "active": true,
+                "targetType": "staticLibrary",
+                "files": [{"role": "source", "path": "source/mir/utility.d"}]
+            },
+            {
+                "path": "/home/u/.dub/packages/silly/1.1.1/silly/",
+                "name": "silly",
+                "version": "1.1.1",
+                "active": false,
+                "targetType": "library"
+            }
+        ],
+        "targets": [
+            {
+                "rootPackage": "dlib",
+                "packages": ["dlib", "mir-core"],
+                "rootConfiguration": "library",
+                "buildSettings": {
+                    "targetType": 6,
+                    "targetPath": "/work/dlib/lib",
+                    "targetName": "dlib",
+                    "importPaths": ["/work/dlib/source/", "/home/u/.dub/packages/mir-core/1.7.1/mir-core/source/"],
+                    "versions": ["Have_dlib", "Have_mir_core"],
+                    "libs": ["z"],
+                    "options": 4096
+                },
+                "dependencies": ["mir-core"],
+                "linkDependencies": ["mir-core"]
+            },
+            {
+                "rootPackage": "mir-core",
+                "packages": ["mir-core"],
+                "rootConfiguration": "library",
+                "buildSettings": {"targetType": "staticLibrary", "importPaths": []}
+            }
+        ]
+    }"#;
+
+    #[test]
+    fn test_parse_description() {
+        let desc = Description::from_json(DESCRIBE).unwrap();
+        let root = desc.root().unwrap();
+        assert_eq!(root.dependencies, vec!["mir-core"]);
+        assert_eq!(
+            root.artifact_path(),
+            Some(PathBuf::from("/work/dlib/lib/libdlib.a"))
+        );
+        assert_eq!(
+            root.input_files().collect::<Vec<_>>(),
+            vec![PathBuf::from("/work/dlib/source/dlib/package.d")]
+        );
+        assert_eq!(desc.active_packages().count(), 2);
+        assert_eq!(desc.package("silly").unwrap().artifact_path(), None);
+        assert_eq!(desc.package_for_file("source/app.d").unwrap().name, "dlib");
+        assert_eq!(
+            desc.package_for_file("/home/u/.dub/packages/silly/1.1.1/silly/silly.d")
+                .unwrap()
+                .name,
+            "silly"
+        );
+    }
+
+    #[test]
+    fn test_targets() {
+        let desc = Description::from_json(DESCRIBE).unwrap();
+        let target = desc.target("dlib").unwrap();
+        assert_eq!(target.build_settings.target_type, "staticLibrary");
+        assert_eq!(target.link_dependencies, vec!["mir-core"]);
+        assert_eq!(
+            desc.target("mir-core").unwrap().build_settings.target_type,
+            "staticLibrary"
+        );
+        assert!(
+            Description::from_json(r#"{"targets": [{"buildSettings": {"targetType": 9}}]}"#)
+                .unwrap_err()
+                .to_string()
+                .starts_with("unknown target type 9")
+        );
+    }
+
+    #[test]
+    fn test_tables() {
+        let desc = Description::from_json(DESCRIBE).unwrap();
+        assert_eq!(
+            desc.table(Table::Targets),
+            "Target    Type           Configuration  Dependencies\n\
+             dlib      staticLibrary  library        mir-core\n\
+             mir-core  staticLibrary  library\n"
+        );
+        assert_eq!(
+            desc.table(Table::ImportPaths),
+            "Target  Import path\n\
+             dlib    /work/dlib/source/\n\
+             dlib    /home/u/.dub/packages/mir-core/1.7.1/mir-core/source/\n"
+        );
+        assert!(desc.table(Table::Packages).contains(
+            "silly     1.1.1    library        no      /home/u/.dub/packages/silly/1.1.1/silly/\n"
+        ));
+        assert_eq!("import-paths".parse(), Ok(Table::ImportPaths));
+        assert!("paths".parse::<Table>().is_err());
+    }
+}
@@ -0,0 +1,472 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Parsing of dmd, ldc and gdc diagnostics, and rustc-style rendering.
+
+use serde::Serialize;
+use std::fmt::{self, Write as _};
+use std::fs;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Level {
+    Error,
+    Warning,
+    Deprecation,
+}
+
+impl Level {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Deprecation => "deprecation",
+        }
+    }
+
+    /// Maps the level keyword of either output style (`Error:`, `error:`).
+    fn from_keyword(keyword: &str) -> Option<Level> {
+        match keyword {
+            "Error" | "error" => Some(Level::Error),
+            "Warning" | "warning" => Some(Level::Warning),
+            "Deprecation" | "deprecation" => Some(Level::Deprecation),
+            _ => None,
+        }
+    }
+
+    /// The rustc-style header: deprecations are warnings tagged `[deprecated]`.
+    fn header(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warning => "warning",
+            Level::Deprecation => "warning[deprecated]",
+        }
+    }
+}
+
+impl fmt::Display for Level {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Where a message points, as printed by the compiler.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Location {
+    /// Usually relative to the directory DUB runs in.
+    pub file: String,
+    pub line: u32,
+    pub column: Option<u32>,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.file, self.line)?;
+        if let Some(column) = self.column {
+            write!(f, ":{column}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Supplemental information attached to a diagnostic, such as an
+/// instantiation trace or overload candidates.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Note {
+    pub message: String,
+    pub location: Option<Location>,
+}
+
+/// A compiler message with its location and notes.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Diagnostic {
+    pub level: Level,
+    pub message: String,
+    pub location: Option<Location>,
+    pub notes: Vec<Note>,
+}
+
+/// One line of compiler output, classified.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Line {
+    Diagnostic(Diagnostic),
+    Supplemental(Note),
+    Other,
+}
+
+fn classify(line: &str) -> Line {
+    if let Some(parsed) = parse_dmd(line).or_else(|| parse_gcc(line)) {
+        return parsed;
+    }
+    // Location-less messages, e.g. `Error: linker exited with status 1`.
+    if let Some((keyword, message)) = line.split_once(": ") {
+        if let Some(level) = Level::from_keyword(keyword) {
+            return Line::Diagnostic(Diagnostic {
+                level,
+                message: message.to_string(),
+                location: None,
+                notes: Vec::new(),
+            });
+        }
+    }
+    Line::Other
+}
+
+fn parse_dmd(line: &str) -> Option<Line> {
+    let (location, rest) = line.split_once("): ")?;
+    let (file, position) = location.rsplit_once('(')?;
+    let (line_number, column) = match position.split_once(',') {
+        Some((line, column)) => (line.parse().ok()?, Some(column.parse().ok()?)),
+        None => (position.parse().ok()?, None),
+    };
+    let location = Location {
+        file: file.to_string(),
+        line: line_number,
+        column,
+    };
+    // Supplemental lines repeat the location and indent the message instead
+    // of naming a level.
+    if rest.starts_with(' ') {
+        return Some(Line::Supplemental(Note {
+            message: rest.trim().to_string(),
+            location: Some(location),
+        }));
+    }
+    let (keyword, message) = rest.split_once(": ")?;
+    Some(Line::Diagnostic(Diagnostic {
+        level: Level::from_keyword(keyword)?,
+        message: message.to_string(),
+        location: Some(location),
+        notes: Vec::new(),
+    }))
+}
+
+fn parse_gcc(line: &str) -> Option<Line> {
+    let (location, keyword, message) = ["error", "warning", "deprecation", "note"]
+        .iter()
+        .find_map(|keyword| {
+            let (location, message) = line.split_once(&format!(": {keyword}: "))?;
+            Some((location, *keyword, message))
+        })?;
+    // Windows drive letters contain a colon too, so split from the right.
+    let mut parts = location.rsplitn(3, ':');
+    let last: u32 = parts.next()?.parse().ok()?;
+    let (file, line_number, column) = match (parts.next(), parts.next()) {
+        (Some(line), Some(file)) if line.parse::<u32>().is_ok() => {
+            (file, line.parse().ok()?, Some(last))
+        }
+        _ => (location.rsplit_once(':')?.0, last, None),
+    };
+    let location = Location {
+        file: file.to_string(),
+        line: line_number,
+        column,
+    };
+    if keyword == "note" {
+        return Some(Line::Supplemental(Note {
+            message: message.to_string(),
+            location: Some(location),
+        }));
+    }
+    // gdc reports deprecations as warnings under -Wdeprecated.
+    let (level, message) = match message.strip_suffix(" [-Wdeprecated]") {
+        Some(message) => (Level::Deprecation, message),
+        None => (Level::from_keyword(keyword)?, message),
+    };
+    Some(Line::Diagnostic(Diagnostic {
+        level,
+        message: message.to_string(),
+        location: Some(location),
+        notes: Vec::new(),
+    }))
+}
+
+/// Parses a single line of compiler output.
+///
+/// dmd and ldc print `file(line[,col]): Error: message`; gdc prints
+/// `file:line[:col]: error: message`. Supplemental lines are not
+/// diagnostics on their own; use [`Parser`] to attach them.
+pub fn parse_line(line: &str) -> Option<Diagnostic> {
+    match classify(line) {
+        Line::Diagnostic(diagnostic) => Some(diagnostic),
+        _ => None,
+    }
+}
+
+/// Output of [`Parser`]: a complete diagnostic, or a line that is not one.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Event {
+    Diagnostic(Diagnostic),
+    Other(String),
+}
+
+/// Groups compiler output into diagnostics with their supplemental lines.
+///
+/// A diagnostic is only complete once the next non-supplemental line
+/// arrives, so it is held back until then or until [`Parser::finish`].
+#[derive(Debug, Default)]
+pub struct Parser {
+    pending: Option<Diagnostic>,
+}
+
+impl Parser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feeds one line and returns the events it completes, in order.
+    pub fn push(&mut self, line: &str) -> Vec<Event> {
+        match classify(line) {
+            Line::Supplemental(note) => match &mut self.pending {
+                Some(pending) => {
+                    pending.notes.push(note);
+                    Vec::new()
+                }
+                None => vec![Event::Other(line.to_string())],
+            },
+            Line::Diagnostic(diagnostic) => {
+                let events = self.finish().into_iter().collect();
+                self.pending = Some(diagnostic);
+                events
+            }
+            Line::Other => {
+                let mut events: Vec<Event> = self.finish().into_iter().collect();
+                events.push(Event::Other(line.to_string()));
+                events
+            }
+        }
+    }
+
+    /// Flushes the diagnostic still waiting for supplemental lines.
+    pub fn finish(&mut self) -> Option<Event> {
+        self.pending.take().map(Event::Diagnostic)
+    }
+}
+
+/// Renders a diagnostic the way rustc does, with a source snippet when the
+/// file can be read relative to `base`.
+///
+/// ```text
+/// error: undefined identifier `x`
+///  --> source/app.d:3:5
+///   |
+/// 3 |     x = 1;
+///   |     ^
+/// ```
+pub fn render(diagnostic: &Diagnostic, base: &Path) -> String {
+    let mut out = format!("{}: {}\n", diagnostic.level.header(), diagnostic.message);
+    if let Some(location) = &diagnostic.location {
+        render_location(&mut out, location, base);
+    }
+    for note in &diagnostic.notes {
+        match &note.location {
+            Some(location) => {
+                let _ = writeln!(out, "note: {}", note.message);
+                render_location(&mut out, location, base);
+            }
+            None => {
+                let _ = writeln!(out, "  = note: {}", note.message);
+            }
+        }
+    }
+    out
+}
+
+fn render_location(out: &mut String, location: &Location, base: &Path) {
+    let source = fs::read_to_string(base.join(&location.file)).ok();
+    let text = source.as_deref().and_then(|source| {
+        let index = usize::try_from(location.line).ok()?.checked_sub(1)?;
+        source.lines().nth(index)
+    });
+    let gutter = " ".repeat(location.line.to_string().len());
+    let _ = writeln!(out, "{gutter}--> {location}");
+    let Some(text) = text else {
+        return;
+    };
+    let _ = writeln!(out, "{gutter} |");
+    let _ = writeln!(out, "{} | {text}", location.line);
+    if let Some(column) = location.column {
+        // Keep tabs so the caret lines up with tab-indented source.
+        let indent: String = text
+            .chars()
+            .take(column.saturating_sub(1) as usize)
+            .map(|c| if c == '\t' { '\t' } else { ' ' })
+            .collect();
+        let _ = writeln!(out, "{gutter} | {indent}^");
+    }
+}
+
+/// Error and warning counts, for the closing summary line.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub errors: usize,
+    pub warnings: usize,
+}
+
+impl Counts {
+    pub fn add(&mut self, diagnostic: &Diagnostic) {
+        match diagnostic.level {
+            Level::Error => self.errors += 1,
+            Level::Warning | Level::Deprecation => self.warnings += 1,
+        }
+    }
+
+    /// Cargo's closing line for `package`, if anything was reported.
+    pub fn summary(&self, package: &str) -> Option<String> {
+        let plural = |n: usize, word: &str| format!("{n} {word}{}", if n == 1 { "" } else { "s" });
+        match (self.errors, self.warnings) {
+            (0, 0) => None,
+            (0, warnings) => Some(format!(
+                "warning: `{package}` generated {}",
+                plural(warnings, "warning")
+            )),
+            (errors, warnings) => {
+                let mut line = format!(
+                    "error: could not compile `{package}` due to {}",
+                    plural(errors, "previous error")
+                );
+                if warnings > 0 {
+                    let _ = write!(line, "; {} emitted", plural(warnings, "warning"));
+                }
+                Some(line)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(file: &str, line: u32, column: Option<u32>) -> Option<Location> {
+        Some(Location {
+            file: file.to_string(),
+            line,
+            column,
+        })
+    }
+
+    #[test]
+    fn test_parse_dmd() {
+        assert_eq!(
+            parse_line("source/app.d(12,5): Error: undefined identifier `x`"),
+            Some(Diagnostic {
+                level: Level::Error,
+                message: "undefined identifier `x`".to_string(),
+                location: at("source/app.d", 12, Some(5)),
+                notes: Vec::new(),
+            })
+        );
+        let warning = parse_line(r"C:\src\app.d(3): Warning: statement is not reachable").unwrap();
+        assert_eq!(warning.level, Level::Warning);
+        assert_eq!(warning.location, at(r"C:\src\app.d", 3, None));
+        let linker = parse_line("Error: linker exited with status 1").unwrap();
+        assert_eq!(linker.location, None);
+        assert_eq!(parse_line("Linking app..."), None);
+        assert_eq!(parse_line("f(x): Error: y"), None);
+        assert_eq!(
+            parse_line("source/app.d(4,1):        instantiated from here"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_parse_gdc() {
+        assert_eq!(
+            parse_line("source/app.d:7:10: error: no property `foo` for `s`"),
+            Some(Diagnostic {
+                level: Level::Error,
+                message: "no property `foo` for `s`".to_string(),
+                location: at("source/app.d", 7, Some(10)),
+                notes: Vec::new(),
+            })
+        );
+        let warning = parse_line(r"C:\src\app.d:4: warning: unused").unwrap();
+        assert_eq!(warning.location, at(r"C:\src\app.d", 4, None));
+        let deprecated =
+            parse_line("app.d:2:5: warning: function `f` is deprecated [-Wdeprecated]").unwrap();
+        assert_eq!(deprecated.level, Level::Deprecation);
+        assert_eq!(deprecated.message, "function `f` is deprecated");
+    }
+
+    #[test]
+    fn test_parser_groups_supplemental_lines() {
+        let mut parser = Parser::new();
+        let mut events = Vec::new();
+        for line in [
+            "Compiling app...",
+            "source/app.d(5,8): Error: none of the overloads of `foo` are callable",
+            "source/app.d(1,6):        Candidates are: `app.foo(int x)`",
+            "source/app.d(9,1): Deprecation: `old` is deprecated",
+            "app.d:3:1: note: declared here",
+        ] {
+            events.extend(parser.push(line));
+        }
+        events.extend(parser.finish());
+        assert_eq!(events.len(), 3);
+        assert_eq!(events[0], Event::Other("Compiling app...".to_string()));
+        let Event::Diagnostic(error) = &events[1] else {
+            panic!("expected a diagnostic");
+        };
+        assert_eq!(
+            error.notes,
+            vec![Note {
+                message: "Candidates are: `app.foo(int x)`".to_string(),
+                location: at("source/app.d", 1, Some(6)),
+            }]
+        );
+        let Event::Diagnostic(deprecation) = &events[2] else {
+            panic!("expected a diagnostic");
+        };
+        assert_eq!(deprecation.notes.len(), 1);
+        assert_eq!(parser.push("x.d(1,1):   orphan").len(), 1);
+    }
+
+    #[test]
+    fn test_render() {
+        let temp = tempfile::TempDir::new().unwrap();
+        fs::create_dir(temp.path().join("source")).unwrap();
+        fs::write(
+            temp.path().join("source/app.d"),
+            "void main()\n{\n\tint y = x;\n}\n",
+        )
+        .unwrap();
+        let mut diagnostic =
+            parse_line("source/app.d(3,10): Error: undefined identifier `x`").unwrap();
+        diagnostic.notes.push(Note {
+            message: "while compiling `main`".to_string(),
+            location: None,
+        });
+        assert_eq!(
+            render(&diagnostic, temp.path()),
+            "error: undefined identifier `x`\n \
+             --> source/app.d:3:10\n  \
+             |\n\
+             3 | \tint y = x;\n  \
+             | \t        ^\n  \
+             = note: while compiling `main`\n"
+        );
+        let missing = parse_line("gone.d(12): Warning: w").unwrap();
+        assert_eq!(
+            render(&missing, temp.path()),
+            "warning: w\n  --> gone.d:12\n"
+        );
+    }
+
+    #[test]
+    fn test_summary() {
+        let mut counts = Counts::default();
+        assert_eq!(counts.summary("app"), None);
+        counts.warnings = 1;
+        assert_eq!(
+            counts.summary("app").as_deref(),
+            Some("warning: `app` generated 1 warning")
+        );
+        counts.errors = 3;
+        assert_eq!(
+            counts.summary("app").as_deref(),
+            Some("error: could not compile `app` due to 3 previous errors; 1 warning emitted")
+        );
+    }
+}
@@ -0,0 +1,221 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Running DUB and other tools, reporting how they ended as an [`Outcome`]
+//! rather than exiting, so callers decide what a failure means.
+
+use std::fmt;
+use std::io;
+use std::path::Path;
+use std::process::{Child, Command, ExitStatus, Output, Stdio};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub enum Error {
+    /// The program could not be started.
+    Spawn { program: String, source: io::Error },
+    /// Waiting for or reading from a started program failed.
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Spawn { program, source } => match source.kind() {
+                io::ErrorKind::NotFound => {
+                    write!(f, "{program} executable not found or not accessible")
+                }
+                io::ErrorKind::PermissionDenied => {
+                    write!(f, "Permission denied when executing {program}")
+                }
+                io::ErrorKind::WouldBlock => {
+                    f.write_str("System resources temporarily unavailable")
+                }
+                _ => write!(f, "Failed to execute {program}: {source}"),
+            },
+            Error::Io(e) => write!(f, "{e}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// What happens to a command's stdout and stderr.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Capture {
+    /// Shared with the calling process.
+    #[default]
+    Inherit,
+    /// Collected into [`Outcome::stdout`] and [`Outcome::stderr`].
+    Output,
+}
+
+/// How a finished command ended.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Outcome {
+    /// Exit code, absent when the process was killed by a signal.
+    pub code: Option<i32>,
+    /// Signal that terminated the process (Unix only).
+    pub signal: Option<i32>,
+    pub duration: Duration,
+    /// Output, when run with [`Capture::Output`].
+    pub stdout: Option<String>,
+    pub stderr: Option<String>,
+}
+
+impl Outcome {
+    /// The outcome of work done without running anything.
+    pub const SUCCESS: Outcome = Outcome {
+        code: Some(0),
+        signal: None,
+        duration: Duration::ZERO,
+        stdout: None,
+        stderr: None,
+    };
+
+    pub fn from_status(status: ExitStatus, duration: Duration) -> Self {
+        #[cfg(unix)]
+        let signal = std::os::unix::process::ExitStatusExt::signal(&status);
+        #[cfg(not(unix))]
+        let signal = None;
+        Self {
+            code: status.code(),
+            signal,
+            duration,
+            stdout: None,
+            stderr: None,
+        }
+    }
+
+    fn from_output(output: Output, duration: Duration) -> Self {
+        Self {
+            stdout: Some(String::from_utf8_lossy(&output.stdout).into_owned()),
+            stderr: Some(String::from_utf8_lossy(&output.stderr).into_owned()),
+            ..Self::from_status(output.status, duration)
+        }
+    }
+
+    pub fn success(&self) -> bool {
+        self.code == Some(0)
+    }
+
+    /// The code a process relaying this outcome should exit with: the
+    /// command's own code, `128 + signal` as shells report kills, or 1.
+    /// Codes outside `1..=255` that would read as success become 1.
+    pub fn exit_code(&self) -> u8 {
+        match (self.code, self.signal) {
+            (Some(0), _) => 0,
+            (Some(code), _) => u8::try_from(code).ok().filter(|&c| c != 0).unwrap_or(1),
+            (None, Some(signal)) => u8::try_from(128 + signal).unwrap_or(1),
+            (None, None) => 1,
+        }
+    }
+}
+
+impl fmt::Display for Outcome {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match (self.code, self.signal) {
+            (Some(code), _) => write!(f, "exit status: {code}")?,
+            (None, Some(signal)) => write!(f, "signal: {signal}")?,
+            (None, None) => f.write_str("unknown status")?,
+        }
+        write!(f, " after {:.2}s", self.duration.as_secs_f64())
+    }
+}
+
+/// Name to report `cmd` by in errors: its file stem, e.g. `dub`.
+fn program_name(cmd: &Command) -> String {
+    let program = Path::new(cmd.get_program());
+    program
+        .file_stem()
+        .unwrap_or(program.as_os_str())
+        .to_string_lossy()
+        .into_owned()
+}
+
+/// Starts `cmd` with whatever stdio it was configured with.
+pub fn spawn(cmd: &mut Command) -> Result<Child> {
+    cmd.spawn().map_err(|source| Error::Spawn {
+        program: program_name(cmd),
+        source,
+    })
+}
+
+/// Waits for a child started at `started`.
+pub fn wait(child: &mut Child, started: Instant) -> Result<Outcome> {
+    let status = child.wait()?;
+    Ok(Outcome::from_status(status, started.elapsed()))
+}
+
+/// Runs `cmd` to completion.
+pub fn run(cmd: &mut Command, capture: Capture) -> Result<Outcome> {
+    let started = Instant::now();
+    match capture {
+        Capture::Inherit => {
+            let mut child = spawn(cmd)?;
+            wait(&mut child, started)
+        }
+        Capture::Output => {
+            let child = spawn(cmd.stdout(Stdio::piped()).stderr(Stdio::piped()))?;
+            let output = child.wait_with_output()?;
+            Ok(Outcome::from_output(output, started.elapsed()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    #[test]
+    fn test_run() {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "echo out; echo err >&2; exit 3"]);
+        let outcome = run(&mut cmd, Capture::Output).unwrap();
+        assert_eq!(outcome.code, Some(3));
+        assert_eq!(outcome.stdout.as_deref(), Some("out\n"));
+        assert_eq!(outcome.stderr.as_deref(), Some("err\n"));
+        assert!(!outcome.success());
+        assert_eq!(outcome.exit_code(), 3);
+
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", "kill -9 $$"]);
+        let outcome = run(&mut cmd, Capture::Inherit).unwrap();
+        assert_eq!((outcome.code, outcome.signal), (None, Some(9)));
+        assert_eq!(outcome.exit_code(), 137);
+        assert_eq!(outcome.stdout, None);
+    }
+
+    #[test]
+    fn test_spawn_error() {
+        let mut cmd = Command::new("/nonexistent/bin/dub");
+        assert_eq!(
+            run(&mut cmd, Capture::Inherit).unwrap_err().to_string(),
+            "dub executable not found or not accessible"
+        );
+    }
+
+    #[test]
+    fn test_exit_code() {
+        let outcome = |code, signal| Outcome {
+            code,
+            signal,
+            ..Outcome::default()
+        };
+        assert_eq!(Outcome::SUCCESS.exit_code(), 0);
+        assert_eq!(outcome(Some(0), None).exit_code(), 0);
+        assert_eq!(outcome(Some(256), None).exit_code(), 1);
+        assert_eq!(outcome(Some(-1), None).exit_code(), 1);
+        assert_eq!(outcome(None, Some(15)).exit_code(), 143);
+        assert_eq!(outcome(None, None).exit_code(), 1);
+    }
+}
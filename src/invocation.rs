@@ -0,0 +1,140 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Printable descriptions of the commands cargo-dub would run, for
+//! `--dry-run`.
+
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::path::PathBuf;
+use std::process::Command;
+
+/// Environment variables DUB or the D compilers read.
+pub const RELEVANT_ENV: &[&str] = &["DC", "DFLAGS", "DUB_HOME", "DPATH"];
+
+/// A fully resolved command line.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Invocation {
+    pub program: String,
+    pub args: Vec<String>,
+    pub cwd: PathBuf,
+    /// Relevant variables the command sees, whether inherited or set on it.
+    pub env: BTreeMap<String, String>,
+    /// Variables removed from the command's environment.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unset: Vec<String>,
+}
+
+impl Invocation {
+    pub fn of(cmd: &Command) -> Self {
+        let mut vars: BTreeMap<String, String> = RELEVANT_ENV
+            .iter()
+            .filter_map(|&name| Some((name.to_string(), env::var(name).ok()?)))
+            .collect();
+        let mut unset = Vec::new();
+        for (name, value) in cmd.get_envs() {
+            let name = name.to_string_lossy().into_owned();
+            match value {
+                Some(value) => {
+                    vars.insert(name, value.to_string_lossy().into_owned());
+                }
+                None => {
+                    vars.remove(&name);
+                    unset.push(name);
+                }
+            }
+        }
+        let cwd = match cmd.get_current_dir() {
+            Some(dir) => dir.to_path_buf(),
+            None => env::current_dir().unwrap_or_default(),
+        };
+        Self {
+            program: cmd.get_program().to_string_lossy().into_owned(),
+            args: cmd
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect(),
+            cwd,
+            env: vars,
+            unset,
+        }
+    }
+
+    /// A POSIX shell line reproducing the invocation:
+    /// `cd DIR && VAR=value program args...`, going through
+    /// `env -u VAR` when variables are removed.
+    pub fn shell(&self) -> String {
+        let mut words = vec![
+            "cd".to_string(),
+            quote(&self.cwd.to_string_lossy()),
+            "&&".to_string(),
+        ];
+        if !self.unset.is_empty() {
+            words.push("env".to_string());
+            for name in &self.unset {
+                words.extend(["-u".to_string(), quote(name)]);
+            }
+        }
+        words.extend(
+            self.env
+                .iter()
+                .map(|(name, value)| format!("{name}={}", quote(value))),
+        );
+        words.push(quote(&self.program));
+        words.extend(self.args.iter().map(|arg| quote(arg)));
+        words.join(" ")
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("invocations serialize")
+    }
+}
+
+/// Quotes `word` for a POSIX shell, leaving it bare when that is safe.
+pub fn quote(word: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "@%+=:,./_-".contains(c);
+    if !word.is_empty() && word.chars().all(safe) {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', r"'\''"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("--compiler=ldc2"), "--compiler=ldc2");
+        assert_eq!(quote("--d-version=A B"), "'--d-version=A B'");
+        assert_eq!(quote("it's"), r"'it'\''s'");
+        assert_eq!(quote(""), "''");
+    }
+
+    #[test]
+    fn test_invocation() {
+        let mut cmd = Command::new("/usr/bin/dub");
+        cmd.args(["build", "--root=my app"])
+            .current_dir("/work")
+            .env("DFLAGS", "-g -O")
+            .env("DUB_REGISTRY", "x")
+            .env("DPATH", "/opt/d")
+            .env_remove("DC")
+            .env_remove("DUB_HOME");
+        let invocation = Invocation::of(&cmd);
+        assert_eq!(invocation.cwd, PathBuf::from("/work"));
+        assert!(!invocation.env.contains_key("DC"));
+        assert_eq!(invocation.unset, ["DC", "DUB_HOME"]);
+        assert_eq!(
+            invocation.shell(),
+            "cd /work && env -u DC -u DUB_HOME DFLAGS='-g -O' DPATH=/opt/d DUB_REGISTRY=x \
+             /usr/bin/dub build '--root=my app'"
+        );
+        let json: serde_json::Value = serde_json::from_str(&invocation.to_json()).unwrap();
+        assert_eq!(json["args"][1], "--root=my app");
+        assert_eq!(json["env"]["DFLAGS"], "-g -O");
+        assert_eq!(json["unset"], serde_json::json!(["DC", "DUB_HOME"]));
+    }
+}
@@ -0,0 +1,31 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Library side of cargo-dub: DUB recipe parsing and related tooling.
+
+pub mod audit;
+pub mod build;
+pub mod config;
+pub mod convert;
+pub mod coverage;
+pub mod describe;
+pub mod diagnostics;
+pub mod exec;
+pub mod invocation;
+pub mod manifest;
+pub mod messages;
+pub mod outdated;
+pub mod query;
+pub mod registry;
+pub mod report;
+pub mod sdl;
+pub mod selections;
+pub mod target;
+pub mod toolchain;
+pub mod tree;
+pub mod unittest;
+pub mod vendor;
+pub mod version;
+pub mod watch;
+
+pub use manifest::Manifest;
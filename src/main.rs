@@ -1,15 +1,54 @@
 // SPDX-License-Identifier: MIT
 // Copyright (c) 2025 Matheus C. França
 
+use cargo_dub::audit::{Database, Informational};
+use cargo_dub::config::{self, DubConfig};
+use cargo_dub::coverage::{Coverage, CoverageFormat, CoverageReport};
+use cargo_dub::describe::{Description, Table};
+use cargo_dub::diagnostics::{self, Counts, Diagnostic, Event};
+use cargo_dub::exec::{self, Capture, Outcome};
+use cargo_dub::invocation::Invocation;
+use cargo_dub::manifest::Dependency;
+use cargo_dub::messages::{CompilerMessage, Message};
+use cargo_dub::outdated;
+use cargo_dub::query;
+use cargo_dub::registry::{self, Mirror};
+use cargo_dub::report::{self, ReportSpec};
+use cargo_dub::selections::{self, Selection, Selections};
+use cargo_dub::target::Target;
+use cargo_dub::toolchain::{self, Family, OnMismatch, Pin, Resolution};
+use cargo_dub::tree::{Graph, Template};
+use cargo_dub::unittest::{self, LineKind, Runner};
+use cargo_dub::vendor::{self, Checksum};
+use cargo_dub::version::{Version, VersionReq};
+use cargo_dub::watch;
+use cargo_dub::{convert, manifest, Manifest};
 use clap::{Args, Parser, Subcommand};
-use std::process::{Command, Stdio};
-use std::{env, io, path::Path};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, Command, ExitCode, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use std::{env, fs, io, thread};
 
 type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
 
 #[derive(Parser, Debug)]
 #[command(name = "cargo-dub", version, about)]
 struct Cli {
+    /// Print the dub command lines instead of running them
+    #[arg(
+        long,
+        visible_alias = "print-command",
+        global = true,
+        value_enum,
+        value_name = "FORMAT",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "shell"
+    )]
+    dry_run: Option<DryRunFormat>,
     #[command(subcommand)]
     command: Option<Commands>,
 }
@@ -29,15 +68,18 @@ enum Commands {
 enum DubCommands {
     /// Build and run package
     #[command(alias = "r")]
-    Run(DubOptions),
+    Run(BuildOptions),
     /// Build package
     #[command(alias = "b")]
-    Build(DubOptions),
-    /// Convert dub.json/dub.sdl
-    Convert {
-        #[arg(short, long, value_enum)]
-        format: Format,
+    Build {
+        #[command(flatten)]
+        options: BuildOptions,
+        /// Output format for compiler messages and artifacts
+        #[arg(long, value_enum, default_value_t)]
+        message_format: MessageFormat,
     },
+    /// Convert dub.json/dub.sdl
+    Convert(ConvertOptions),
     /// Pass raw arguments to dub
     Raw {
         #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
@@ -57,6 +99,117 @@ enum DubCommands {
     Clean(CleanOptions),
     /// Run D-Scanner linter tests
     Lint(LintOptions),
+    /// Run unittests and report results like `cargo test`
+    Test(TestOptions),
+    /// Run unittests with coverage and export lcov or Cobertura reports
+    Coverage(CoverageOptions),
+    /// Show installed D compilers and DUB
+    Toolchain(ToolchainOptions),
+    /// Rebuild or rerun when the package's sources change
+    Watch(WatchOptions),
+    /// Display the dependency tree of the package
+    Tree(TreeOptions),
+    /// Inspect dub.selections.json
+    Lock {
+        #[command(subcommand)]
+        command: LockCommand,
+    },
+    /// Upgrade dependencies, or pin one with --precise
+    Update(UpdateOptions),
+    /// Explain which dependency chains require a package
+    Why(WhyOptions),
+    /// Copy all dependencies into the package, like `cargo vendor`
+    Vendor(VendorOptions),
+    /// Search the package registry
+    Search(SearchOptions),
+    /// Show a package's versions, dependencies and metadata from the registry
+    Info(InfoOptions),
+    /// List dependencies with newer versions in the registry
+    Outdated(OutdatedOptions),
+    /// Check dependencies against an advisory database
+    Audit(AuditOptions),
+    /// Mirror the package registry for offline use
+    Registry {
+        #[command(subcommand)]
+        command: RegistryCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum LockCommand {
+    /// Show how dub.selections.json changed since a git revision
+    Diff {
+        /// Git revision to compare from, e.g. HEAD or main
+        rev: String,
+        /// Git revision to compare to [default: the working tree]
+        to: Option<String>,
+        #[command(flatten)]
+        options: DubOptions,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum RegistryCommand {
+    /// Serve a mirror directory to DUB's --registry
+    Serve {
+        /// Mirror directory
+        #[arg(long, default_value = "dub-registry")]
+        dir: PathBuf,
+        /// Address to listen on
+        #[arg(long, default_value = "127.0.0.1:8080")]
+        addr: String,
+    },
+    /// Copy packages and their dependencies into a mirror directory
+    Sync {
+        /// Packages to mirror [default: the versions in dub.selections.json]
+        #[arg(value_name = "PACKAGE[@VERSION]")]
+        packages: Vec<String>,
+        /// Mirror directory
+        #[arg(long, default_value = "dub-registry")]
+        dir: PathBuf,
+        /// Registry to copy from
+        #[arg(long, value_name = "URL", default_value = registry::DEFAULT_URL)]
+        upstream: String,
+        /// Path to the D package whose dub.selections.json is mirrored [default: current directory]
+        #[arg(long)]
+        root: Option<String>,
+    },
+}
+
+impl DubCommands {
+    /// Options that take defaults from `[package.metadata.dub]`. `init` is left
+    /// alone since it creates a new package rather than using the configured one.
+    fn options_mut(&mut self) -> Option<&mut DubOptions> {
+        match self {
+            DubCommands::Run(opts) | DubCommands::Build { options: opts, .. } => {
+                Some(&mut opts.options)
+            }
+            DubCommands::Describe(opts) => Some(&mut opts.options),
+            DubCommands::Add(opts) | DubCommands::Remove(opts) => Some(&mut opts.options),
+            DubCommands::Fetch(opts) => Some(&mut opts.options),
+            DubCommands::Clean(opts) => Some(&mut opts.options),
+            DubCommands::Lint(opts) => Some(&mut opts.options),
+            DubCommands::Test(opts) => Some(&mut opts.options),
+            DubCommands::Coverage(opts) => Some(&mut opts.options),
+            DubCommands::Watch(opts) => Some(&mut opts.options),
+            DubCommands::Tree(opts) => Some(&mut opts.options),
+            DubCommands::Lock {
+                command: LockCommand::Diff { options, .. },
+            } => Some(options),
+            DubCommands::Update(opts) => Some(&mut opts.options),
+            DubCommands::Why(opts) => Some(&mut opts.options),
+            DubCommands::Vendor(opts) => Some(&mut opts.options),
+            DubCommands::Outdated(opts) => Some(&mut opts.options),
+            DubCommands::Audit(opts) => Some(&mut opts.options),
+            DubCommands::Convert(_)
+            | DubCommands::Raw { .. }
+            | DubCommands::Init(_)
+            | DubCommands::Toolchain(_)
+            | DubCommands::Registry { .. }
+            | DubCommands::Search(_)
+            | DubCommands::Info(_) => None,
+        }
+    }
 }
 
 #[derive(clap::ValueEnum, Clone, Debug)]
@@ -65,6 +218,106 @@ enum Format {
     Sdl,
 }
 
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum Deny {
+    /// Every informational advisory
+    Warnings,
+    /// Packages no longer maintained
+    Unmaintained,
+    /// Unsound APIs
+    Unsound,
+    /// Other notices
+    Notice,
+    /// Withdrawn releases
+    Yanked,
+}
+
+impl Deny {
+    fn denies(self, kind: Informational) -> bool {
+        matches!(
+            (self, kind),
+            (Deny::Warnings, _)
+                | (Deny::Unmaintained, Informational::Unmaintained)
+                | (Deny::Unsound, Informational::Unsound)
+                | (Deny::Notice, Informational::Notice)
+                | (Deny::Yanked, Informational::Yanked)
+        )
+    }
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum VendorOverride {
+    /// Point dub.selections.json at the vendored directories
+    #[default]
+    Selections,
+    /// Register the vendored packages in .dub/packages/local-packages.json
+    LocalPackages,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum TreeFormat {
+    /// An indented tree
+    #[default]
+    Text,
+    /// A Graphviz digraph
+    Dot,
+    /// The packages with their versions, sources and dependencies
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum DryRunFormat {
+    /// A POSIX shell line, with the working directory and environment
+    Shell,
+    /// One JSON object per command: program, args, cwd and env
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum MessageFormat {
+    #[default]
+    Human,
+    /// Newline-delimited JSON messages in Cargo's format
+    Json,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum TestRunner {
+    /// silly or unit-threaded when the package uses them, otherwise a
+    /// generated runner reporting each unittest block
+    #[default]
+    Auto,
+    /// druntime's runner, which only reports failed modules
+    Druntime,
+}
+
+#[derive(clap::ValueEnum, Clone, Copy, Debug, Default, PartialEq, Eq)]
+enum WatchCommand {
+    Run,
+    #[default]
+    Build,
+    Test,
+    Lint,
+}
+
+impl WatchCommand {
+    fn as_str(self) -> &'static str {
+        match self {
+            WatchCommand::Run => "run",
+            WatchCommand::Build => "build",
+            WatchCommand::Test => "test",
+            WatchCommand::Lint => "lint",
+        }
+    }
+}
+
 #[derive(clap::ValueEnum, Clone, Debug)]
 enum ProjectType {
     Minimal,
@@ -103,6 +356,75 @@ struct DubOptions {
     yes: bool,
     #[arg(long)]
     non_interactive: bool,
+    /// Registry to fetch packages from, such as a `cargo dub registry serve` mirror
+    #[arg(long, value_name = "URL")]
+    registry: Option<String>,
+    /// Path to the D package [default: current directory]
+    #[arg(long)]
+    root: Option<String>,
+}
+
+impl DubOptions {
+    /// Fills options not given on the command line from Cargo.toml metadata.
+    /// `dc`, the value of `DC`, still takes precedence over a configured
+    /// compiler.
+    fn apply_defaults(&mut self, dc: Option<String>, defaults: &DubConfig) {
+        if self.compiler.is_none() {
+            self.compiler = dc.or_else(|| defaults.compiler.clone());
+        }
+        for (value, default) in [
+            (&mut self.build, &defaults.build_type),
+            (&mut self.config, &defaults.config),
+            (&mut self.arch, &defaults.arch),
+            (&mut self.registry, &defaults.registry),
+        ] {
+            if value.is_none() {
+                value.clone_from(default);
+            }
+        }
+        if self.d_versions.is_empty() {
+            self.d_versions.clone_from(&defaults.d_versions);
+        }
+        if self.debug.is_empty() {
+            self.debug.clone_from(&defaults.debug);
+        }
+        if self.root.is_none() {
+            self.root = defaults
+                .path
+                .as_ref()
+                .map(|path| path.display().to_string());
+        }
+    }
+}
+
+#[derive(Args, Clone, Debug)]
+struct ConvertOptions {
+    /// Recipe to convert [default: dub.sdl or dub.json in the current directory]
+    #[arg(value_name = "INPUT")]
+    input: Option<PathBuf>,
+    #[arg(short, long, value_enum)]
+    format: Format,
+    /// Only report whether the recipe and its converted counterpart are equivalent
+    #[arg(long)]
+    check: bool,
+    /// Print the converted recipe instead of writing it
+    #[arg(long, conflicts_with = "output")]
+    to_stdout: bool,
+    /// Write the converted recipe to this path, keeping the input
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+    /// Re-parse the result and fail if any field would change
+    #[arg(long)]
+    verify: bool,
+}
+
+#[derive(Args, Default, Clone, Debug)]
+struct BuildOptions {
+    /// Rust target triple to build for, e.g. aarch64-unknown-linux-gnu
+    #[arg(long, value_name = "TRIPLE", conflicts_with = "arch")]
+    target: Option<String>,
+    #[command(flatten)]
+    options: DubOptions,
 }
 
 #[derive(Args, Clone, Debug)]
@@ -111,6 +433,17 @@ struct DescribeOptions {
     data: Option<Vec<String>>,
     #[arg(long)]
     data_list: bool,
+    /// Print the values at a jq-style path, e.g.
+    /// '.targets[] | select(.rootPackage == "app") | .buildSettings.importPaths[]'
+    #[arg(long, value_name = "PATH", conflicts_with_all = ["data", "data_list", "table"])]
+    query: Option<String>,
+    /// Print a table: packages, targets, import-paths, string-import-paths,
+    /// versions, libs or source-files
+    #[arg(long, value_name = "VIEW", conflicts_with_all = ["data", "data_list"])]
+    table: Option<Table>,
+    /// Rust target triple to describe the build for
+    #[arg(long, value_name = "TRIPLE", conflicts_with = "arch")]
+    target: Option<String>,
     #[command(flatten)]
     options: DubOptions,
 }
@@ -141,8 +474,6 @@ struct InitOptions {
     dependencies: Vec<String>,
     #[arg(short, long, value_enum, default_value_t = ProjectType::Minimal)]
     r#type: ProjectType,
-    #[arg(long, action = clap::ArgAction::SetTrue)]
-    non_interactive: bool,
     #[command(flatten)]
     options: DubOptions,
 }
@@ -181,41 +512,184 @@ struct LintOptions {
     options: DubOptions,
 }
 
+#[derive(Args, Clone, Debug)]
+struct TestOptions {
+    /// Only run tests whose name contains one of these
+    #[arg(value_name = "FILTER")]
+    filters: Vec<String>,
+    /// Write a report: json, junit or tap, to stdout or to FORMAT=FILE
+    #[arg(long, value_name = "FORMAT[=FILE]")]
+    report: Vec<ReportSpec>,
+    /// Test runner for packages without a test framework
+    #[arg(long, value_enum, default_value_t)]
+    runner: TestRunner,
+    #[command(flatten)]
+    options: DubOptions,
+}
+
+#[derive(Args, Clone, Debug)]
+struct CoverageOptions {
+    /// Write a report: lcov or cobertura, to stdout or to FORMAT=FILE
+    #[arg(long, value_name = "FORMAT[=FILE]")]
+    report: Vec<CoverageReport>,
+    /// Fail when less than this percentage of lines ran
+    #[arg(long, value_name = "PERCENT")]
+    fail_under: Option<f64>,
+    #[command(flatten)]
+    options: DubOptions,
+}
+
+#[derive(Args, Clone, Debug)]
+struct WatchOptions {
+    /// DUB command to run on each change
+    #[arg(value_enum, default_value_t)]
+    command: WatchCommand,
+    /// Milliseconds without further changes before restarting
+    #[arg(long, value_name = "MS", default_value_t = 300)]
+    debounce: u64,
+    /// Clear the screen before each run
+    #[arg(long)]
+    clear: bool,
+    #[command(flatten)]
+    options: DubOptions,
+}
+
+#[derive(Args, Clone, Debug)]
+struct TreeOptions {
+    /// Show the packages that depend on PACKAGE instead
+    #[arg(short, long, value_name = "PACKAGE")]
+    invert: Option<String>,
+    /// Show only packages required by more than one package, inverted
+    #[arg(long, conflicts_with = "invert")]
+    duplicates: bool,
+    /// Maximum depth of the tree
+    #[arg(long, value_name = "N")]
+    depth: Option<usize>,
+    /// Label template: {p} package, {n} name, {v} version, {s} source, {t} target type
+    #[arg(long, value_name = "TEMPLATE", default_value = "{p}")]
+    format: Template,
+    #[arg(long, value_enum, default_value_t)]
+    output_format: TreeFormat,
+    #[command(flatten)]
+    options: DubOptions,
+}
+
+#[derive(Args, Clone, Debug)]
+struct UpdateOptions {
+    /// Package to upgrade [default: all]
+    #[arg(value_name = "PACKAGE")]
+    package: Option<String>,
+    /// Select exactly this version of PACKAGE
+    #[arg(long, value_name = "VERSION", requires = "package")]
+    precise: Option<String>,
+    #[command(flatten)]
+    options: DubOptions,
+}
+
+#[derive(Args, Clone, Debug)]
+struct WhyOptions {
+    #[arg(value_name = "PACKAGE")]
+    package: String,
+    #[command(flatten)]
+    options: DubOptions,
+}
+
+#[derive(Args, Clone, Debug)]
+struct VendorOptions {
+    /// Directory to vendor into, relative to the package
+    #[arg(value_name = "DIR", default_value = "vendor")]
+    dir: PathBuf,
+    /// How builds are directed to the vendored packages
+    #[arg(long = "override", value_enum, default_value_t)]
+    override_with: VendorOverride,
+    /// Only check the vendored packages against their checksums
+    #[arg(long)]
+    verify: bool,
+    #[command(flatten)]
+    options: DubOptions,
+}
+
+#[derive(Args, Clone, Debug)]
+struct SearchOptions {
+    #[arg(value_name = "QUERY")]
+    query: String,
+    /// Maximum number of packages to list
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    limit: usize,
+    /// Registry to search [default: the configured registry, or code.dlang.org]
+    #[arg(long, value_name = "URL")]
+    registry: Option<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct InfoOptions {
+    #[arg(value_name = "PACKAGE[@VERSION]")]
+    package: String,
+    /// Registry to query [default: the configured registry, or code.dlang.org]
+    #[arg(long, value_name = "URL")]
+    registry: Option<String>,
+}
+
+#[derive(Args, Clone, Debug)]
+struct OutdatedOptions {
+    /// Fail when any dependency is behind the registry
+    #[arg(long)]
+    exit_code: bool,
+    #[arg(long, value_enum, default_value_t)]
+    format: OutputFormat,
+    #[command(flatten)]
+    options: DubOptions,
+}
+
+#[derive(Args, Clone, Debug)]
+struct AuditOptions {
+    /// Advisory database directory [default: ~/.dub/advisory-db]
+    #[arg(long, value_name = "PATH")]
+    db: Option<PathBuf>,
+    /// Fail on informational advisories too
+    #[arg(long, value_enum, value_name = "KIND")]
+    deny: Vec<Deny>,
+    #[arg(long, value_enum, default_value_t)]
+    format: OutputFormat,
+    #[command(flatten)]
+    options: DubOptions,
+}
+
+#[derive(Args, Clone, Debug)]
+struct ToolchainOptions {
+    #[arg(long, value_enum, default_value_t)]
+    format: OutputFormat,
+}
+
 /// Trait for DUB executable command creation
 trait DubCommand {
     fn command(&self) -> Command;
+
+    /// The DUB release, when known, for `d-toolchain.toml` checks.
+    fn version(&self) -> Option<&Version> {
+        None
+    }
 }
 
 /// Cached DUB executable path
 struct DubExecutable {
     path: String,
+    version: Option<Version>,
 }
 
 impl DubExecutable {
     fn new() -> Result<Self> {
-        let candidates = if cfg!(windows) {
-            vec!["dub.exe", "dub"]
-        } else {
-            vec!["dub"]
-        };
-        for candidate in candidates {
-            if Command::new(candidate)
-                .arg("--version")
-                .stdout(Stdio::null())
-                .stderr(Stdio::null())
-                .status()
-                .map(|s| s.success())
-                .unwrap_or(false)
-            {
-                return Ok(Self {
-                    path: candidate.to_string(),
-                });
-            }
-        }
-        Err("dub executable not found. Install DUB from https://dub.pm".into())
+        let dub = toolchain::find_dub().ok_or(DUB_NOT_FOUND)?;
+        dub.check_minimum()?;
+        Ok(Self {
+            path: dub.path.display().to_string(),
+            version: dub.version,
+        })
     }
 }
 
+const DUB_NOT_FOUND: &str = "dub executable not found. Install DUB from https://dub.pm";
+
 impl DubCommand for DubExecutable {
     fn command(&self) -> Command {
         let mut cmd = Command::new(&self.path);
@@ -224,150 +698,655 @@ impl DubCommand for DubExecutable {
             .stderr(Stdio::inherit());
         cmd
     }
+
+    fn version(&self) -> Option<&Version> {
+        self.version.as_ref()
+    }
 }
 
-fn main() {
-    if let Err(e) = run() {
-        eprintln!("Error: {e}");
-        std::process::exit(1);
+fn main() -> ExitCode {
+    match run() {
+        Ok(outcome) => ExitCode::from(outcome.exit_code()),
+        Err(e) => {
+            eprintln!("Error: {e}");
+            ExitCode::FAILURE
+        }
     }
 }
 
-fn run() -> Result<()> {
-    let args = Cli::parse();
-    let dub = DubExecutable::new()?;
+fn run() -> Result<Outcome> {
+    run_with(Cli::parse(), env::var("DC").ok(), DubExecutable::new)
+}
 
-    let cmd = match args.command {
-        Some(Commands::Dub { cmd }) => cmd.unwrap_or(DubCommands::Run(DubOptions::default())),
+/// Runs parsed arguments, locating DUB with `find_dub` only once a command
+/// needs it. `dc` is the compiler `DC` names, used when `--compiler` is not
+/// given.
+fn run_with<D: DubCommand>(
+    args: Cli,
+    dc: Option<String>,
+    find_dub: impl FnOnce() -> Result<D>,
+) -> Result<Outcome> {
+    let mut cmd = match args.command {
+        Some(Commands::Dub { cmd }) => cmd.unwrap_or(DubCommands::Run(BuildOptions::default())),
         Some(Commands::Direct(cmd)) => cmd,
-        None => DubCommands::Run(DubOptions::default()),
+        None => DubCommands::Run(BuildOptions::default()),
     };
+    if let DubCommands::Init(opts) = &mut cmd {
+        opts.options.compiler = opts.options.compiler.take().or(dc.clone());
+    }
+    if let Some(opts) = cmd.options_mut() {
+        opts.apply_defaults(dc, &config::load(&env::current_dir()?)?);
+    }
+
+    // Native commands must work without a DUB installation.
+    if let (
+        Some(_),
+        DubCommands::Convert(_)
+        | DubCommands::Toolchain(_)
+        | DubCommands::Lock { .. }
+        | DubCommands::Registry { .. }
+        | DubCommands::Vendor(VendorOptions { verify: true, .. })
+        | DubCommands::Search(_)
+        | DubCommands::Info(_)
+        | DubCommands::Outdated(_)
+        | DubCommands::Audit(_),
+    ) = (args.dry_run, &cmd)
+    {
+        return Err("--dry-run only applies to commands that run dub".into());
+    }
+    let mut cmd = match cmd {
+        DubCommands::Convert(opts) => {
+            return convert_format(Path::new("."), &opts).map(|()| Outcome::SUCCESS)
+        }
+        DubCommands::Toolchain(opts) => return execute_toolchain(&opts).map(|()| Outcome::SUCCESS),
+        DubCommands::Lock { command } => return execute_lock(&command).map(|()| Outcome::SUCCESS),
+        DubCommands::Registry { command } => {
+            return execute_registry(&command).map(|()| Outcome::SUCCESS)
+        }
+        DubCommands::Vendor(opts) if opts.verify => {
+            return verify_vendored(&opts).map(|()| Outcome::SUCCESS)
+        }
+        DubCommands::Search(opts) => {
+            let client = registry_client(opts.registry.as_deref())?;
+            return search(&client, &opts, &mut io::stdout().lock()).map(|()| Outcome::SUCCESS);
+        }
+        DubCommands::Info(opts) => {
+            let client = registry_client(opts.registry.as_deref())?;
+            return package_info(&client, &opts, &mut io::stdout().lock())
+                .map(|()| Outcome::SUCCESS);
+        }
+        DubCommands::Outdated(opts) => {
+            let client = registry_client(opts.options.registry.as_deref())?;
+            return outdated(&client, &opts, &mut io::stdout().lock()).map(|()| Outcome::SUCCESS);
+        }
+        DubCommands::Audit(opts) => {
+            return audit(&opts, &mut io::stdout().lock()).map(|()| Outcome::SUCCESS)
+        }
+        cmd => cmd,
+    };
+    let dub = find_dub()?;
+    // Commands that compile honor d-toolchain.toml and --target.
+    let compiling = match &mut cmd {
+        DubCommands::Run(opts) | DubCommands::Build { options: opts, .. } => {
+            Some((&mut opts.options, opts.target.clone()))
+        }
+        DubCommands::Describe(opts) => Some((&mut opts.options, opts.target.clone())),
+        DubCommands::Test(opts) => Some((&mut opts.options, None)),
+        DubCommands::Coverage(opts) => Some((&mut opts.options, None)),
+        DubCommands::Watch(opts) => Some((&mut opts.options, None)),
+        _ => None,
+    };
+    if let Some((opts, target)) = compiling {
+        let dir = package_dir(opts)?;
+        apply_pin(&dir, opts, dub.version())?;
+        if let Some(triple) = target {
+            apply_target(&triple, opts)?;
+        }
+    }
+
+    if let Some(format) = args.dry_run {
+        for cmd in planned_commands(&dub, &cmd)? {
+            let invocation = Invocation::of(&cmd);
+            match format {
+                DryRunFormat::Shell => println!("{}", invocation.shell()),
+                DryRunFormat::Json => println!("{}", invocation.to_json()),
+            }
+        }
+        return Ok(Outcome::SUCCESS);
+    }
 
     match cmd {
-        DubCommands::Run(opts) => execute_dub(&dub, "run", &opts),
-        DubCommands::Build(opts) => execute_dub(&dub, "build", &opts),
-        DubCommands::Convert { format } => convert_format(&dub, format),
-        DubCommands::Raw { args } => execute_raw(&dub, &args),
+        DubCommands::Run(opts) => execute_command(dub_command(&dub, "run", &opts.options)?),
+        DubCommands::Build {
+            options,
+            message_format: MessageFormat::Human,
+        } => execute_build(&dub, &options.options),
+        DubCommands::Build {
+            options,
+            message_format: MessageFormat::Json,
+        } => execute_build_json(&dub, &options.options),
+        DubCommands::Convert(_)
+        | DubCommands::Toolchain(_)
+        | DubCommands::Lock { .. }
+        | DubCommands::Registry { .. }
+        | DubCommands::Search(_)
+        | DubCommands::Info(_)
+        | DubCommands::Outdated(_)
+        | DubCommands::Audit(_) => unreachable!("handled above"),
+        DubCommands::Raw { args } => execute_command(raw_command(&dub, &args)),
         DubCommands::Describe(opts) => execute_describe(&dub, &opts),
-        DubCommands::Add(opts) => execute_add_remove(&dub, "add", &opts),
-        DubCommands::Remove(opts) => execute_add_remove(&dub, "remove", &opts),
-        DubCommands::Fetch(opts) => execute_fetch(&dub, &opts),
-        DubCommands::Init(opts) => execute_init(&dub, &opts),
-        DubCommands::Clean(opts) => execute_clean(&dub, &opts),
-        DubCommands::Lint(opts) => execute_lint(&dub, &opts),
+        DubCommands::Add(opts) => execute_command(add_remove_command(&dub, "add", &opts)?),
+        DubCommands::Remove(opts) => execute_command(add_remove_command(&dub, "remove", &opts)?),
+        DubCommands::Fetch(opts) => execute_command(fetch_command(&dub, &opts)?),
+        DubCommands::Init(opts) => execute_command(init_command(&dub, &opts)?),
+        DubCommands::Clean(opts) => execute_command(clean_command(&dub, &opts)?),
+        DubCommands::Lint(opts) => execute_command(lint_command(&dub, &opts)?),
+        DubCommands::Test(opts) => execute_test(&dub, &opts),
+        DubCommands::Coverage(opts) => execute_coverage(&dub, &opts),
+        DubCommands::Watch(opts) => execute_watch(&dub, &opts),
+        DubCommands::Tree(opts) => execute_tree(&dub, &opts),
+        DubCommands::Update(opts) => execute_update(&dub, &opts),
+        DubCommands::Why(opts) => execute_why(&dub, &opts),
+        DubCommands::Vendor(opts) => execute_vendor(&dub, &opts),
     }
 }
 
-fn execute_dub(dub: &impl DubCommand, subcommand: &str, opts: &DubOptions) -> Result<()> {
+/// The DUB invocations `cmd` would run, in order, for `--dry-run`.
+fn planned_commands(dub: &impl DubCommand, cmd: &DubCommands) -> Result<Vec<Command>> {
+    let base = env::current_dir()?;
+    let root = |opts: &DubOptions| match &opts.root {
+        Some(root) => base.join(root),
+        None => base.clone(),
+    };
+    Ok(match cmd {
+        DubCommands::Run(opts) => vec![dub_command(dub, "run", &opts.options)?],
+        DubCommands::Build {
+            options,
+            message_format,
+        } => {
+            let build = dub_command(dub, "build", &options.options)?;
+            match message_format {
+                MessageFormat::Human => vec![build],
+                MessageFormat::Json => vec![dub_command(dub, "describe", &options.options)?, build],
+            }
+        }
+        DubCommands::Raw { args } => vec![raw_command(dub, args)],
+        DubCommands::Describe(opts) => vec![describe_command(dub, opts)?],
+        DubCommands::Add(opts) => vec![add_remove_command(dub, "add", opts)?],
+        DubCommands::Remove(opts) => vec![add_remove_command(dub, "remove", opts)?],
+        DubCommands::Fetch(opts) => vec![fetch_command(dub, opts)?],
+        DubCommands::Init(opts) => vec![init_command(dub, opts)?],
+        DubCommands::Clean(opts) => vec![clean_command(dub, opts)?],
+        DubCommands::Lint(opts) => vec![lint_command(dub, opts)?],
+        DubCommands::Test(opts) => {
+            let dir = root(&opts.options);
+            match test_plan(&dir, opts) {
+                (runner, Some(main_file)) => vec![
+                    dub_command(dub, "describe", &test_describe_options(&dir, opts))?,
+                    test_command(dub, opts, runner, Some(&main_file))?,
+                ],
+                (runner, None) => vec![test_command(dub, opts, runner, None)?],
+            }
+        }
+        DubCommands::Coverage(opts) => vec![coverage_command(
+            dub,
+            opts,
+            &coverage_dir(&root(&opts.options)),
+        )?],
+        DubCommands::Watch(opts) => vec![watch_command(dub, opts)?],
+        DubCommands::Tree(opts) => vec![dub_command(dub, "describe", &opts.options)?],
+        DubCommands::Update(opts) => vec![update_command(dub, opts)?],
+        DubCommands::Why(opts) => vec![dub_command(dub, "describe", &opts.options)?],
+        DubCommands::Vendor(opts) => vec![dub_command(dub, "describe", &opts.options)?],
+        DubCommands::Convert(_)
+        | DubCommands::Toolchain(_)
+        | DubCommands::Lock { .. }
+        | DubCommands::Registry { .. }
+        | DubCommands::Search(_)
+        | DubCommands::Info(_)
+        | DubCommands::Outdated(_)
+        | DubCommands::Audit(_) => Vec::new(),
+    })
+}
+
+fn dub_command(dub: &impl DubCommand, subcommand: &str, opts: &DubOptions) -> Result<Command> {
     let mut cmd = dub.command();
     cmd.arg(subcommand);
     build_dub_args(&mut cmd, opts)?;
-    execute_command(cmd)
+    Ok(cmd)
 }
 
-fn execute_raw(dub: &impl DubCommand, args: &[String]) -> Result<()> {
+fn raw_command(dub: &impl DubCommand, args: &[String]) -> Command {
     let mut cmd = dub.command();
     cmd.args(args);
-    execute_command(cmd)
+    cmd
 }
 
-fn convert_format(dub: &impl DubCommand, format: Format) -> Result<()> {
-    let (source, target) = match format {
-        Format::Json => ("dub.sdl", "json"),
-        Format::Sdl => ("dub.json", "sdl"),
+fn convert_format(root: &Path, opts: &ConvertOptions) -> Result<()> {
+    let to = match opts.format {
+        Format::Json => manifest::Format::Json,
+        Format::Sdl => manifest::Format::Sdl,
     };
-
-    if !Path::new(source).exists() {
+    let (source_path, from) = match &opts.input {
+        Some(input) => {
+            let from = manifest::Format::from_path(input)
+                .ok_or_else(|| format!("Cannot tell the format of '{}'", input.display()))?;
+            (input.clone(), from)
+        }
+        None => {
+            let from = match to {
+                manifest::Format::Json => manifest::Format::Sdl,
+                manifest::Format::Sdl => manifest::Format::Json,
+            };
+            (root.join(from.file_name()), from)
+        }
+    };
+    // Default recipes are reported by file name, explicit paths as given.
+    let source = match &opts.input {
+        Some(input) => input.display().to_string(),
+        None => from.file_name().to_string(),
+    };
+    if from == to {
+        return Err(format!("'{source}' is already in the requested format").into());
+    }
+    if !source_path.exists() {
         return Err(format!("Source file '{source}' not found").into());
     }
+    let in_place = opts.output.is_none() && !opts.to_stdout;
+    let target_path = match &opts.output {
+        Some(output) => output.clone(),
+        None if opts.input.is_some() => source_path.with_extension(match to {
+            manifest::Format::Json => "json",
+            manifest::Format::Sdl => "sdl",
+        }),
+        None => root.join(to.file_name()),
+    };
+    let target = match (&opts.input, &opts.output) {
+        (None, None) => to.file_name().to_string(),
+        _ => target_path.display().to_string(),
+    };
+    let text = fs::read_to_string(&source_path)?;
 
-    let mut cmd = dub.command();
-    cmd.args(["convert", &format!("--format={target}")]);
-    execute_command(cmd)
-}
+    if opts.check {
+        if !target_path.exists() {
+            return Err(format!("Target file '{target}' not found").into());
+        }
+        let old = Manifest::parse(&text, from)?;
+        let new = Manifest::parse(&fs::read_to_string(&target_path)?, to)?;
+        let diffs = old.diff(&new);
+        if !diffs.is_empty() {
+            return Err(difference_report(
+                &format!("'{source}' and '{target}' are not equivalent"),
+                &diffs,
+            ));
+        }
+        println!("'{source}' and '{target}' are equivalent");
+        return Ok(());
+    }
 
-fn execute_describe(dub: &impl DubCommand, opts: &DescribeOptions) -> Result<()> {
-    let mut cmd = dub.command();
-    cmd.arg("describe");
-    if let Some(data) = &opts.data {
-        for d in data {
-            cmd.arg(format!("--data={d}"));
+    let converted = convert::convert(&text, from, to)?;
+    if opts.verify {
+        let diffs = Manifest::parse(&text, from)?.diff(&Manifest::parse(&converted, to)?);
+        if !diffs.is_empty() {
+            return Err(difference_report(
+                &format!("Converting '{source}' would lose information"),
+                &diffs,
+            ));
         }
     }
-    if opts.data_list {
-        cmd.arg("--data-list");
+
+    if opts.to_stdout {
+        print!("{converted}");
+        return Ok(());
     }
-    build_dub_args(&mut cmd, &opts.options)?;
-    execute_command(cmd)
+    fs::write(&target_path, converted)?;
+    if in_place {
+        // Like `dub convert`, the new recipe replaces the old one.
+        fs::remove_file(&source_path)?;
+    }
+    Ok(())
 }
 
-fn execute_add_remove(
-    dub: &impl DubCommand,
-    subcommand: &str,
-    opts: &AddRemoveOptions,
-) -> Result<()> {
-    let mut cmd = dub.command();
-    cmd.arg(subcommand);
-    cmd.args(&opts.packages);
-    build_dub_args(&mut cmd, &opts.options)?;
-    execute_command(cmd)
+fn difference_report(headline: &str, diffs: &[manifest::FieldDiff]) -> Box<dyn std::error::Error> {
+    let mut report = format!("{headline}:");
+    for diff in diffs {
+        report.push_str(&format!("\n  {diff}"));
+    }
+    report.into()
 }
 
-fn execute_fetch(dub: &impl DubCommand, opts: &FetchOptions) -> Result<()> {
-    let mut cmd = dub.command();
-    cmd.arg("fetch");
-    cmd.arg(&opts.package);
-    if let Some(cache) = &opts.cache {
-        cmd.arg(format!("--cache={cache}"));
+fn execute_toolchain(opts: &ToolchainOptions) -> Result<()> {
+    let toolchain = toolchain::discover();
+    match opts.format {
+        OutputFormat::Table => print!("{}", toolchain.table()),
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(&toolchain)?),
     }
-    build_dub_args(&mut cmd, &opts.options)?;
-    execute_command(cmd)
+    let dub = toolchain.dub.as_ref().ok_or(DUB_NOT_FOUND)?;
+    dub.check_minimum()?;
+    if toolchain.compilers.is_empty() {
+        let names: Vec<&str> = toolchain::COMPILERS.iter().map(|(name, _)| *name).collect();
+        return Err(format!("No D compiler found (looked for {})", names.join(", ")).into());
+    }
+    Ok(())
 }
 
-fn execute_init(dub: &impl DubCommand, opts: &InitOptions) -> Result<()> {
-    let mut cmd = dub.command();
-    cmd.arg("init");
-    if let Some(dir) = &opts.directory {
-        cmd.arg(dir);
+/// Enforces the nearest `d-toolchain.toml` and, unless a compiler was chosen
+/// explicitly, passes the pinned one through `--compiler=`.
+fn apply_pin(dir: &Path, opts: &mut DubOptions, dub: Option<&Version>) -> Result<()> {
+    let Some((path, pin)) = Pin::find(dir)? else {
+        return Ok(());
+    };
+    let explicit = opts.compiler.clone();
+    let mut resolution = match &explicit {
+        Some(name) => match toolchain::probe(name) {
+            Some(compiler) => pin.resolve(Some(&compiler), &[], dub),
+            None => Resolution {
+                compiler: None,
+                problems: pin
+                    .check_dub(dub)
+                    .into_iter()
+                    .chain([format!("compiler '{name}' could not be probed")])
+                    .collect(),
+            },
+        },
+        None => pin.resolve(None, &toolchain::discover().compilers, dub),
+    };
+
+    if !resolution.problems.is_empty() {
+        let report = format!(
+            "Toolchain does not satisfy '{}':\n  {}",
+            path.display(),
+            resolution.problems.join("\n  ")
+        );
+        match pin.on_mismatch {
+            OnMismatch::Error => return Err(report.into()),
+            OnMismatch::Warn => eprintln!("warning: {report}"),
+        }
     }
-    cmd.args(&opts.dependencies);
-    cmd.arg(format!(
-        "--type={}",
-        match opts.r#type {
-            ProjectType::Minimal => "minimal",
-            ProjectType::VibeD => "vibe.d",
-            ProjectType::Deimos => "deimos",
-            ProjectType::Custom => "custom",
+    if explicit.is_none() {
+        if let Some(compiler) = resolution.compiler.take() {
+            opts.compiler = Some(compiler.path.display().to_string());
         }
-    ));
-    if opts.non_interactive {
-        cmd.arg("--non-interactive");
     }
-    build_dub_args(&mut cmd, &opts.options)?;
-    execute_command(cmd)
+    Ok(())
 }
 
-fn execute_clean(dub: &impl DubCommand, opts: &CleanOptions) -> Result<()> {
-    let mut cmd = dub.command();
-    cmd.arg("clean");
-    if let Some(package) = &opts.package {
-        cmd.arg(package);
-    }
-    if opts.all_packages {
-        cmd.arg("--all-packages");
+/// Replaces `--arch` with the value matching a Rust target triple for the
+/// compiler that will be used.
+fn apply_target(triple: &str, opts: &mut DubOptions) -> Result<()> {
+    let compiler = opts.compiler.clone();
+    let family = compiler.as_deref().and_then(Family::of);
+    opts.arch = Target::parse(triple)?.dub_arch(family)?;
+    Ok(())
+}
+
+/// Runs `dub build`, re-rendering compiler diagnostics rustc-style and
+/// closing with Cargo's error/warning summary.
+fn execute_build(dub: &impl DubCommand, opts: &DubOptions) -> Result<Outcome> {
+    let base = env::current_dir()?;
+    let mut cmd = dub_command(dub, "build", opts)?;
+    let started = Instant::now();
+    let mut child = exec::spawn(cmd.stdout(Stdio::piped()).stderr(Stdio::piped()))?;
+    let mut counts = Counts::default();
+    relay_diagnostics(&mut child, true, |diagnostic| {
+        counts.add(&diagnostic);
+        eprintln!("{}", diagnostics::render(&diagnostic, &base));
+        Ok(())
+    })?;
+    let outcome = exec::wait(&mut child, started)?;
+    if let Some(summary) = counts.summary(&package_name(&base, opts)) {
+        eprintln!("{summary}");
     }
-    build_dub_args(&mut cmd, &opts.options)?;
-    execute_command(cmd)
+    Ok(outcome)
 }
 
-fn execute_lint(dub: &impl DubCommand, opts: &LintOptions) -> Result<()> {
-    let mut cmd = dub.command();
-    cmd.arg("lint");
-    if let Some(package) = &opts.package {
-        cmd.arg(package);
+/// Name of the package being built, for summaries.
+fn package_name(base: &Path, opts: &DubOptions) -> String {
+    let dir = match &opts.root {
+        Some(root) => base.join(root),
+        None => base.to_path_buf(),
+    };
+    Manifest::find(&dir)
+        .and_then(|recipe| Manifest::from_path(&recipe).ok())
+        .map(|manifest| manifest.name)
+        .or_else(|| Some(dir.file_name()?.to_string_lossy().into_owned()))
+        .unwrap_or_else(|| "package".to_string())
+}
+
+/// Runs `dub build`, printing Cargo-style JSON messages on stdout and any
+/// other output on stderr.
+fn execute_build_json(dub: &impl DubCommand, opts: &DubOptions) -> Result<Outcome> {
+    build_messages(dub, opts, &mut io::stdout().lock())
+}
+
+fn build_messages(
+    dub: &impl DubCommand,
+    opts: &DubOptions,
+    out: &mut impl Write,
+) -> Result<Outcome> {
+    // Describe first so diagnostics can be attributed to their package.
+    let output = exec::run(&mut dub_command(dub, "describe", opts)?, Capture::Output)?;
+    if !output.success() {
+        eprint!("{}", output.stderr.unwrap_or_default());
+        return Err("`dub describe` failed".into());
     }
-    if opts.syntax_check {
-        cmd.arg("--syntax-check");
+    let description = Description::from_json(output.stdout.as_deref().unwrap_or_default())?;
+
+    let base = env::current_dir()?;
+    let started = Instant::now();
+    let mut child = exec::spawn(
+        dub_command(dub, "build", opts)?
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped()),
+    )?;
+    relay_diagnostics(&mut child, false, |diagnostic| {
+        let file = diagnostic.location.as_ref().map_or("", |l| l.file.as_str());
+        let Some(package) = description.package_for_file(file) else {
+            return Ok(());
+        };
+        let rendered = diagnostics::render(&diagnostic, &base);
+        let message = CompilerMessage::from_diagnostic(&diagnostic, rendered);
+        writeln!(
+            out,
+            "{}",
+            Message::compiler_message(package, message).to_json()
+        )?;
+        Ok(())
+    })?;
+    let outcome = exec::wait(&mut child, started)?;
+
+    if outcome.success() {
+        let build_type = description
+            .build_type
+            .clone()
+            .or_else(|| opts.build.clone())
+            .unwrap_or_else(|| "debug".to_string());
+        for package in description.active_packages() {
+            if let Some(message) = Message::artifact(package, &build_type) {
+                writeln!(out, "{}", message.to_json())?;
+            }
+        }
+    }
+    let finished = Message::BuildFinished {
+        success: outcome.success(),
+    };
+    writeln!(out, "{}", finished.to_json())?;
+    Ok(outcome)
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Stream {
+    Stdout,
+    Stderr,
+}
+
+/// Lines of a child's stdout and stderr, in the order they arrive.
+fn merged_lines(child: &mut Child) -> mpsc::Receiver<(Stream, String)> {
+    let (sender, receiver) = mpsc::channel();
+    fn forward(
+        stream: Stream,
+        reader: impl io::Read + Send + 'static,
+        sender: mpsc::Sender<(Stream, String)>,
+    ) {
+        thread::spawn(move || {
+            for line in BufReader::new(reader).lines().map_while(|l| l.ok()) {
+                if sender.send((stream, line)).is_err() {
+                    break;
+                }
+            }
+        });
+    }
+    if let Some(stdout) = child.stdout.take() {
+        forward(Stream::Stdout, stdout, sender.clone());
+    }
+    if let Some(stderr) = child.stderr.take() {
+        forward(Stream::Stderr, stderr, sender);
+    }
+    receiver
+}
+
+/// Hands complete diagnostics from a child's output to `on_diagnostic`.
+/// Other lines are echoed to the stream they came from, or all to stderr
+/// when stdout is reserved for machine-readable output.
+fn relay_diagnostics(
+    child: &mut Child,
+    keep_streams: bool,
+    mut on_diagnostic: impl FnMut(Diagnostic) -> Result<()>,
+) -> Result<()> {
+    let mut parser = diagnostics::Parser::new();
+    for (stream, line) in merged_lines(child) {
+        for event in parser.push(&line) {
+            match event {
+                Event::Diagnostic(diagnostic) => on_diagnostic(diagnostic)?,
+                Event::Other(line) if keep_streams && stream == Stream::Stdout => {
+                    println!("{line}")
+                }
+                Event::Other(line) => eprintln!("{line}"),
+            }
+        }
+    }
+    if let Some(Event::Diagnostic(diagnostic)) = parser.finish() {
+        on_diagnostic(diagnostic)?;
+    }
+    Ok(())
+}
+
+fn describe_command(dub: &impl DubCommand, opts: &DescribeOptions) -> Result<Command> {
+    let mut cmd = dub.command();
+    cmd.arg("describe");
+    if let Some(data) = &opts.data {
+        for d in data {
+            cmd.arg(format!("--data={d}"));
+        }
+    }
+    if opts.data_list {
+        cmd.arg("--data-list");
+    }
+    build_dub_args(&mut cmd, &opts.options)?;
+    Ok(cmd)
+}
+
+/// Runs `dub describe`, answering `--query` and `--table` from its parsed
+/// output and passing everything else through.
+fn execute_describe(dub: &impl DubCommand, opts: &DescribeOptions) -> Result<Outcome> {
+    if opts.query.is_none() && opts.table.is_none() {
+        return execute_command(describe_command(dub, opts)?);
+    }
+    describe_views(dub, opts, &mut io::stdout().lock())
+}
+
+fn describe_views(
+    dub: &impl DubCommand,
+    opts: &DescribeOptions,
+    out: &mut impl Write,
+) -> Result<Outcome> {
+    let output = exec::run(&mut describe_command(dub, opts)?, Capture::Output)?;
+    eprint!("{}", output.stderr.as_deref().unwrap_or_default());
+    if !output.success() {
+        return Ok(output);
+    }
+    // Queries see DUB's output as printed, including fields the typed
+    // model leaves out.
+    let document: serde_json::Value =
+        serde_json::from_str(output.stdout.as_deref().unwrap_or_default())?;
+    if let Some(path) = &opts.query {
+        for value in query::query(&document, path)? {
+            match value {
+                serde_json::Value::String(s) => writeln!(out, "{s}")?,
+                value => writeln!(out, "{}", serde_json::to_string_pretty(&value)?)?,
+            }
+        }
+    }
+    if let Some(table) = opts.table {
+        write!(out, "{}", Description::from_value(&document)?.table(table))?;
+    }
+    Ok(output)
+}
+
+fn add_remove_command(
+    dub: &impl DubCommand,
+    subcommand: &str,
+    opts: &AddRemoveOptions,
+) -> Result<Command> {
+    let mut cmd = dub.command();
+    cmd.arg(subcommand);
+    cmd.args(&opts.packages);
+    build_dub_args(&mut cmd, &opts.options)?;
+    Ok(cmd)
+}
+
+fn fetch_command(dub: &impl DubCommand, opts: &FetchOptions) -> Result<Command> {
+    let mut cmd = dub.command();
+    cmd.arg("fetch");
+    cmd.arg(&opts.package);
+    if let Some(cache) = &opts.cache {
+        cmd.arg(format!("--cache={cache}"));
+    }
+    build_dub_args(&mut cmd, &opts.options)?;
+    Ok(cmd)
+}
+
+fn init_command(dub: &impl DubCommand, opts: &InitOptions) -> Result<Command> {
+    let mut cmd = dub.command();
+    cmd.arg("init");
+    if let Some(dir) = &opts.directory {
+        cmd.arg(dir);
+    }
+    cmd.args(&opts.dependencies);
+    cmd.arg(format!(
+        "--type={}",
+        match opts.r#type {
+            ProjectType::Minimal => "minimal",
+            ProjectType::VibeD => "vibe.d",
+            ProjectType::Deimos => "deimos",
+            ProjectType::Custom => "custom",
+        }
+    ));
+    build_dub_args(&mut cmd, &opts.options)?;
+    Ok(cmd)
+}
+
+fn clean_command(dub: &impl DubCommand, opts: &CleanOptions) -> Result<Command> {
+    let mut cmd = dub.command();
+    cmd.arg("clean");
+    if let Some(package) = &opts.package {
+        cmd.arg(package);
+    }
+    if opts.all_packages {
+        cmd.arg("--all-packages");
+    }
+    build_dub_args(&mut cmd, &opts.options)?;
+    Ok(cmd)
+}
+
+fn lint_command(dub: &impl DubCommand, opts: &LintOptions) -> Result<Command> {
+    let mut cmd = dub.command();
+    cmd.arg("lint");
+    if let Some(package) = &opts.package {
+        cmd.arg(package);
+    }
+    if opts.syntax_check {
+        cmd.arg("--syntax-check");
     }
     if opts.style_check {
         cmd.arg("--style-check");
@@ -393,11 +1372,1162 @@ fn execute_lint(dub: &impl DubCommand, opts: &LintOptions) -> Result<()> {
         cmd.arg(format!("--dscanner-config={config}"));
     }
     build_dub_args(&mut cmd, &opts.options)?;
-    execute_command(cmd)
+    Ok(cmd)
+}
+
+/// The runner `dub test` will use for the package in `dir`, with the path
+/// of the generated runner when cargo-dub provides it.
+fn test_plan(dir: &Path, opts: &TestOptions) -> (Runner, Option<PathBuf>) {
+    let manifest = Manifest::find(dir).and_then(|recipe| Manifest::from_path(&recipe).ok());
+    let runner = manifest.as_ref().map_or(Runner::Default, Runner::detect);
+    match (runner, opts.runner, &manifest) {
+        (Runner::Default, TestRunner::Auto, Some(_)) => {
+            let path = dir
+                .join(".dub")
+                .join("cargo-dub")
+                .join("cargo_dub_test_runner.d");
+            (Runner::CargoDub, Some(path))
+        }
+        _ => (runner, None),
+    }
+}
+
+/// Options describing the configuration `dub test` builds: the one given
+/// with `--config`, else `unittest` when the recipe has it.
+fn test_describe_options(dir: &Path, opts: &TestOptions) -> DubOptions {
+    let mut options = opts.options.clone();
+    if options.config.is_none() {
+        let manifest = Manifest::find(dir).and_then(|recipe| Manifest::from_path(&recipe).ok());
+        if manifest.is_some_and(|m| m.configuration("unittest").is_some()) {
+            options.config = Some("unittest".to_string());
+        }
+    }
+    options
+}
+
+/// Source of the generated runner, covering the modules `dub describe`
+/// lists for the test configuration.
+fn generated_runner(dub: &impl DubCommand, dir: &Path, opts: &TestOptions) -> Result<String> {
+    let options = test_describe_options(dir, opts);
+    let output = exec::run(
+        &mut dub_command(dub, "describe", &options)?,
+        Capture::Output,
+    )?;
+    if !output.success() {
+        eprint!("{}", output.stderr.unwrap_or_default());
+        return Err("`dub describe` failed".into());
+    }
+    let description = Description::from_json(output.stdout.as_deref().unwrap_or_default())?;
+    let root = description
+        .root()
+        .ok_or("`dub describe` did not list the root package")?;
+    Ok(unittest::runner_source(&unittest::package_modules(root)?))
+}
+
+fn test_command(
+    dub: &impl DubCommand,
+    opts: &TestOptions,
+    runner: Runner,
+    main_file: Option<&Path>,
+) -> Result<Command> {
+    let mut cmd = dub_command(dub, "test", &opts.options)?;
+    if let Some(main_file) = main_file {
+        cmd.arg(format!("--main-file={}", main_file.display()));
+    }
+    let runner_args = runner.filter_args(&opts.filters);
+    if !runner_args.is_empty() {
+        cmd.arg("--").args(runner_args);
+    }
+    Ok(cmd)
+}
+
+fn execute_test(dub: &impl DubCommand, opts: &TestOptions) -> Result<Outcome> {
+    let base = env::current_dir()?;
+    let dir = match &opts.options.root {
+        Some(root) => base.join(root),
+        None => base.clone(),
+    };
+    let (runner, main_file) = test_plan(&dir, opts);
+    if let Some(path) = &main_file {
+        let source = generated_runner(dub, &dir, opts)?;
+        fs::create_dir_all(path.parent().expect("runner path has a parent"))?;
+        fs::write(path, source)?;
+    }
+    if runner == Runner::Default && !opts.filters.is_empty() {
+        eprintln!(
+            "warning: druntime's test runner cannot select tests; \
+             running all modules and reporting the matching ones"
+        );
+    }
+
+    let mut cmd = test_command(dub, opts, runner, main_file.as_deref())?;
+    // A report on stdout takes it over; everything else moves to stderr.
+    let stdout_reserved = opts.report.iter().any(|spec| spec.path.is_none());
+    let started = Instant::now();
+    let mut child = exec::spawn(cmd.stdout(Stdio::piped()).stderr(Stdio::piped()))?;
+    let mut parser = unittest::Parser::new(runner);
+    for (stream, line) in merged_lines(&mut child) {
+        if parser.push(&line) == LineKind::Other {
+            match stream {
+                Stream::Stdout if !stdout_reserved => println!("{line}"),
+                _ => eprintln!("{line}"),
+            }
+        }
+    }
+    let mut outcome = exec::wait(&mut child, started)?;
+    let report = parser.finish(&opts.filters);
+
+    let summary = report.libtest(started.elapsed());
+    if stdout_reserved {
+        eprint!("{summary}");
+    } else {
+        print!("{summary}");
+    }
+    let suite = package_name(&base, &opts.options);
+    for spec in &opts.report {
+        let text = report::render(spec.format, &report, &suite);
+        match &spec.path {
+            Some(path) => fs::write(path, text)
+                .map_err(|e| format!("Failed to write '{}': {e}", path.display()))?,
+            None => print!("{text}"),
+        }
+    }
+
+    // Like libtest, failing tests exit with 101 even if the runner did not.
+    if outcome.success() && !report.success() {
+        outcome.code = Some(101);
+    }
+    Ok(outcome)
+}
+
+/// Directory `coverage` has druntime write its listings to, so clearing
+/// stale ones never touches `.lst` files belonging to the package.
+fn coverage_dir(dir: &Path) -> PathBuf {
+    dir.join(".dub").join("cargo-dub").join("coverage")
+}
+
+fn coverage_command(
+    dub: &impl DubCommand,
+    opts: &CoverageOptions,
+    listings: &Path,
+) -> Result<Command> {
+    // Build types from Cargo.toml metadata usually lack -cov; custom
+    // coverage build types such as unittest-cov-ctfe are kept.
+    let mut options = opts.options.clone();
+    if !options.build.as_deref().is_some_and(|b| b.contains("cov")) {
+        options.build = Some("unittest-cov".to_string());
+    }
+    let mut cmd = dub_command(dub, "test", &options)?;
+    cmd.arg("--")
+        .arg(format!("--DRT-covopt=dstpath:{}", listings.display()));
+    Ok(cmd)
+}
+
+fn execute_coverage(dub: &impl DubCommand, opts: &CoverageOptions) -> Result<Outcome> {
+    let base = env::current_dir()?;
+    let dir = match &opts.options.root {
+        Some(root) => base.join(root),
+        None => base.clone(),
+    };
+    // Listings of modules that no longer exist would be merged in.
+    let listings = coverage_dir(&dir);
+    if listings.exists() {
+        fs::remove_dir_all(&listings)?;
+    }
+    fs::create_dir_all(&listings)?;
+
+    let mut cmd = coverage_command(dub, opts, &listings)?;
+    let stdout_reserved = opts.report.iter().any(|spec| spec.path.is_none());
+    let started = Instant::now();
+    let mut child = exec::spawn(cmd.stdout(Stdio::piped()).stderr(Stdio::piped()))?;
+    for (stream, line) in merged_lines(&mut child) {
+        match stream {
+            Stream::Stdout if !stdout_reserved => println!("{line}"),
+            _ => eprintln!("{line}"),
+        }
+    }
+    let outcome = exec::wait(&mut child, started)?;
+
+    let coverage = Coverage::collect(&listings)?;
+    if coverage.files.is_empty() {
+        eprintln!(
+            "warning: no coverage listings were written; \
+             the build type must pass -cov (dmd, ldc2)"
+        );
+    }
+    let summary = coverage.summary();
+    if stdout_reserved {
+        eprint!("\n{summary}");
+    } else {
+        print!("\n{summary}");
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis() as u64);
+    for spec in &opts.report {
+        let text = match spec.format {
+            CoverageFormat::Lcov => coverage.lcov(),
+            CoverageFormat::Cobertura => coverage.cobertura(&dir, timestamp),
+        };
+        match &spec.path {
+            Some(path) => fs::write(path, text)
+                .map_err(|e| format!("Failed to write '{}': {e}", path.display()))?,
+            None => print!("{text}"),
+        }
+    }
+
+    if !outcome.success() {
+        return Ok(outcome);
+    }
+    if let Some(threshold) = opts.fail_under {
+        let percent = coverage.percent();
+        if percent < threshold {
+            return Err(
+                format!("Coverage of {percent:.2}% is below --fail-under {threshold}%").into(),
+            );
+        }
+    }
+    Ok(outcome)
+}
+
+fn watch_command(dub: &impl DubCommand, opts: &WatchOptions) -> Result<Command> {
+    let mut cmd = dub_command(dub, opts.command.as_str(), &opts.options)?;
+    // Own process group, so stopping `dub run` also stops the program.
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+    Ok(cmd)
+}
+
+/// Stops a watched command and everything it started, giving it a moment
+/// to exit cleanly first.
+/// Nothing is signalled once the child has been reaped, as its process
+/// group ID may then belong to someone else.
+fn stop(child: &mut Child) -> io::Result<()> {
+    if child.try_wait()?.is_some() {
+        return Ok(());
+    }
+    #[cfg(unix)]
+    {
+        let group = -(child.id() as libc::pid_t);
+        // SAFETY: kill(2) takes no pointers.
+        unsafe { libc::kill(group, libc::SIGTERM) };
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if child.try_wait()?.is_some() {
+                return Ok(());
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        // SAFETY: as above; the child still runs, so the group is its own.
+        unsafe { libc::kill(group, libc::SIGKILL) };
+    }
+    child.kill()?;
+    child.wait().map(drop)
+}
+
+/// Signal asking `watch` to exit, recorded by [`on_signal`].
+#[cfg(unix)]
+static INTERRUPTED: std::sync::atomic::AtomicI32 = std::sync::atomic::AtomicI32::new(0);
+
+#[cfg(unix)]
+extern "C" fn on_signal(signal: libc::c_int) {
+    INTERRUPTED.store(signal, std::sync::atomic::Ordering::SeqCst);
+}
+
+/// How `watch` shares the terminal with the command it reruns. On Unix,
+/// SIGINT, SIGTERM and SIGHUP are recorded instead of ending cargo-dub, so
+/// the command's process group can be stopped first, and that group is made
+/// the terminal's foreground while it runs so the program can read stdin.
+struct WatchSession {
+    /// Whether cargo-dub started in the foreground of a terminal on stdin.
+    terminal: bool,
+}
+
+impl WatchSession {
+    fn start() -> Self {
+        #[cfg(unix)]
+        // SAFETY: the handler only stores to an atomic, and the signal set
+        // is initialized by sigemptyset(3) before use.
+        unsafe {
+            for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+                libc::signal(signal, on_signal as *const () as libc::sighandler_t);
+            }
+            // Handing the terminal back from a background group raises
+            // SIGTTOU unless it is blocked; children start with an empty mask.
+            let mut set = std::mem::MaybeUninit::<libc::sigset_t>::uninit();
+            libc::sigemptyset(set.as_mut_ptr());
+            libc::sigaddset(set.as_mut_ptr(), libc::SIGTTOU);
+            libc::pthread_sigmask(libc::SIG_BLOCK, set.as_ptr(), std::ptr::null_mut());
+            let terminal = libc::isatty(libc::STDIN_FILENO) == 1
+                && libc::tcgetpgrp(libc::STDIN_FILENO) == libc::getpgrp();
+            Self { terminal }
+        }
+        #[cfg(not(unix))]
+        Self { terminal: false }
+    }
+
+    /// The signal that asked cargo-dub to exit, if any.
+    fn interrupted(&self) -> Option<i32> {
+        #[cfg(unix)]
+        {
+            let signal = INTERRUPTED.load(std::sync::atomic::Ordering::SeqCst);
+            (signal != 0).then_some(signal)
+        }
+        #[cfg(not(unix))]
+        None
+    }
+
+    /// Gives the terminal to the process group of `child`, or takes it
+    /// back for cargo-dub with `None`.
+    fn foreground(&self, child: Option<&Child>) {
+        #[cfg(unix)]
+        if self.terminal {
+            let group = match child {
+                Some(child) => child.id() as libc::pid_t,
+                // SAFETY: getpgrp(2) cannot fail.
+                None => unsafe { libc::getpgrp() },
+            };
+            // SAFETY: tcsetpgrp(3) takes no pointers; failures, such as a
+            // group that already exited, leave the terminal where it was.
+            unsafe { libc::tcsetpgrp(libc::STDIN_FILENO, group) };
+        }
+        #[cfg(not(unix))]
+        let _ = child;
+    }
+}
+
+impl Drop for WatchSession {
+    fn drop(&mut self) {
+        self.foreground(None);
+    }
+}
+
+fn execute_watch(dub: &impl DubCommand, opts: &WatchOptions) -> Result<Outcome> {
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    let base = env::current_dir()?;
+    let dir = match &opts.options.root {
+        Some(root) => base.join(root),
+        None => base.clone(),
+    };
+    let watcher_for = |dir: &Path| -> Result<watch::Watcher> {
+        let recipe = Manifest::find(dir)
+            .ok_or_else(|| format!("No dub.json or dub.sdl found in '{}'", dir.display()))?;
+        let manifest = Manifest::from_path(&recipe)?;
+        Ok(watch::Watcher::new(
+            watch::watched_paths(dir, &recipe, &manifest),
+            Duration::from_millis(opts.debounce),
+        ))
+    };
+    let mut watcher = watcher_for(&dir)?;
+    let session = WatchSession::start();
+    let start = |changed: Option<&[PathBuf]>| -> Result<(Child, Instant)> {
+        if opts.clear {
+            print!("\x1b[2J\x1b[H");
+        }
+        if let Some(changed) = changed {
+            let first = changed
+                .first()
+                .map(|path| path.strip_prefix(&dir).unwrap_or(path));
+            match (first, changed.len()) {
+                (Some(path), 1) => eprintln!("[Change detected: {}]", path.display()),
+                (Some(path), n) => {
+                    eprintln!("[Change detected: {} and {} more]", path.display(), n - 1)
+                }
+                (None, _) => {}
+            }
+        }
+        eprintln!("[Running 'dub {}']", opts.command.as_str());
+        let started = Instant::now();
+        let child = exec::spawn(&mut watch_command(dub, opts)?)?;
+        session.foreground(Some(&child));
+        Ok((child, started))
+    };
+
+    let mut child = Some(start(None)?);
+    loop {
+        thread::sleep(POLL_INTERVAL);
+        if let Some(signal) = session.interrupted() {
+            if let Some((mut running, _)) = child.take() {
+                stop(&mut running)?;
+            }
+            return Ok(Outcome {
+                code: None,
+                signal: Some(signal),
+                ..Outcome::SUCCESS
+            });
+        }
+        if let Some((running, started)) = &mut child {
+            if let Some(status) = running.try_wait()? {
+                session.foreground(None);
+                let outcome = Outcome::from_status(status, started.elapsed());
+                eprintln!("[Finished running: {outcome}]");
+                // Ctrl-C went to the command's group, which had the terminal.
+                #[cfg(unix)]
+                if outcome.signal == Some(libc::SIGINT) {
+                    stop(running)?;
+                    return Ok(outcome);
+                }
+                child = None;
+            }
+        }
+        let Some(changed) = watcher.poll(Instant::now()) else {
+            continue;
+        };
+        if let Some((mut running, _)) = child.take() {
+            session.foreground(None);
+            stop(&mut running)?;
+        }
+        // A changed recipe may watch different paths. One saved halfway
+        // through an edit keeps the previous ones until it parses again.
+        if changed
+            .iter()
+            .any(|path| manifest::Format::from_path(path).is_some())
+        {
+            match watcher_for(&dir) {
+                Ok(updated) => watcher = updated,
+                Err(e) => eprintln!("[Keeping the previous watch paths: {e}]"),
+            }
+        }
+        child = Some(start(Some(&changed))?);
+    }
+}
+
+fn execute_tree(dub: &impl DubCommand, opts: &TreeOptions) -> Result<Outcome> {
+    dependency_tree(dub, opts, &mut io::stdout().lock())
+}
+
+/// Prints the dependency graph from `dub describe`, with sources from the
+/// package's `dub.selections.json`.
+fn dependency_tree(
+    dub: &impl DubCommand,
+    opts: &TreeOptions,
+    out: &mut impl Write,
+) -> Result<Outcome> {
+    let output = exec::run(
+        &mut dub_command(dub, "describe", &opts.options)?,
+        Capture::Output,
+    )?;
+    eprint!("{}", output.stderr.as_deref().unwrap_or_default());
+    if !output.success() {
+        return Ok(output);
+    }
+    let description = Description::from_json(output.stdout.as_deref().unwrap_or_default())?;
+    let selections = Selections::load(&package_dir(&opts.options)?)?;
+    let graph = Graph::new(&description, selections.as_ref());
+
+    match opts.output_format {
+        TreeFormat::Dot => write!(out, "{}", graph.dot())?,
+        TreeFormat::Json => writeln!(out, "{}", graph.to_json())?,
+        TreeFormat::Text if opts.duplicates => {
+            for (i, name) in graph.duplicates().into_iter().enumerate() {
+                if i > 0 {
+                    writeln!(out)?;
+                }
+                write!(out, "{}", graph.tree(name, true, opts.depth, &opts.format))?;
+            }
+        }
+        TreeFormat::Text => {
+            let (start, invert) = match &opts.invert {
+                Some(name) if !graph.contains(name) => {
+                    return Err(format!("Package '{name}' is not in the dependency graph").into())
+                }
+                Some(name) => (name.as_str(), true),
+                None => (graph.root.as_str(), false),
+            };
+            write!(
+                out,
+                "{}",
+                graph.tree(start, invert, opts.depth, &opts.format)
+            )?;
+        }
+    }
+    Ok(output)
+}
+
+/// Directory of the D package the options refer to.
+fn package_dir(opts: &DubOptions) -> Result<PathBuf> {
+    let base = env::current_dir()?;
+    Ok(match &opts.root {
+        Some(root) => base.join(root),
+        None => base,
+    })
+}
+
+fn execute_lock(command: &LockCommand) -> Result<()> {
+    let changes = lock_diff(command)?;
+    if changes.is_empty() {
+        println!("No changes to {}", selections::FILE_NAME);
+    }
+    for change in changes {
+        println!("{change}");
+    }
+    Ok(())
+}
+
+fn lock_diff(command: &LockCommand) -> Result<Vec<selections::Change>> {
+    let LockCommand::Diff { rev, to, options } = command;
+    let dir = package_dir(options)?;
+    let old = git_selections(&dir, rev)?;
+    let new = match to {
+        Some(rev) => git_selections(&dir, rev)?,
+        None => Selections::load(&dir)?.unwrap_or_default(),
+    };
+    Ok(selections::diff(&old, &new))
+}
+
+/// The lockfile of the package in `dir` as committed at `rev`.
+fn git_selections(dir: &Path, rev: &str) -> Result<Selections> {
+    let spec = format!("{rev}:./{}", selections::FILE_NAME);
+    let mut git = Command::new("git");
+    git.args(["show", &spec]).current_dir(dir);
+    let output = exec::run(&mut git, Capture::Output)?;
+    if !output.success() {
+        let stderr = output.stderr.unwrap_or_default();
+        return Err(format!("`git show {spec}` failed: {}", stderr.trim()).into());
+    }
+    Ok(Selections::from_json_str(
+        output.stdout.as_deref().unwrap_or_default(),
+    )?)
+}
+
+/// Copies every registry and repository dependency of the package into the
+/// vendor directory, then points DUB at the copies. Packages vendored by an
+/// earlier run are verified against their checksums instead.
+fn execute_vendor(dub: &impl DubCommand, opts: &VendorOptions) -> Result<Outcome> {
+    let output = exec::run(
+        &mut dub_command(dub, "describe", &opts.options)?,
+        Capture::Output,
+    )?;
+    eprint!("{}", output.stderr.as_deref().unwrap_or_default());
+    if !output.success() {
+        return Ok(output);
+    }
+    let description = Description::from_json(output.stdout.as_deref().unwrap_or_default())?;
+    let dir = package_dir(&opts.options)?;
+    let mut locked = Selections::load(&dir)?
+        .ok_or_else(|| format!("No {} in '{}'", selections::FILE_NAME, dir.display()))?;
+    let vendor_dir = dir.join(&opts.dir);
+    let relative = |name: &str| format!("{}/{name}", opts.dir.display()).replace('\\', "/");
+
+    let mut vendored = Vec::new();
+    for (name, selection) in &locked.versions {
+        let dest = vendor_dir.join(name);
+        let existing = Checksum::load(&dest)?;
+        let version = match (selection, &existing) {
+            (Selection::Path(path), Some(checksum))
+                if path.trim_start_matches("./") == relative(name) =>
+            {
+                checksum.version.clone()
+            }
+            // Local packages are already part of the repository.
+            (Selection::Path(_), _) => continue,
+            (selection, _) => selection.version().unwrap_or_default().to_string(),
+        };
+        match existing.filter(|checksum| checksum.version == version) {
+            Some(checksum) => {
+                check_vendored(name, &dest, &checksum)?;
+                eprintln!("{:>12} {name} v{version}", "Fresh");
+            }
+            None => {
+                let source = package_source(&description, name).ok_or_else(|| {
+                    format!("Package '{name}' is missing from `dub describe`; is it fetched?")
+                })?;
+                vendor::vendor(&source, &dest, name, &version)?;
+                eprintln!("{:>12} {name} v{version}", "Vendoring");
+            }
+        }
+        vendored.push((name.clone(), version, dest));
+    }
+
+    // Drop packages vendored earlier that are no longer selected.
+    if let Ok(entries) = fs::read_dir(&vendor_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().into_owned();
+            let stale = !vendored.iter().any(|(vendored, ..)| *vendored == name);
+            if stale && Checksum::load(&entry.path())?.is_some() {
+                fs::remove_dir_all(entry.path())?;
+                eprintln!("{:>12} {name}", "Removing");
+            }
+        }
+    }
+
+    match opts.override_with {
+        VendorOverride::Selections => {
+            for (name, ..) in &vendored {
+                locked
+                    .versions
+                    .insert(name.clone(), Selection::Path(relative(name)));
+            }
+            locked.save(&dir)?;
+        }
+        VendorOverride::LocalPackages => {
+            let packages = dir.join(".dub").join("packages");
+            fs::create_dir_all(&packages)?;
+            let path = packages.join("local-packages.json");
+            fs::write(&path, vendor::local_packages_json(&vendored))?;
+            eprintln!(
+                "{:>12} vendored packages in {}",
+                "Registered",
+                path.display()
+            );
+        }
+    }
+    Ok(Outcome::SUCCESS)
+}
+
+/// Root directory of `package` in the DUB cache. Sub-packages live inside
+/// their parent, which is the first enclosing directory named after it.
+fn package_source(description: &Description, package: &str) -> Option<PathBuf> {
+    if let Some(found) = description.package(package) {
+        return Some(found.path.clone());
+    }
+    let prefix = format!("{package}:");
+    let sub = description
+        .packages
+        .iter()
+        .find(|p| p.name.starts_with(&prefix))?;
+    sub.path
+        .ancestors()
+        .find(|dir| dir.file_name().is_some_and(|name| name == package))
+        .map(Path::to_path_buf)
+}
+
+fn check_vendored(name: &str, dir: &Path, checksum: &Checksum) -> Result<()> {
+    let differences = vendor::verify(dir, checksum)?;
+    if differences.is_empty() {
+        return Ok(());
+    }
+    let mut message = format!("Vendored package '{name}' does not match its checksums:");
+    for difference in differences {
+        message.push_str(&format!("\n  {difference}"));
+    }
+    message.push_str(&format!(
+        "\nRestore it, or delete '{}' to vendor it again",
+        dir.display()
+    ));
+    Err(message.into())
+}
+
+/// `vendor --verify`: checks every vendored package without running DUB.
+fn verify_vendored(opts: &VendorOptions) -> Result<()> {
+    let vendor_dir = package_dir(&opts.options)?.join(&opts.dir);
+    let mut entries: Vec<PathBuf> = fs::read_dir(&vendor_dir)
+        .map_err(|e| format!("Failed to read '{}': {e}", vendor_dir.display()))?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    let mut failed = Vec::new();
+    for dir in entries {
+        let Some(checksum) = Checksum::load(&dir)? else {
+            continue;
+        };
+        let name = checksum.package.clone();
+        match check_vendored(&name, &dir, &checksum) {
+            Ok(()) => eprintln!("{:>12} {name} v{}", "Verified", checksum.version),
+            Err(e) => {
+                eprintln!("{e}");
+                failed.push(name);
+            }
+        }
+    }
+    if !failed.is_empty() {
+        return Err(format!(
+            "{} vendored package(s) modified: {}",
+            failed.len(),
+            failed.join(", ")
+        )
+        .into());
+    }
+    Ok(())
+}
+
+/// A client for `registry`, else the one configured in Cargo.toml metadata,
+/// else the public registry. Responses are cached per user.
+fn registry_client(registry: Option<&str>) -> Result<registry::Client> {
+    let base = match registry {
+        Some(url) => url.to_string(),
+        None => config::load(&env::current_dir()?)?
+            .registry
+            .unwrap_or_else(|| registry::DEFAULT_URL.to_string()),
+    };
+    let client = registry::Client::new(&base);
+    let cache = env::var_os("XDG_CACHE_HOME")
+        .or_else(|| env::var_os("LOCALAPPDATA"))
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(env::var_os("HOME")?).join(".cache")));
+    Ok(match cache {
+        Some(dir) => client.with_cache(dir.join("cargo-dub").join("registry")),
+        None => client,
+    })
+}
+
+/// Lists matching packages like `cargo search`:
+/// `mir-core = "1.7.1"    # Base software building blocks`.
+fn search(client: &registry::Client, opts: &SearchOptions, out: &mut impl Write) -> Result<()> {
+    let found = client.search(&opts.query)?;
+    if found.is_empty() {
+        writeln!(out, "No packages match '{}'", opts.query)?;
+        return Ok(());
+    }
+    let shown = &found[..found.len().min(opts.limit)];
+    let entries: Vec<String> = shown
+        .iter()
+        .map(|p| format!("{} = \"{}\"", p.name, p.version))
+        .collect();
+    let width = entries.iter().map(String::len).max().unwrap_or_default();
+    for (entry, package) in entries.iter().zip(shown) {
+        let description = package.description.lines().next().unwrap_or_default();
+        writeln!(out, "{entry:width$}    # {description}")?;
+    }
+    if found.len() > shown.len() {
+        writeln!(
+            out,
+            "... and {} packages more (use --limit N to see more)",
+            found.len() - shown.len()
+        )?;
+    }
+    Ok(())
+}
+
+/// Prints what the registry knows about a package, for its latest release
+/// or the version given as `PACKAGE@VERSION`.
+fn package_info(client: &registry::Client, opts: &InfoOptions, out: &mut impl Write) -> Result<()> {
+    let (name, version) = match opts.package.split_once('@') {
+        Some((name, version)) => (name, Some(version)),
+        None => (opts.package.as_str(), None),
+    };
+    let info = client.info(name)?;
+    let release = match version {
+        Some(version) => info
+            .version(version)
+            .ok_or_else(|| format!("Package '{name}' has no version {version}"))?,
+        None => info
+            .latest()
+            .or(info.versions.first())
+            .ok_or_else(|| format!("Package '{name}' has no versions"))?,
+    };
+
+    writeln!(out, "{}", info.name)?;
+    let description = release
+        .extra
+        .get("description")
+        .and_then(serde_json::Value::as_str)
+        .unwrap_or(info.description());
+    if !description.is_empty() {
+        writeln!(out, "{description}")?;
+    }
+    writeln!(out, "version: {}", release.version)?;
+    if let Some(license) = release.license() {
+        writeln!(out, "license: {license}")?;
+    }
+    if let Some(repository) = info.repository_url() {
+        writeln!(out, "repository: {repository}")?;
+    }
+    let configurations = release.configurations();
+    if !configurations.is_empty() {
+        writeln!(out, "configurations: {}", configurations.join(", "))?;
+    }
+    let dependencies = release.requirements();
+    if !dependencies.is_empty() {
+        writeln!(out, "dependencies:")?;
+        for (dependency, requirement) in dependencies {
+            writeln!(out, "  {dependency} {requirement}")?;
+        }
+    }
+
+    // Releases newest first, then branches.
+    let mut releases: Vec<(Version, &str)> = info
+        .versions
+        .iter()
+        .filter_map(|v| Some((v.version.parse().ok()?, v.version.as_str())))
+        .collect();
+    releases.sort_by(|a, b| b.0.cmp(&a.0));
+    let versions: Vec<&str> = releases
+        .into_iter()
+        .map(|(_, version)| version)
+        .chain(
+            info.versions
+                .iter()
+                .map(|v| v.version.as_str())
+                .filter(|v| v.starts_with('~')),
+        )
+        .collect();
+    const SHOWN: usize = 10;
+    let mut line = versions[..versions.len().min(SHOWN)].join(", ");
+    if versions.len() > SHOWN {
+        line.push_str(&format!(" (+{} more)", versions.len() - SHOWN));
+    }
+    writeln!(out, "versions: {line}")?;
+    Ok(())
+}
+
+/// Reports locked dependencies that have newer releases, honoring the root
+/// recipe's version requirements for the compatible column.
+fn outdated(client: &registry::Client, opts: &OutdatedOptions, out: &mut impl Write) -> Result<()> {
+    let dir = package_dir(&opts.options)?;
+    let locked = Selections::load(&dir)?.ok_or_else(|| {
+        format!(
+            "No {} in '{}'; run `cargo dub update` to create it",
+            selections::FILE_NAME,
+            dir.display()
+        )
+    })?;
+    let direct: BTreeMap<String, String> = match Manifest::find(&dir) {
+        Some(recipe) => Manifest::from_path(&recipe)?
+            .all_dependencies()
+            .filter_map(|d| Some((d.name.clone(), d.version.clone()?)))
+            .collect(),
+        None => BTreeMap::new(),
+    };
+    let names: Vec<&str> = locked
+        .versions
+        .iter()
+        .filter(|(_, selection)| matches!(selection, Selection::Version(_)))
+        .map(|(name, _)| name.as_str())
+        .collect();
+    let infos = if names.is_empty() {
+        BTreeMap::new()
+    } else {
+        client.infos(&names, false)?
+    };
+
+    let report = outdated::report(&locked, &direct, &infos);
+    match opts.format {
+        OutputFormat::Table if report.is_empty() => {
+            writeln!(out, "All dependencies are up to date")?
+        }
+        OutputFormat::Table => write!(out, "{}", outdated::table(&report))?,
+        OutputFormat::Json => writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?,
+    }
+    if opts.exit_code && !report.is_empty() {
+        return Err(match report.len() {
+            1 => "1 dependency is behind the registry".to_string(),
+            n => format!("{n} dependencies are behind the registry"),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+/// Reports advisories affecting the versions locked in dub.selections.json.
+/// Vulnerabilities fail the audit, informational advisories only when denied.
+fn audit(opts: &AuditOptions, out: &mut impl Write) -> Result<()> {
+    let db = match &opts.db {
+        Some(db) => db.clone(),
+        None => env::var_os("HOME")
+            .or_else(|| env::var_os("USERPROFILE"))
+            .map(|home| PathBuf::from(home).join(".dub").join("advisory-db"))
+            .ok_or("Cannot locate the advisory database; pass --db")?,
+    };
+    if !db.is_dir() {
+        return Err(format!(
+            "No advisory database at '{}'; clone one there or pass --db",
+            db.display()
+        )
+        .into());
+    }
+    let database = Database::open(&db)?;
+    let dir = package_dir(&opts.options)?;
+    let locked = Selections::load(&dir)?.ok_or_else(|| {
+        format!(
+            "No {} in '{}'; run `cargo dub update` to create it",
+            selections::FILE_NAME,
+            dir.display()
+        )
+    })?;
+    eprintln!(
+        "{:>12} {} security advisories (from {})",
+        "Loaded",
+        database.entries.len(),
+        db.display()
+    );
+    eprintln!(
+        "{:>12} {} for vulnerabilities ({} packages)",
+        "Scanning",
+        selections::FILE_NAME,
+        locked.versions.len()
+    );
+
+    let (warnings, vulnerabilities): (Vec<_>, Vec<_>) = database
+        .audit(&locked)
+        .into_iter()
+        .partition(|finding| finding.is_warning());
+    match opts.format {
+        OutputFormat::Table => {
+            for (i, finding) in vulnerabilities.iter().chain(&warnings).enumerate() {
+                if i > 0 {
+                    writeln!(out)?;
+                }
+                write!(out, "{finding}")?;
+            }
+        }
+        OutputFormat::Json => {
+            let report = serde_json::json!({
+                "database": {"path": db, "advisory-count": database.entries.len()},
+                "vulnerabilities": vulnerabilities,
+                "warnings": warnings,
+            });
+            writeln!(out, "{}", serde_json::to_string_pretty(&report)?)?;
+        }
+    }
+
+    let denied = warnings
+        .iter()
+        .filter(|finding| {
+            let kind = finding.advisory.informational;
+            kind.is_some_and(|kind| opts.deny.iter().any(|deny| deny.denies(kind)))
+        })
+        .count();
+    let plural =
+        |n: usize, one: &str, many: &str| format!("{n} {}", if n == 1 { one } else { many });
+    if !vulnerabilities.is_empty() {
+        let found = plural(vulnerabilities.len(), "vulnerability", "vulnerabilities");
+        return Err(format!("{found} found").into());
+    }
+    if denied > 0 {
+        return Err(format!(
+            "{} found",
+            plural(denied, "denied warning", "denied warnings")
+        )
+        .into());
+    }
+    if !warnings.is_empty() {
+        let found = plural(warnings.len(), "allowed warning", "allowed warnings");
+        eprintln!("warning: {found} found");
+    }
+    Ok(())
+}
+
+fn execute_registry(command: &RegistryCommand) -> Result<()> {
+    match command {
+        RegistryCommand::Serve { dir, addr } => {
+            if !dir.is_dir() {
+                return Err(format!(
+                    "No mirror at '{}'; populate it with `cargo dub registry sync`",
+                    dir.display()
+                )
+                .into());
+            }
+            let listener = std::net::TcpListener::bind(addr)?;
+            let addr = listener.local_addr()?;
+            eprintln!(
+                "{:>12} {} on http://{addr}/ (use --registry=http://{addr}/)",
+                "Serving",
+                dir.display()
+            );
+            Ok(registry::serve(&Mirror::new(dir), listener)?)
+        }
+        RegistryCommand::Sync {
+            packages,
+            dir,
+            upstream,
+            root,
+        } => {
+            for synced in registry_sync(packages, dir, upstream, root.as_deref())? {
+                let status = if synced.downloaded {
+                    "Mirrored"
+                } else {
+                    "Fresh"
+                };
+                eprintln!("{status:>12} {} v{}", synced.package, synced.version);
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Mirrors `packages` with their dependencies, or without any packages the
+/// registry versions locked in the package's dub.selections.json.
+fn registry_sync(
+    packages: &[String],
+    dir: &Path,
+    upstream: &str,
+    root: Option<&str>,
+) -> Result<Vec<registry::Synced>> {
+    let client = registry::Client::new(upstream);
+    let mirror = Mirror::new(dir);
+    if !packages.is_empty() {
+        let requests: Vec<(String, Option<String>)> = packages
+            .iter()
+            .map(|spec| match spec.split_once('@') {
+                Some((name, version)) => (name.to_string(), Some(version.to_string())),
+                None => (spec.clone(), None),
+            })
+            .collect();
+        return Ok(registry::sync(&client, &mirror, &requests, true)?);
+    }
+    let base = env::current_dir()?;
+    let package = root.map_or(base.clone(), |root| base.join(root));
+    let locked = Selections::load(&package)?.ok_or_else(|| {
+        format!(
+            "No {} in '{}'; name the packages to mirror",
+            selections::FILE_NAME,
+            package.display()
+        )
+    })?;
+    // The lockfile already lists every dependency.
+    let requests: Vec<(String, Option<String>)> = locked
+        .versions
+        .iter()
+        .filter_map(|(name, selection)| match selection {
+            Selection::Version(version) => Some((name.clone(), Some(version.clone()))),
+            _ => None,
+        })
+        .collect();
+    Ok(registry::sync(&client, &mirror, &requests, false)?)
+}
+
+fn update_command(dub: &impl DubCommand, opts: &UpdateOptions) -> Result<Command> {
+    let mut cmd = dub.command();
+    cmd.arg("upgrade");
+    match (&opts.package, &opts.precise) {
+        // The pinned selection is written directly; DUB fetches it.
+        (_, Some(_)) => {
+            cmd.arg("--missing-only");
+        }
+        (Some(package), None) => {
+            cmd.arg(package);
+        }
+        (None, None) => {}
+    }
+    build_dub_args(&mut cmd, &opts.options)?;
+    Ok(cmd)
+}
+
+/// Runs `dub upgrade`, reporting the changed selections like `cargo update`.
+fn execute_update(dub: &impl DubCommand, opts: &UpdateOptions) -> Result<Outcome> {
+    let dir = package_dir(&opts.options)?;
+    let before = Selections::load(&dir)?.unwrap_or_default();
+    let precise = opts.package.as_ref().zip(opts.precise.as_ref());
+    if let Some((package, version)) = precise {
+        let mut pinned = before.clone();
+        let name = selections::base_name(package);
+        let selection = match before.versions.get(name) {
+            None => {
+                return Err(format!("Package '{name}' is not in {}", selections::FILE_NAME).into())
+            }
+            Some(Selection::Path(path)) => {
+                return Err(format!("Package '{name}' is selected by path ({path})").into())
+            }
+            Some(Selection::Repository { repository, .. }) => Selection::Repository {
+                repository: repository.clone(),
+                version: version.clone(),
+            },
+            Some(Selection::Version(_)) => {
+                check_requirement(&dir, name, version)?;
+                Selection::Version(version.clone())
+            }
+        };
+        pinned.versions.insert(name.to_string(), selection);
+        pinned.save(&dir)?;
+    }
+    let outcome = execute_command(update_command(dub, opts)?);
+    // Like `cargo update --precise`, leave the lock file alone when DUB
+    // cannot resolve the pinned version.
+    if precise.is_some() && !outcome.as_ref().is_ok_and(Outcome::success) {
+        before.save(&dir)?;
+    }
+    let outcome = outcome?;
+    let after = Selections::load(&dir)?.unwrap_or_default();
+    for change in selections::diff(&before, &after) {
+        eprintln!("{change}");
+    }
+    Ok(outcome)
+}
+
+/// Fails when the recipe in `dir` requires a range of `package` that
+/// excludes `version`. Branches such as `~master` are not checked.
+fn check_requirement(dir: &Path, package: &str, version: &str) -> Result<()> {
+    let Some(recipe) = Manifest::find(dir) else {
+        return Ok(());
+    };
+    let manifest = Manifest::from_path(&recipe)?;
+    let Ok(version) = version.parse::<Version>() else {
+        return Ok(());
+    };
+    let requirements = manifest
+        .all_dependencies()
+        .filter(|d| selections::base_name(&d.name) == package)
+        .filter_map(|d| d.version.as_deref());
+    for requirement in requirements {
+        if !requirement.parse::<VersionReq>()?.matches(&version) {
+            return Err(format!(
+                "{package} {version} does not satisfy '{requirement}' required by '{}'",
+                recipe.display()
+            )
+            .into());
+        }
+    }
+    Ok(())
+}
+
+fn execute_why(dub: &impl DubCommand, opts: &WhyOptions) -> Result<Outcome> {
+    why(dub, opts, &mut io::stdout().lock())
+}
+
+/// Prints every chain of dependencies from the root package to the
+/// requested one, with the version each link asks for.
+fn why(dub: &impl DubCommand, opts: &WhyOptions, out: &mut impl Write) -> Result<Outcome> {
+    let output = exec::run(
+        &mut dub_command(dub, "describe", &opts.options)?,
+        Capture::Output,
+    )?;
+    eprint!("{}", output.stderr.as_deref().unwrap_or_default());
+    if !output.success() {
+        return Ok(output);
+    }
+    let description = Description::from_json(output.stdout.as_deref().unwrap_or_default())?;
+    let selections = Selections::load(&package_dir(&opts.options)?)?;
+    let graph = Graph::new(&description, selections.as_ref());
+    let name = &opts.package;
+    if !graph.contains(name) {
+        return Err(format!("Package '{name}' is not in the dependency graph").into());
+    }
+
+    let version = &graph.nodes[name].version;
+    match selections.as_ref().and_then(|s| s.get(name)) {
+        Some(selection) => writeln!(
+            out,
+            "{name} {version} is selected as {selection} in {}",
+            selections::FILE_NAME
+        )?,
+        None => writeln!(out, "{name} {version} is not in {}", selections::FILE_NAME)?,
+    }
+    // Requirements each package's recipe places on its dependencies.
+    let mut recipes = BTreeMap::new();
+    let mut requirement = |from: &str, to: &str| -> String {
+        let manifest = recipes.entry(from.to_string()).or_insert_with(|| {
+            let package = description.package(from)?;
+            Manifest::from_path(&Manifest::find(&package.path)?).ok()
+        });
+        let dependency = manifest
+            .as_ref()
+            .and_then(|m| m.all_dependencies().find(|d| d.name == to));
+        match dependency {
+            Some(Dependency {
+                path: Some(path), ..
+            }) => format!("path {path}"),
+            Some(Dependency {
+                version: Some(version),
+                ..
+            }) => version.clone(),
+            _ => "*".to_string(),
+        }
+    };
+    for chain in graph.chains(name) {
+        let mut line = chain[0].to_string();
+        for pair in chain.windows(2) {
+            line = format!("{line} -> {} ({})", pair[1], requirement(pair[0], pair[1]));
+        }
+        writeln!(out, "  {line}")?;
+    }
+    Ok(output)
 }
 
 fn build_dub_args(cmd: &mut Command, opts: &DubOptions) -> Result<()> {
-    if let Some(compiler) = opts.compiler.clone().or_else(|| env::var("DC").ok()) {
+    if let Some(compiler) = opts.compiler.clone() {
         cmd.arg(format!("--compiler={compiler}"));
     }
     if let Some(build) = &opts.build {
@@ -431,20 +2561,17 @@ fn build_dub_args(cmd: &mut Command, opts: &DubOptions) -> Result<()> {
     for config in &opts.override_config {
         cmd.arg(format!("--override-config={config}"));
     }
+    if let Some(registry) = &opts.registry {
+        cmd.arg(format!("--registry={registry}"));
+    }
+    if let Some(root) = &opts.root {
+        cmd.arg(format!("--root={root}"));
+    }
     Ok(())
 }
 
-fn execute_command(mut cmd: Command) -> Result<()> {
-    match cmd.status() {
-        Ok(status) => std::process::exit(status.code().unwrap_or(1)),
-        Err(e) => Err(match e.kind() {
-            io::ErrorKind::NotFound => "dub executable not found or not accessible",
-            io::ErrorKind::PermissionDenied => "Permission denied when executing dub",
-            io::ErrorKind::WouldBlock => "System resources temporarily unavailable",
-            _ => "Failed to execute dub",
-        }
-        .into()),
-    }
+fn execute_command(mut cmd: Command) -> Result<Outcome> {
+    Ok(exec::run(&mut cmd, Capture::Inherit)?)
 }
 
 #[cfg(test)]
@@ -454,26 +2581,164 @@ mod tests {
     use std::io::Write;
     use tempfile::TempDir;
 
-    // Mock DubExecutable for tests
-    struct MockDubExecutable {
-        path: String,
-    }
+    #[cfg(unix)]
+    use fake_dub::FakeDub;
+
+    /// A scripted stand-in for `dub`, so commands can be run end to end
+    /// through `run_with` without a D toolchain.
+    #[cfg(unix)]
+    mod fake_dub {
+        use super::*;
+        use std::collections::BTreeMap;
+        use std::os::unix::fs::PermissionsExt;
+        use std::rc::Rc;
 
-    impl MockDubExecutable {
-        fn new(path: &str) -> Self {
-            MockDubExecutable {
-                path: path.to_string(),
+        /// The script each fake writes to its directory. It answers
+        /// `--version` like DUB 1.36.0; any other call is appended to
+        /// `$FAKE_DUB_DIR/calls` and answered from the files under
+        /// `$FAKE_DUB_DIR/replies/<command>/`.
+        fn script() -> String {
+            let env: String = cargo_dub::invocation::RELEVANT_ENV
+                .iter()
+                .map(|name| {
+                    format!("  if value=$(printenv {name}); then echo \"env {name}=$value\"; fi\n")
+                })
+                .collect();
+            format!(
+                "#!/bin/sh\n\
+                     if [ \"$1\" = --version ]; then\n  \
+                       echo 'DUB version 1.36.0, built on Jan  1 2024'\n  exit 0\nfi\n\
+                     {{\n  echo call\n  echo \"cwd $PWD\"\n{env}  \
+                       for arg in \"$@\"; do echo \"arg $arg\"; done\n\
+                     }} >> \"$FAKE_DUB_DIR/calls\"\n\
+                     for arg in \"$@\"; do\n  \
+                       case \"$arg\" in -*) ;; *) command=$arg; break ;; esac\n\
+                     done\n\
+                     reply=\"$FAKE_DUB_DIR/replies/$command\"\n\
+                     [ -f \"$reply/stdout\" ] && cat \"$reply/stdout\"\n\
+                     [ -f \"$reply/stderr\" ] && cat \"$reply/stderr\" >&2\n\
+                     [ -f \"$reply/script\" ] && . \"$reply/script\"\n\
+                     [ -f \"$reply/code\" ] && exit \"$(cat \"$reply/code\")\"\n\
+                     exit 0\n"
+            )
+        }
+
+        /// One recorded invocation.
+        #[derive(Debug, Default)]
+        pub struct Call {
+            pub args: Vec<String>,
+            pub cwd: PathBuf,
+            /// The variables of `RELEVANT_ENV` that were set.
+            pub env: BTreeMap<String, String>,
+        }
+
+        /// A fake `dub` with its own call log and replies.
+        #[derive(Clone)]
+        pub struct FakeDub {
+            dir: Rc<TempDir>,
+        }
+
+        /// The canned answer to one DUB command; exits 0 with no output
+        /// unless configured otherwise.
+        pub struct Reply {
+            dir: PathBuf,
+        }
+
+        impl Reply {
+            pub fn stdout(self, text: &str) -> Self {
+                fs::write(self.dir.join("stdout"), text).unwrap();
+                self
+            }
+
+            pub fn stderr(self, text: &str) -> Self {
+                fs::write(self.dir.join("stderr"), text).unwrap();
+                self
+            }
+
+            /// Shell commands to run after printing, e.g. to write files.
+            pub fn script(self, script: &str) -> Self {
+                fs::write(self.dir.join("script"), script).unwrap();
+                self
+            }
+
+            pub fn exit(self, code: i32) -> Self {
+                fs::write(self.dir.join("code"), code.to_string()).unwrap();
+                self
             }
         }
-    }
 
-    impl DubCommand for MockDubExecutable {
-        fn command(&self) -> Command {
-            let mut cmd = Command::new(&self.path);
-            cmd.stdin(Stdio::null())
-                .stdout(Stdio::null())
-                .stderr(Stdio::null());
-            cmd
+        impl FakeDub {
+            pub fn new() -> Self {
+                let dir = TempDir::new().unwrap();
+                let path = dir.path().join("dub");
+                fs::write(&path, script()).unwrap();
+                fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+                // A process another test forks while the script is open for
+                // writing makes exec fail with ETXTBSY until it execs itself.
+                for _ in 0..100 {
+                    match Command::new(&path).arg("--version").output() {
+                        Err(e) if e.raw_os_error() == Some(libc::ETXTBSY) => {
+                            thread::sleep(Duration::from_millis(10))
+                        }
+                        result => {
+                            assert!(result.unwrap().status.success());
+                            break;
+                        }
+                    }
+                }
+                FakeDub { dir: Rc::new(dir) }
+            }
+
+            /// Configures the reply to `dub <command>`.
+            pub fn on(&self, command: &str) -> Reply {
+                let dir = self.dir.path().join("replies").join(command);
+                fs::create_dir_all(&dir).unwrap();
+                Reply { dir }
+            }
+
+            pub fn calls(&self) -> Vec<Call> {
+                let log = fs::read_to_string(self.dir.path().join("calls")).unwrap_or_default();
+                let mut calls: Vec<Call> = Vec::new();
+                for line in log.lines() {
+                    let (kind, value) = line.split_once(' ').unwrap_or((line, ""));
+                    match (kind, calls.last_mut()) {
+                        ("call", _) => calls.push(Call::default()),
+                        ("cwd", Some(call)) => call.cwd = PathBuf::from(value),
+                        ("env", Some(call)) => {
+                            let (name, value) = value.split_once('=').unwrap();
+                            call.env.insert(name.to_string(), value.to_string());
+                        }
+                        ("arg", Some(call)) => call.args.push(value.to_string()),
+                        _ => panic!("Unexpected line in the fake dub log: {line}"),
+                    }
+                }
+                calls
+            }
+
+            /// The arguments of each call so far, joined by spaces.
+            pub fn argv(&self) -> Vec<String> {
+                self.calls()
+                    .iter()
+                    .map(|call| call.args.join(" "))
+                    .collect()
+            }
+
+            /// Runs `cargo-dub ARGS...` against this fake.
+            pub fn run(&self, args: &[&str]) -> Result<Outcome> {
+                let cli = Cli::try_parse_from(["cargo-dub"].iter().chain(args))?;
+                run_with(cli, None, || Ok(self.clone()))
+            }
+        }
+
+        impl DubCommand for FakeDub {
+            fn command(&self) -> Command {
+                let mut cmd = Command::new(self.dir.path().join("dub"));
+                // `run` passes no `DC`; keep the ambient one out of the calls too.
+                cmd.env("FAKE_DUB_DIR", self.dir.path())
+                    .env_remove("DC")
+                    .stdin(Stdio::null());
+                cmd
+            }
         }
     }
 
@@ -494,6 +2759,8 @@ mod tests {
             override_config: vec!["conf1".to_string()],
             yes: true,
             non_interactive: false,
+            registry: Some("http://127.0.0.1:8080/".to_string()),
+            root: Some("dlib".to_string()),
         };
 
         let cmd = Command::new("dub");
@@ -520,30 +2787,454 @@ mod tests {
                 "--d-version=ver2",
                 "--debug=debug1",
                 "--override-config=conf1",
+                "--registry=http://127.0.0.1:8080/",
+                "--root=dlib",
             ]
         );
     }
 
     #[test]
-    fn test_build_dub_args_with_env_dc() {
-        env::set_var("DC", "dmd");
-        let opts = DubOptions::default();
-        let cmd = Command::new("dub");
-        let mut cmd = cmd;
-        build_dub_args(&mut cmd, &opts).unwrap();
+    fn test_apply_defaults() {
+        let defaults = DubConfig {
+            compiler: Some("ldc2".to_string()),
+            build_type: Some("release".to_string()),
+            config: Some("library".to_string()),
+            d_versions: vec!["UseRust".to_string()],
+            registry: Some("http://mirror:8080/".to_string()),
+            path: Some(PathBuf::from("/work/dlib")),
+            ..Default::default()
+        };
+        let mut opts = DubOptions {
+            compiler: Some("dmd".to_string()),
+            config: Some("unittest".to_string()),
+            ..Default::default()
+        };
+        opts.apply_defaults(Some("gdc".to_string()), &defaults);
+        assert_eq!(opts.compiler.as_deref(), Some("dmd"));
+        assert_eq!(opts.build.as_deref(), Some("release"));
+        assert_eq!(opts.config.as_deref(), Some("unittest"));
+        assert_eq!(opts.d_versions, vec!["UseRust"]);
+        assert_eq!(opts.registry.as_deref(), Some("http://mirror:8080/"));
+        assert_eq!(opts.root.as_deref(), Some("/work/dlib"));
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
+        for (dc, compiler) in [(Some("gdc"), "gdc"), (None, "ldc2")] {
+            let mut opts = DubOptions::default();
+            opts.apply_defaults(dc.map(str::to_string), &defaults);
+            assert_eq!(opts.compiler.as_deref(), Some(compiler));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_apply_pin() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp = TempDir::new().unwrap();
+        let ldc2 = temp.path().join("ldc2");
+        fs::write(
+            &ldc2,
+            "#!/bin/sh\necho 'LDC - the LLVM D compiler (1.36.0):'\necho '  based on DMD v2.106.1'\n",
+        )
+        .unwrap();
+        fs::set_permissions(&ldc2, fs::Permissions::from_mode(0o755)).unwrap();
+        let mut opts = DubOptions {
+            compiler: Some(ldc2.display().to_string()),
+            ..Default::default()
+        };
+        let dub = Version::new(1, 35, 0);
+        apply_pin(temp.path(), &mut opts, Some(&dub)).unwrap();
+
+        let pin = temp.path().join(toolchain::TOOLCHAIN_FILE);
+        fs::write(
+            &pin,
+            "[toolchain]\ncompiler = \"ldc\"\nversion = \"~>1.36\"\ndub = \">=1.30\"\n",
+        )
+        .unwrap();
+        apply_pin(temp.path(), &mut opts, Some(&dub)).unwrap();
+        assert_eq!(opts.compiler, Some(ldc2.display().to_string()));
+
+        fs::write(
+            &pin,
+            "[toolchain]\nfrontend = \">=2.107\"\ndub = \">=1.36\"\n",
+        )
+        .unwrap();
+        let err = apply_pin(temp.path(), &mut opts, Some(&dub)).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Toolchain does not satisfy '{}':\n  DUB 1.35.0 does not satisfy >=1.36.0\n  \
+                 ldc2 1.36.0 at '{}' does not satisfy any compiler (frontend >=2.107.0)",
+                pin.display(),
+                ldc2.display()
+            )
+        );
+
+        fs::write(
+            &pin,
+            "[toolchain]\nfrontend = \">=2.107\"\non-mismatch = \"warn\"\n",
+        )
+        .unwrap();
+        apply_pin(temp.path(), &mut opts, Some(&dub)).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_pin_found_from_root() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().display().to_string();
+        let pin = temp.path().join(toolchain::TOOLCHAIN_FILE);
+        fs::write(&pin, "[toolchain]\ncompiler = \"ldc\"\n").unwrap();
+        let dub = FakeDub::new();
+        for command in ["build", "test", "coverage"] {
+            let err = dub
+                .run(&[command, "--compiler=/nonexistent/ldc2", "--root", &root])
+                .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                format!(
+                    "Toolchain does not satisfy '{}':\n  \
+                     compiler '/nonexistent/ldc2' could not be probed",
+                    pin.display()
+                ),
+                "{command}"
+            );
+        }
+        assert!(dub.calls().is_empty());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_build_messages() {
+        let dub = FakeDub::new();
+        dub.on("describe").stdout(
+            r#"{"rootPackage": "app", "buildType": "debug", "packages": [
+            {"name": "app", "version": "1.0.0", "path": "/work/app/", "active": true,
+             "targetType": "executable", "targetPath": "bin", "targetFileName": "app"}]}"#,
+        );
+        dub.on("build")
+            .stdout("Compiling app...\n")
+            .stderr("source/app.d(3,5): Warning: unused\n");
+
+        let mut out = Vec::new();
+        let opts = DubOptions {
+            compiler: Some("dmd".to_string()),
+            ..Default::default()
+        };
+        let status = build_messages(&dub, &opts, &mut out).unwrap();
+        assert!(status.success());
+        assert_eq!(
+            dub.argv(),
+            ["describe --compiler=dmd", "build --compiler=dmd"]
+        );
+        let messages: Vec<serde_json::Value> = String::from_utf8(out)
+            .unwrap()
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        let reasons: Vec<&str> = messages
+            .iter()
+            .map(|m| m["reason"].as_str().unwrap())
             .collect();
-        assert_eq!(args, vec!["--compiler=dmd"]);
-        env::remove_var("DC");
+        assert_eq!(
+            reasons,
+            vec!["compiler-message", "compiler-artifact", "build-finished"]
+        );
+        assert_eq!(messages[0]["message"]["message"], "unused");
+        assert_eq!(messages[0]["target"]["kind"][0], "bin");
+        assert_eq!(messages[1]["executable"], "/work/app/bin/app");
+        assert_eq!(messages[2]["success"], true);
+
+        dub.on("describe").stderr("Error: no recipe\n").exit(2);
+        assert_eq!(
+            build_messages(&dub, &opts, &mut Vec::new())
+                .unwrap_err()
+                .to_string(),
+            "`dub describe` failed"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_test_report() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().display().to_string();
+        fs::write(
+            temp.path().join("dub.sdl"),
+            "name \"calc\"\nconfiguration \"unittest\" {\n\tdependency \"silly\" version=\"~>1.1\"\n}\n",
+        )
+        .unwrap();
+        let dub = FakeDub::new();
+        dub.on("test").stdout(
+            " ✓ calc.ops adds\n ✓ calc.ops subtracts\nSummary: 2 passed, 0 failed in 3 ms\n",
+        );
+
+        let junit = temp.path().join("junit.xml");
+        let report = format!("--report=junit={}", junit.display());
+        let args = ["test", "ops.", &report, "--compiler=dmd", "--root", &root];
+        assert!(dub.run(&args).unwrap().success());
+        assert_eq!(
+            dub.argv(),
+            [format!(
+                "test --compiler=dmd --root={root} -- --no-colours --include ops\\."
+            )]
+        );
+        let xml = fs::read_to_string(&junit).unwrap();
+        assert!(xml.contains("<testsuite name=\"calc\" tests=\"2\" failures=\"0\""));
+        assert!(xml.contains("<testcase name=\"calc.ops.adds\" classname=\"calc.ops\"/>"));
+
+        // Failing tests exit with 101 like libtest, even if dub did not.
+        dub.on("test")
+            .stdout(" ✗ calc.ops adds\nSummary: 0 passed, 1 failed in 3 ms\n");
+        assert_eq!(dub.run(&args).unwrap().exit_code(), 101);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_test_generated_runner() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().display().to_string();
+        fs::create_dir_all(temp.path().join("source/calc")).unwrap();
+        fs::create_dir(temp.path().join("tests")).unwrap();
+        fs::write(
+            temp.path().join("dub.sdl"),
+            "name \"calc\"\nconfiguration \"library\" {\n}\n\
+             configuration \"unittest\" {\n\tsourcePaths \"source\" \"tests\"\n\
+             \texcludedSourceFiles \"source/calc/slow.d\"\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("source/calc/ops.d"),
+            "module calc.ops;\nstruct Stack\n{\n    unittest {}\n}\n",
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("source/calc/slow.d"),
+            "module calc.slow;\n",
+        )
+        .unwrap();
+        fs::write(temp.path().join("tests/integration.d"), "").unwrap();
+        let dub = FakeDub::new();
+        // The unittest configuration adds tests/ and drops slow.d.
+        dub.on("describe").stdout(&format!(
+            r#"{{"rootPackage": "calc", "configuration": "unittest", "packages": [
+            {{"name": "calc", "path": "{root}/", "active": true,
+              "importPaths": ["source/", "tests/"], "files": [
+                {{"role": "source", "path": "source/calc/ops.d"}},
+                {{"role": "source", "path": "tests/integration.d"}}]}}]}}"#
+        ));
+        dub.on("test").stdout(
+            "##cargo-dub-test\tok\tcalc.ops\tStack.__unittest_L4_C5\t900\t\n\
+             ##cargo-dub-summary\t1\t0\t0\n",
+        );
+
+        let tap = temp.path().join("tests.tap");
+        let report = format!("--report=tap={}", tap.display());
+        dub.run(&["test", &report, "--compiler=dmd", "--root", &root])
+            .unwrap();
+        let main_file = temp.path().join(".dub/cargo-dub/cargo_dub_test_runner.d");
+        assert_eq!(
+            dub.calls()[0].args,
+            [
+                "describe",
+                "--compiler=dmd",
+                "--config=unittest",
+                &format!("--root={root}")
+            ]
+        );
+        assert!(dub.calls()[1]
+            .args
+            .contains(&format!("--main-file={}", main_file.display())));
+        let source = fs::read_to_string(&main_file).unwrap();
+        assert!(source.contains("import m0 = calc.ops;\nimport m1 = integration;\n"));
+        assert!(!source.contains("calc.slow"));
+        assert_eq!(
+            fs::read_to_string(&tap).unwrap(),
+            "TAP version 13\n1..1\nok 1 - calc.ops.Stack.__unittest_L4_C5 # time=0.900ms\n"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_coverage() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().display().to_string();
+        let listings = temp.path().join(".dub/cargo-dub/coverage");
+        fs::create_dir_all(&listings).unwrap();
+        fs::write(listings.join("source-old.lst"), "      1|int z;\n").unwrap();
+        // Listings of the package's own are left alone.
+        fs::write(temp.path().join("notes.lst"), "not coverage").unwrap();
+        let dub = FakeDub::new();
+        dub.on("test").script(&format!(
+            "printf '      2|int x;\\n0000000|int y;\\nsource/app.d is 50%% covered\\n' \
+             > {}/source-app.lst\n",
+            listings.display()
+        ));
+
+        let lcov = temp.path().join("lcov.info");
+        let report = format!("--report=lcov={}", lcov.display());
+        let mut args = vec!["coverage", &report, "--compiler=dmd", "-b", "release"];
+        args.extend(["--root", &root, "--fail-under", "40"]);
+        assert!(dub.run(&args).unwrap().success());
+        assert_eq!(
+            dub.argv(),
+            [format!(
+                "test --compiler=dmd --build=unittest-cov --root={root} \
+                 -- --DRT-covopt=dstpath:{}",
+                listings.display()
+            )]
+        );
+        assert!(!listings.join("source-old.lst").exists());
+        assert!(temp.path().join("notes.lst").exists());
+        assert_eq!(
+            fs::read_to_string(&lcov).unwrap(),
+            "TN:\nSF:source/app.d\nDA:1,2\nDA:2,0\nLF:2\nLH:1\nend_of_record\n"
+        );
+
+        *args.last_mut().unwrap() = "75";
+        assert_eq!(
+            dub.run(&args).unwrap_err().to_string(),
+            "Coverage of 50.00% is below --fail-under 75%"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_watch_stops_process_group() {
+        let opts = WatchOptions {
+            command: WatchCommand::Run,
+            debounce: 0,
+            clear: false,
+            options: DubOptions {
+                compiler: Some("dmd".to_string()),
+                ..Default::default()
+            },
+        };
+        let cmd = watch_command(&FakeDub::new(), &opts).unwrap();
+        assert_eq!(
+            cmd.get_args().collect::<Vec<_>>(),
+            ["run", "--compiler=dmd"]
+        );
+
+        // A shell standing in for `dub run` with its program in the background.
+        let temp = TempDir::new().unwrap();
+        let pid_file = temp.path().join("pid");
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c")
+            .arg(format!("sleep 30 & echo $! > {}; wait", pid_file.display()));
+        std::os::unix::process::CommandExt::process_group(&mut cmd, 0);
+        let mut child = cmd.spawn().unwrap();
+        while fs::read_to_string(&pid_file).map_or(true, |pid| !pid.ends_with('\n')) {
+            thread::sleep(Duration::from_millis(10));
+        }
+        let program = fs::read_to_string(&pid_file).unwrap();
+        stop(&mut child).unwrap();
+        // Killed but possibly not yet reaped by init.
+        let stat = fs::read_to_string(format!("/proc/{}/stat", program.trim()));
+        assert!(stat.map_or(true, |stat| stat.contains(") Z ")));
+
+        // A reaped child is left alone.
+        let mut done = Command::new("true").spawn().unwrap();
+        done.wait().unwrap();
+        stop(&mut done).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_watch_session_records_signals() {
+        let session = WatchSession::start();
+        assert_eq!(session.interrupted(), None);
+        // SAFETY: the session installed a handler for SIGTERM.
+        unsafe { libc::raise(libc::SIGTERM) };
+        assert_eq!(session.interrupted(), Some(libc::SIGTERM));
+        INTERRUPTED.store(0, std::sync::atomic::Ordering::SeqCst);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_dry_run() {
+        let cli = Cli::try_parse_from(["cargo-dub", "dub", "build", "--dry-run=json"]).unwrap();
+        assert_eq!(cli.dry_run, Some(DryRunFormat::Json));
+        let cli = Cli::try_parse_from(["cargo-dub", "--print-command", "test"]).unwrap();
+        assert_eq!(cli.dry_run, Some(DryRunFormat::Shell));
+
+        let dub = FakeDub::new();
+        let build = DubCommands::Build {
+            options: BuildOptions {
+                target: None,
+                options: DubOptions {
+                    compiler: Some("ldc2".to_string()),
+                    ..Default::default()
+                },
+            },
+            message_format: MessageFormat::Json,
+        };
+        let lines: Vec<String> = planned_commands(&dub, &build)
+            .unwrap()
+            .iter()
+            .map(|cmd| Invocation::of(cmd).args.join(" "))
+            .collect();
+        assert_eq!(lines, ["describe --compiler=ldc2", "build --compiler=ldc2"]);
+
+        // Planning a test run must not write the generated runner.
+        let temp = TempDir::new().unwrap();
+        fs::write(temp.path().join("dub.sdl"), "name \"calc\"\n").unwrap();
+        let test = DubCommands::Test(TestOptions {
+            filters: Vec::new(),
+            report: Vec::new(),
+            runner: TestRunner::Auto,
+            options: DubOptions {
+                root: Some(temp.path().display().to_string()),
+                ..Default::default()
+            },
+        });
+        let cmds = planned_commands(&dub, &test).unwrap();
+        let main_file = temp.path().join(".dub/cargo-dub/cargo_dub_test_runner.d");
+        assert_eq!(Invocation::of(&cmds[0]).args[0], "describe");
+        assert_eq!(
+            Invocation::of(&cmds[1]).args.last().unwrap(),
+            &format!("--main-file={}", main_file.display())
+        );
+        assert!(!main_file.exists());
+    }
+
+    #[test]
+    fn test_apply_target() {
+        let mut opts = DubOptions {
+            compiler: Some("/opt/ldc/bin/ldc2".to_string()),
+            arch: Some("x86_64".to_string()),
+            ..Default::default()
+        };
+        apply_target("aarch64-unknown-linux-gnu", &mut opts).unwrap();
+        assert_eq!(opts.arch.as_deref(), Some("aarch64-unknown-linux-gnu"));
+
+        opts.compiler = Some("dmd".to_string());
+        let err = apply_target("wasm32-unknown-unknown", &mut opts).unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("Target 'wasm32-unknown-unknown' is not supported with dmd"));
+        assert!(apply_target("linux", &mut opts).is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_build_dub_args_with_env_dc() {
+        let dub = FakeDub::new();
+        let run = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["cargo-dub"].iter().chain(args)).unwrap();
+            run_with(cli, Some("dmd".to_string()), || Ok(dub.clone())).unwrap();
+        };
+        run(&["build"]);
+        run(&["build", "--compiler=ldc2"]);
+        assert_eq!(
+            dub.argv(),
+            ["build --compiler=dmd", "build --compiler=ldc2"]
+        );
     }
 
     #[test]
     fn test_convert_format_file_missing() {
-        let dub = MockDubExecutable::new("dub");
-        let result = convert_format(&dub, Format::Json);
+        let temp_dir = TempDir::new().unwrap();
+        let result = convert_format(temp_dir.path(), &convert_options(Format::Json));
         assert!(result.is_err());
         assert_eq!(
             result.unwrap_err().to_string(),
@@ -555,338 +3246,876 @@ mod tests {
     fn test_convert_format_file_exists() {
         let temp_dir = TempDir::new().unwrap();
         let source_path = temp_dir.path().join("dub.sdl");
-        File::create(&source_path).unwrap().write_all(b"").unwrap();
+        File::create(&source_path)
+            .unwrap()
+            .write_all(b"// demo\nname \"demo\"\n")
+            .unwrap();
 
-        let dub = MockDubExecutable::new("dub");
-        let cmd = dub.command();
-        let mut cmd = cmd;
-        cmd.current_dir(temp_dir.path());
-        cmd.args(["convert", "--format=json"]);
+        convert_format(temp_dir.path(), &convert_options(Format::Json)).unwrap();
+        assert!(!source_path.exists());
+        let json = fs::read_to_string(temp_dir.path().join("dub.json")).unwrap();
+        assert_eq!(
+            json,
+            "{\n\t\"//name\": \"demo\",\n\t\"name\": \"demo\"\n}\n"
+        );
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
-            .collect();
-        assert_eq!(args, vec!["convert", "--format=json"]);
+        convert_format(temp_dir.path(), &convert_options(Format::Sdl)).unwrap();
+        fs::write(temp_dir.path().join("dub.json"), "{\"name\": \"demo\"}").unwrap();
+        let check = ConvertOptions {
+            check: true,
+            ..convert_options(Format::Sdl)
+        };
+        convert_format(temp_dir.path(), &check).unwrap();
+        fs::write(temp_dir.path().join("dub.json"), "{\"name\": \"other\"}").unwrap();
+        assert_eq!(
+            convert_format(temp_dir.path(), &check)
+                .unwrap_err()
+                .to_string(),
+            "'dub.json' and 'dub.sdl' are not equivalent:\n  name: \"other\" -> \"demo\""
+        );
+        fs::write(
+            temp_dir.path().join("dub.json"),
+            "{\"name\": \"demo\", \"x:ddoxTool\": \"scod\"}",
+        )
+        .unwrap();
+        assert_eq!(
+            convert_format(temp_dir.path(), &check)
+                .unwrap_err()
+                .to_string(),
+            "'dub.json' and 'dub.sdl' are not equivalent:\n  x:ddoxTool: \"scod\" -> (missing)"
+        );
+
+        let sdl = "name \"demo\"\nconfiguration \"cli\" {\n\tsubConfiguration \"vibe-d\" \"vibe-core\"\n}\n";
+        fs::write(&source_path, sdl).unwrap();
+        fs::remove_file(temp_dir.path().join("dub.json")).unwrap();
+        convert_format(temp_dir.path(), &convert_options(Format::Json)).unwrap();
+        fs::write(&source_path, sdl).unwrap();
+        let check = ConvertOptions {
+            check: true,
+            ..convert_options(Format::Json)
+        };
+        convert_format(temp_dir.path(), &check).unwrap();
+    }
+
+    #[test]
+    fn test_convert_format_paths() {
+        let temp_dir = TempDir::new().unwrap();
+        let input = temp_dir.path().join("recipe.json");
+        fs::write(&input, "{\"name\": \"demo\", \"sourcePaths\": [\"src\"]}").unwrap();
+        let output = temp_dir.path().join("out").with_extension("sdl");
+
+        let opts = ConvertOptions {
+            input: Some(input.clone()),
+            output: Some(output.clone()),
+            verify: true,
+            ..convert_options(Format::Sdl)
+        };
+        convert_format(temp_dir.path(), &opts).unwrap();
+        assert!(input.exists());
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "name \"demo\"\nsourcePaths \"src\"\n"
+        );
+
+        let opts = ConvertOptions {
+            input: Some(input.clone()),
+            ..convert_options(Format::Json)
+        };
+        assert!(convert_format(temp_dir.path(), &opts)
+            .unwrap_err()
+            .to_string()
+            .ends_with("is already in the requested format"));
     }
 
+    fn convert_options(format: Format) -> ConvertOptions {
+        ConvertOptions {
+            input: None,
+            format,
+            check: false,
+            to_stdout: false,
+            output: None,
+            verify: false,
+        }
+    }
+
+    #[cfg(unix)]
     #[test]
     fn test_execute_dub_command() {
-        let dub = MockDubExecutable::new("dub");
-        let opts = DubOptions::default();
-        let cmd = dub.command();
-        let mut cmd = cmd;
-        cmd.arg("run");
-        build_dub_args(&mut cmd, &opts).unwrap();
+        let dub = FakeDub::new();
+        dub.on("run").stdout("Running app\n").exit(3);
+        let outcome = dub
+            .run(&["dub", "run", "--compiler=ldc2", "-b", "release"])
+            .unwrap();
+        assert_eq!(outcome.exit_code(), 3);
+        dub.run(&["build", "--compiler=ldc2"]).unwrap();
+        dub.run(&["raw", "upgrade", "--missing-only"]).unwrap();
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
-            .collect();
-        assert_eq!(args, vec!["run"]);
+        let calls = dub.calls();
+        assert_eq!(
+            dub.argv(),
+            [
+                "run --compiler=ldc2 --build=release",
+                "build --compiler=ldc2",
+                "upgrade --missing-only"
+            ]
+        );
+        assert_eq!(calls[0].cwd, env::current_dir().unwrap());
+        assert_eq!(calls[0].env.get("DFLAGS"), env::var("DFLAGS").ok().as_ref());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_dry_run() {
+        let dub = FakeDub::new();
+        dub.run(&["build", "--dry-run", "--compiler=ldc2"]).unwrap();
+        assert!(dub.calls().is_empty());
+        assert_eq!(
+            dub.run(&["convert", "--format=sdl", "--dry-run"])
+                .unwrap_err()
+                .to_string(),
+            "--dry-run only applies to commands that run dub"
+        );
     }
 
+    #[cfg(unix)]
     #[test]
     fn test_execute_describe() {
-        let dub = MockDubExecutable::new("dub");
-        let opts = DescribeOptions {
-            data: Some(vec!["main-source-file".to_string(), "libs".to_string()]),
-            data_list: true,
-            options: DubOptions {
-                compiler: Some("ldc2".to_string()),
-                ..Default::default()
-            },
+        let dub = FakeDub::new();
+        dub.run(&[
+            "describe",
+            "--data=main-source-file,libs",
+            "--data-list",
+            "--compiler=ldc2",
+        ])
+        .unwrap();
+        assert_eq!(
+            dub.argv(),
+            ["describe --data=main-source-file --data=libs --data-list --compiler=ldc2"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_describe_views() {
+        let dub = FakeDub::new();
+        dub.on("describe").stdout(
+            r#"{"rootPackage": "app", "packages": [{"name": "app", "targetType": "executable",
+                "license": "BSL-1.0", "versions": ["Have_app"]}],
+            "targets": [{"rootPackage": "app", "rootConfiguration": "application",
+                "buildSettings": {"targetType": 2, "importPaths": ["/work/app/source/"]}}]}"#,
+        );
+        let mut opts = DescribeOptions {
+            data: None,
+            data_list: false,
+            query: Some(
+                ".targets[] | select(.rootPackage == \"app\") | .buildSettings.importPaths[]"
+                    .to_string(),
+            ),
+            table: None,
+            target: None,
+            options: DubOptions::default(),
         };
-        let cmd = dub.command();
-        let mut cmd = cmd;
-        cmd.arg("describe");
-        if let Some(data) = &opts.data {
-            for d in data {
-                cmd.arg(format!("--data={d}"));
-            }
-        }
-        if opts.data_list {
-            cmd.arg("--data-list");
+        let mut out = Vec::new();
+        describe_views(&dub, &opts, &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "/work/app/source/\n");
+        assert_eq!(dub.argv(), ["describe"]);
+
+        for (query, expected) in [
+            (".packages[0].license", "BSL-1.0\n"),
+            (".packages[0].versions[]", "Have_app\n"),
+        ] {
+            opts.query = Some(query.to_string());
+            let mut out = Vec::new();
+            describe_views(&dub, &opts, &mut out).unwrap();
+            assert_eq!(String::from_utf8(out).unwrap(), expected);
         }
-        build_dub_args(&mut cmd, &opts.options).unwrap();
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
-            .collect();
+        opts.query = Some(".targets[0].buildSettings.targetType".to_string());
+        opts.table = Some(Table::Targets);
+        let mut out = Vec::new();
+        describe_views(&dub, &opts, &mut out).unwrap();
         assert_eq!(
-            args,
-            vec![
-                "describe",
-                "--data=main-source-file",
-                "--data=libs",
-                "--data-list",
-                "--compiler=ldc2"
-            ]
+            String::from_utf8(out).unwrap(),
+            "2\n\
+             Target  Type        Configuration  Dependencies\n\
+             app     executable  application\n"
         );
+
+        dub.on("describe").stderr("Error: no recipe\n").exit(2);
+        let outcome = describe_views(&dub, &opts, &mut Vec::new()).unwrap();
+        assert_eq!(outcome.code, Some(2));
+        assert!(dub.run(&["describe", "--data=libs", "--query=."]).is_err());
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_execute_add() {
-        let dub = MockDubExecutable::new("dub");
-        let opts = AddRemoveOptions {
-            packages: vec!["vibelog@1.0.0".to_string(), "libdparse".to_string()],
-            options: DubOptions {
-                yes: true,
-                ..Default::default()
-            },
+    fn test_dependency_tree() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().display().to_string();
+        fs::write(
+            temp.path().join("dub.selections.json"),
+            r#"{"fileVersion": 1, "versions": {"dlib": {"path": "../dlib"}, "mir-core": "1.7.1"}}"#,
+        )
+        .unwrap();
+        let dub = FakeDub::new();
+        dub.on("describe").stdout(
+            r#"{"rootPackage": "app", "packages": [
+            {"name": "app", "version": "1.0.0", "path": "/work/app/", "active": true,
+             "dependencies": ["dlib", "mir-core"]},
+            {"name": "dlib", "version": "0.1.0", "path": "/work/dlib/", "active": true,
+             "dependencies": ["mir-core"]},
+            {"name": "mir-core", "version": "1.7.1", "active": true}]}"#,
+        );
+        let tree = |args: &[&str]| {
+            let cli =
+                Cli::try_parse_from(["cargo-dub", "tree", "--root", &root].iter().chain(args))
+                    .unwrap();
+            let Some(Commands::Direct(DubCommands::Tree(opts))) = cli.command else {
+                unreachable!()
+            };
+            let mut out = Vec::new();
+            dependency_tree(&dub, &opts, &mut out).map(|_| String::from_utf8(out).unwrap())
         };
-        let cmd = dub.command();
-        let mut cmd = cmd;
-        cmd.arg("add");
-        cmd.args(&opts.packages);
-        build_dub_args(&mut cmd, &opts.options).unwrap();
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
-            .collect();
-        assert_eq!(args, vec!["add", "vibelog@1.0.0", "libdparse", "--yes"]);
+        assert_eq!(
+            tree(&[]).unwrap(),
+            "app v1.0.0 (/work/app/)\n\
+             ├── dlib v0.1.0 (/work/dlib/)\n\
+             │   └── mir-core v1.7.1\n\
+             └── mir-core v1.7.1\n"
+        );
+        assert_eq!(
+            tree(&["--duplicates", "--format={n}"]).unwrap(),
+            "mir-core\n├── app\n└── dlib\n    └── app\n"
+        );
+        assert_eq!(
+            tree(&["-i", "silly"]).unwrap_err().to_string(),
+            "Package 'silly' is not in the dependency graph"
+        );
+        assert!(tree(&["--output-format=dot"])
+            .unwrap()
+            .contains("\"dlib\" -> \"mir-core\";"));
+        assert_eq!(dub.argv()[0], format!("describe --root={root}"));
     }
 
     #[test]
-    fn test_execute_remove() {
-        let dub = MockDubExecutable::new("dub");
-        let opts = AddRemoveOptions {
-            packages: vec!["vibelog@1.0.0".to_string()],
-            options: DubOptions {
-                force: true,
-                ..Default::default()
-            },
+    fn test_lock_diff() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().display().to_string();
+        let git = |args: &[&str]| {
+            let status = Command::new("git")
+                .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+                .args(args)
+                .current_dir(temp.path())
+                .stdout(Stdio::null())
+                .status()
+                .unwrap();
+            assert!(status.success());
         };
-        let cmd = dub.command();
-        let mut cmd = cmd;
-        cmd.arg("remove");
-        cmd.args(&opts.packages);
-        build_dub_args(&mut cmd, &opts.options).unwrap();
+        let lock = temp.path().join("dub.selections.json");
+        git(&["init", "-q"]);
+        fs::write(
+            &lock,
+            r#"{"fileVersion": 1, "versions": {"mir-core": "1.7.1", "dlib": {"path": "../dlib"}}}"#,
+        )
+        .unwrap();
+        git(&["add", "dub.selections.json"]);
+        git(&["commit", "-qm", "lock"]);
+        fs::write(
+            &lock,
+            r#"{"fileVersion": 1, "versions": {"mir-core": "1.7.3", "dlib": {"path": "../dlib"}}}"#,
+        )
+        .unwrap();
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
-            .collect();
-        assert_eq!(args, vec!["remove", "vibelog@1.0.0", "--force"]);
+        let diff = |args: &[&str]| {
+            let cli = Cli::try_parse_from(
+                ["cargo-dub", "lock", "diff", "--root", &root]
+                    .iter()
+                    .chain(args),
+            )
+            .unwrap();
+            let Some(Commands::Direct(DubCommands::Lock { command })) = cli.command else {
+                unreachable!()
+            };
+            lock_diff(&command).map(|changes| {
+                changes
+                    .iter()
+                    .map(|change| change.to_string())
+                    .collect::<Vec<_>>()
+            })
+        };
+        assert_eq!(
+            diff(&["HEAD"]).unwrap(),
+            ["    Updating mir-core v1.7.1 -> v1.7.3"]
+        );
+        assert!(diff(&["HEAD", "HEAD"]).unwrap().is_empty());
+        assert!(diff(&["no-such-rev"])
+            .unwrap_err()
+            .to_string()
+            .starts_with("`git show no-such-rev:./dub.selections.json` failed: fatal:"));
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_execute_fetch() {
-        let dub = MockDubExecutable::new("dub");
-        let opts = FetchOptions {
-            package: "vibelog@1.0.0".to_string(),
-            cache: Some("local".to_string()),
-            options: DubOptions {
-                yes: true,
-                ..Default::default()
-            },
-        };
-        let cmd = dub.command();
-        let mut cmd = cmd;
-        cmd.arg("fetch");
-        cmd.arg(&opts.package);
-        if let Some(cache) = &opts.cache {
-            cmd.arg(format!("--cache={cache}"));
-        }
-        build_dub_args(&mut cmd, &opts.options).unwrap();
+    fn test_execute_update() {
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().display().to_string();
+        let lock = temp.path().join("dub.selections.json");
+        fs::write(
+            temp.path().join("dub.sdl"),
+            "name \"app\"\ndependency \"mir-core\" version=\"~>1.7.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            &lock,
+            r#"{"fileVersion": 1, "versions": {"mir-core": "1.7.1", "dlib": {"path": "../dlib"}}}"#,
+        )
+        .unwrap();
+        let dub = FakeDub::new();
+        dub.on("upgrade").script(&format!(
+            "[ \"$2\" = --missing-only ] || \
+             printf '{{\"fileVersion\": 1, \"versions\": {{\"mir-core\": \"1.7.3\"}}}}' > {}\n",
+            lock.display()
+        ));
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
-            .collect();
+        dub.run(&["update", "mir-core", "--precise", "1.7.2", "--root", &root])
+            .unwrap();
+        let selections = Selections::load(temp.path()).unwrap().unwrap();
         assert_eq!(
-            args,
-            vec!["fetch", "vibelog@1.0.0", "--cache=local", "--yes"]
+            selections.get("mir-core"),
+            Some(&Selection::Version("1.7.2".to_string()))
+        );
+        assert_eq!(
+            dub.run(&["update", "mir-core", "--precise", "1.8.0", "--root", &root])
+                .unwrap_err()
+                .to_string(),
+            format!(
+                "mir-core 1.8.0 does not satisfy '~>1.7.0' required by '{}'",
+                temp.path().join("dub.sdl").display()
+            )
+        );
+        assert_eq!(
+            dub.run(&["update", "dlib", "--precise", "1.0.0", "--root", &root])
+                .unwrap_err()
+                .to_string(),
+            "Package 'dlib' is selected by path (../dlib)"
+        );
+        assert!(dub.run(&["update", "--precise", "1.0.0"]).is_err());
+
+        dub.run(&["update", "mir-core", "--root", &root]).unwrap();
+        assert_eq!(
+            dub.argv(),
+            [
+                format!("upgrade --missing-only --root={root}"),
+                format!("upgrade mir-core --root={root}")
+            ]
+        );
+        assert!(fs::read_to_string(&lock).unwrap().contains("1.7.3"));
+
+        dub.on("upgrade").exit(2);
+        let outcome = dub
+            .run(&["update", "mir-core", "--precise", "1.7.9", "--root", &root])
+            .unwrap();
+        assert_eq!(outcome.code, Some(2));
+        assert_eq!(
+            Selections::load(temp.path())
+                .unwrap()
+                .unwrap()
+                .get("mir-core"),
+            Some(&Selection::Version("1.7.3".to_string()))
         );
     }
 
+    #[cfg(unix)]
     #[test]
-    fn test_execute_init() {
-        let dub = MockDubExecutable::new("dub");
-        let opts = InitOptions {
-            directory: Some("my_project".to_string()),
-            dependencies: vec!["vibelog@1.0.0".to_string()],
-            r#type: ProjectType::VibeD,
-            non_interactive: true,
+    fn test_why() {
+        let temp = TempDir::new().unwrap();
+        let (app, dlib) = (temp.path().join("app"), temp.path().join("dlib"));
+        fs::create_dir_all(&app).unwrap();
+        fs::create_dir_all(&dlib).unwrap();
+        fs::write(
+            app.join("dub.sdl"),
+            "name \"app\"\ndependency \"dlib\" path=\"../dlib\"\n\
+             dependency \"mir-core\" version=\"~>1.7.0\"\n",
+        )
+        .unwrap();
+        fs::write(
+            dlib.join("dub.json"),
+            r#"{"name": "dlib", "dependencies": {"mir-core": ">=1.6.0"}}"#,
+        )
+        .unwrap();
+        fs::write(
+            app.join("dub.selections.json"),
+            r#"{"fileVersion": 1, "versions": {"mir-core": "1.7.1", "dlib": {"path": "../dlib"}}}"#,
+        )
+        .unwrap();
+        let dub = FakeDub::new();
+        dub.on("describe").stdout(&format!(
+            r#"{{"rootPackage": "app", "packages": [
+            {{"name": "app", "version": "1.0.0", "path": "{app}/", "active": true,
+             "dependencies": ["dlib", "mir-core"]}},
+            {{"name": "dlib", "version": "0.1.0", "path": "{dlib}/", "active": true,
+             "dependencies": ["mir-core"]}},
+            {{"name": "mir-core", "version": "1.7.1", "active": true}}]}}"#,
+            app = app.display(),
+            dlib = dlib.display()
+        ));
+
+        let opts = WhyOptions {
+            package: "mir-core".to_string(),
             options: DubOptions {
-                yes: true,
+                root: Some(app.display().to_string()),
                 ..Default::default()
             },
         };
-        let cmd = dub.command();
-        let mut cmd = cmd;
-        cmd.arg("init");
-        if let Some(dir) = &opts.directory {
-            cmd.arg(dir);
-        }
-        cmd.args(&opts.dependencies);
-        cmd.arg(format!(
-            "--type={}",
-            match opts.r#type {
-                ProjectType::Minimal => "minimal",
-                ProjectType::VibeD => "vibe.d",
-                ProjectType::Deimos => "deimos",
-                ProjectType::Custom => "custom",
-            }
-        ));
-        if opts.non_interactive {
-            cmd.arg("--non-interactive");
+        let mut out = Vec::new();
+        why(&dub, &opts, &mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "mir-core 1.7.1 is selected as 1.7.1 in dub.selections.json\n  \
+             app -> dlib (path ../dlib) -> mir-core (>=1.6.0)\n  \
+             app -> mir-core (~>1.7.0)\n"
+        );
+        let opts = WhyOptions {
+            package: "silly".to_string(),
+            ..opts
+        };
+        assert_eq!(
+            why(&dub, &opts, &mut Vec::new()).unwrap_err().to_string(),
+            "Package 'silly' is not in the dependency graph"
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_vendor() {
+        let temp = TempDir::new().unwrap();
+        let app = temp.path().join("app");
+        let core = temp.path().join("cache/mir-core/1.7.1/mir-core");
+        let vibe = temp.path().join("cache/vibe-d/0.9.8/vibe-d");
+        for dir in [&app, &core.join("source"), &vibe.join("http")] {
+            fs::create_dir_all(dir).unwrap();
         }
-        build_dub_args(&mut cmd, &opts.options).unwrap();
+        fs::write(core.join("source/core.d"), "module core;\n").unwrap();
+        fs::write(vibe.join("http/dub.json"), "{}").unwrap();
+        fs::write(
+            app.join(selections::FILE_NAME),
+            r#"{"fileVersion": 1, "versions": {"dlib": {"path": "../dlib"}, "mir-core": "1.7.1",
+                "vibe-d": "0.9.8"}}"#,
+        )
+        .unwrap();
+        let dub = FakeDub::new();
+        dub.on("describe").stdout(&format!(
+            r#"{{"rootPackage": "app", "packages": [
+            {{"name": "app", "version": "1.0.0", "path": "{}", "active": true}},
+            {{"name": "mir-core", "version": "1.7.1", "path": "{}", "active": true}},
+            {{"name": "vibe-d:http", "version": "0.9.8", "path": "{}", "active": true}}]}}"#,
+            app.display(),
+            core.display(),
+            vibe.join("http").display()
+        ));
+        let cli = Cli::try_parse_from(["cargo-dub", "vendor", "--root", app.to_str().unwrap()]);
+        let Some(Commands::Direct(DubCommands::Vendor(opts))) = cli.unwrap().command else {
+            unreachable!()
+        };
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
-            .collect();
+        assert!(execute_vendor(&dub, &opts).unwrap().success());
+        assert!(app.join("vendor/mir-core/source/core.d").is_file());
+        assert!(app.join("vendor/vibe-d/http/dub.json").is_file());
+        let locked = Selections::load(&app).unwrap().unwrap();
         assert_eq!(
-            args,
-            vec![
-                "init",
-                "my_project",
-                "vibelog@1.0.0",
-                "--type=vibe.d",
-                "--non-interactive",
-                "--yes"
+            locked.versions.values().cloned().collect::<Vec<_>>(),
+            [
+                Selection::Path("../dlib".to_string()),
+                Selection::Path("vendor/mir-core".to_string()),
+                Selection::Path("vendor/vibe-d".to_string()),
             ]
         );
 
-        // Test with no directory, no dependencies, and minimal flags
-        let opts_minimal = InitOptions {
-            directory: None,
-            dependencies: vec![],
-            r#type: ProjectType::Minimal,
-            non_interactive: false,
-            options: DubOptions::default(),
-        };
-        let cmd = dub.command();
-        let mut cmd = cmd;
-        cmd.arg("init");
-        if let Some(dir) = &opts_minimal.directory {
-            cmd.arg(dir);
-        }
-        cmd.args(&opts_minimal.dependencies);
-        cmd.arg(format!(
-            "--type={}",
-            match opts_minimal.r#type {
-                ProjectType::Minimal => "minimal",
-                ProjectType::VibeD => "vibe.d",
-                ProjectType::Deimos => "deimos",
-                ProjectType::Custom => "custom",
+        // A second run keeps the vendored copies and checks them.
+        fs::remove_dir_all(temp.path().join("cache")).unwrap();
+        assert!(execute_vendor(&dub, &opts).unwrap().success());
+        verify_vendored(&opts).unwrap();
+
+        fs::write(app.join("vendor/mir-core/source/core.d"), "patched").unwrap();
+        assert_eq!(
+            execute_vendor(&dub, &opts).unwrap_err().to_string(),
+            format!(
+                "Vendored package 'mir-core' does not match its checksums:\n  \
+                 modified: source/core.d\n\
+                 Restore it, or delete '{}' to vendor it again",
+                app.join("vendor/mir-core").display()
+            )
+        );
+        assert_eq!(
+            verify_vendored(&opts).unwrap_err().to_string(),
+            "1 vendored package(s) modified: mir-core"
+        );
+    }
+
+    #[test]
+    fn test_search_and_info() {
+        use cargo_dub::registry::PackageInfo;
+
+        let temp = TempDir::new().unwrap();
+        let mirror = Mirror::new(temp.path());
+        let core: PackageInfo = serde_json::from_value(serde_json::json!({
+            "name": "mir-core",
+            "repository": {"kind": "github", "owner": "libmir", "project": "mir-core"},
+            "versions": [
+                {"version": "~master"},
+                {"version": "1.6.0"},
+                {"version": "1.7.1", "description": "Base software building blocks",
+                 "license": "Apache-2.0", "configurations": [{"name": "library"}, {"name": "unittest"}],
+                 "dependencies": {"mir-algorithm": ">=3.0.0", "silly": {"version": "~>1.1", "optional": true}}}
+            ]
+        }))
+        .unwrap();
+        let ion: PackageInfo = serde_json::from_value(serde_json::json!({
+            "name": "mir-ion", "description": "Ion and JSON", "versions": [{"version": "2.0.0"}]
+        }))
+        .unwrap();
+        for package in [&core, &ion] {
+            for version in &package.versions {
+                mirror.add(package, version, b"zip").unwrap();
             }
-        ));
-        if opts_minimal.non_interactive {
-            cmd.arg("--non-interactive");
         }
-        build_dub_args(&mut cmd, &opts_minimal.options).unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = registry::Client::new(&format!("http://{}", listener.local_addr().unwrap()));
+        thread::spawn(move || registry::serve(&mirror, listener));
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
-            .collect();
-        assert_eq!(args, vec!["init", "--type=minimal"]);
+        let search_for = |args: &[&str]| {
+            let cli = Cli::try_parse_from(["cargo-dub", "search"].iter().chain(args)).unwrap();
+            let Some(Commands::Direct(DubCommands::Search(opts))) = cli.command else {
+                unreachable!()
+            };
+            let mut out = Vec::new();
+            search(&client, &opts, &mut out).unwrap();
+            String::from_utf8(out).unwrap()
+        };
+        assert_eq!(
+            search_for(&["mir"]),
+            "mir-core = \"1.7.1\"    # Base software building blocks\n\
+             mir-ion = \"2.0.0\"     # Ion and JSON\n"
+        );
+        assert_eq!(
+            search_for(&["mir", "--limit=1"]),
+            "mir-core = \"1.7.1\"    # Base software building blocks\n\
+             ... and 1 packages more (use --limit N to see more)\n"
+        );
+        assert_eq!(search_for(&["vibe"]), "No packages match 'vibe'\n");
+
+        let info = |package: &str| {
+            let cli = Cli::try_parse_from(["cargo-dub", "info", package]).unwrap();
+            let Some(Commands::Direct(DubCommands::Info(opts))) = cli.command else {
+                unreachable!()
+            };
+            let mut out = Vec::new();
+            package_info(&client, &opts, &mut out).map(|()| String::from_utf8(out).unwrap())
+        };
+        assert_eq!(
+            info("mir-core").unwrap(),
+            "mir-core\n\
+             Base software building blocks\n\
+             version: 1.7.1\n\
+             license: Apache-2.0\n\
+             repository: https://github.com/libmir/mir-core\n\
+             configurations: library, unittest\n\
+             dependencies:\n  \
+             mir-algorithm >=3.0.0\n  \
+             silly ~>1.1\n\
+             versions: 1.7.1, 1.6.0, ~master\n"
+        );
+        assert!(info("mir-core@1.6.0")
+            .unwrap()
+            .starts_with("mir-core\nBase software building blocks\nversion: 1.6.0\nrepository:"));
+        assert_eq!(
+            info("mir-core@9.0.0").unwrap_err().to_string(),
+            "Package 'mir-core' has no version 9.0.0"
+        );
     }
 
     #[test]
-    fn test_execute_clean() {
-        let dub = MockDubExecutable::new("dub");
-        let opts = CleanOptions {
-            package: Some("my_package".to_string()),
-            all_packages: false,
-            options: DubOptions {
-                force: true,
-                ..Default::default()
-            },
-        };
-        let cmd = dub.command();
-        let mut cmd = cmd;
-        cmd.arg("clean");
-        if let Some(package) = &opts.package {
-            cmd.arg(package);
-        }
-        if opts.all_packages {
-            cmd.arg("--all-packages");
+    fn test_outdated() {
+        use cargo_dub::registry::PackageInfo;
+
+        let upstream_dir = TempDir::new().unwrap();
+        let upstream = Mirror::new(upstream_dir.path());
+        let core: PackageInfo = serde_json::from_value(serde_json::json!({
+            "name": "mir-core",
+            "versions": [{"version": "1.6.0"}, {"version": "1.6.3"}, {"version": "1.7.1"}]
+        }))
+        .unwrap();
+        for version in &core.versions {
+            upstream.add(&core, version, b"zip").unwrap();
         }
-        build_dub_args(&mut cmd, &opts.options).unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = registry::Client::new(&format!("http://{}", listener.local_addr().unwrap()));
+        thread::spawn(move || registry::serve(&upstream, listener));
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
-            .collect();
-        assert_eq!(args, vec!["clean", "my_package", "--force"]);
+        let temp = TempDir::new().unwrap();
+        let root = temp.path().display().to_string();
+        let outdated_with = |args: &[&str]| {
+            let cli = Cli::try_parse_from(
+                ["cargo-dub", "outdated", "--root", &root]
+                    .iter()
+                    .chain(args),
+            )
+            .unwrap();
+            let Some(Commands::Direct(DubCommands::Outdated(opts))) = cli.command else {
+                unreachable!()
+            };
+            let mut out = Vec::new();
+            let result = outdated(&client, &opts, &mut out);
+            (String::from_utf8(out).unwrap(), result)
+        };
+        assert!(outdated_with(&[])
+            .1
+            .unwrap_err()
+            .to_string()
+            .starts_with("No dub.selections.json in"));
+
+        fs::write(
+            temp.path().join("dub.json"),
+            r#"{"name": "app", "dependencies": {"mir-core": "~>1.6.0", "dlib": {"path": "../dlib"}}}"#,
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join(selections::FILE_NAME),
+            r#"{"fileVersion": 1, "versions": {"dlib": {"path": "../dlib"}, "mir-core": "1.6.0"}}"#,
+        )
+        .unwrap();
+        let (table, result) = outdated_with(&[]);
+        result.unwrap();
+        assert_eq!(
+            table,
+            "Name      Current  Compatible  Latest  Kind\n\
+             mir-core  1.6.0    1.6.3       1.7.1   Normal\n"
+        );
+        let (json, result) = outdated_with(&["--format=json", "--exit-code"]);
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "1 dependency is behind the registry"
+        );
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&json).unwrap()[0]["compatible"],
+            "1.6.3"
+        );
+
+        let mut locked = Selections::load(temp.path()).unwrap().unwrap();
+        locked.versions.insert(
+            "mir-core".to_string(),
+            Selection::Version("1.7.1".to_string()),
+        );
+        locked.save(temp.path()).unwrap();
+        let (table, result) = outdated_with(&["--exit-code"]);
+        result.unwrap();
+        assert_eq!(table, "All dependencies are up to date\n");
     }
 
     #[test]
-    fn test_execute_lint() {
-        let dub = MockDubExecutable::new("dub");
-        let opts = LintOptions {
-            package: Some("my_package@1.0.0".to_string()),
-            syntax_check: true,
-            style_check: true,
-            error_format: Some("custom".to_string()),
-            report: true,
-            report_format: Some("json".to_string()),
-            report_file: Some("report.json".to_string()),
-            import_paths: Some(vec!["src".to_string()]),
-            dscanner_config: Some("dscanner.ini".to_string()),
-            options: DubOptions {
-                yes: true,
-                ..Default::default()
-            },
+    fn test_audit() {
+        let db = TempDir::new().unwrap();
+        fs::write(
+            db.path().join("DUB-2024-0001.toml"),
+            "[advisory]\nid = \"DUB-2024-0001\"\npackage = \"vibe-d\"\n\
+             title = \"HTTP request smuggling\"\n[versions]\npatched = [\">=0.9.8\"]\n",
+        )
+        .unwrap();
+        fs::write(
+            db.path().join("DUB-2024-0002.toml"),
+            "[advisory]\nid = \"DUB-2024-0002\"\npackage = \"silly\"\n\
+             title = \"silly is unmaintained\"\ninformational = \"unmaintained\"\n",
+        )
+        .unwrap();
+        let temp = TempDir::new().unwrap();
+        let (db_path, root) = (
+            db.path().display().to_string(),
+            temp.path().display().to_string(),
+        );
+        let audit_with = |args: &[&str]| {
+            let cli = Cli::try_parse_from(
+                ["cargo-dub", "audit", "--db", &db_path, "--root", &root]
+                    .iter()
+                    .chain(args),
+            )
+            .unwrap();
+            let Some(Commands::Direct(DubCommands::Audit(opts))) = cli.command else {
+                unreachable!()
+            };
+            let mut out = Vec::new();
+            let result = audit(&opts, &mut out).map_err(|e| e.to_string());
+            (String::from_utf8(out).unwrap(), result)
         };
-        let cmd = dub.command();
-        let mut cmd = cmd;
-        cmd.arg("lint");
-        if let Some(package) = &opts.package {
-            cmd.arg(package);
-        }
-        if opts.syntax_check {
-            cmd.arg("--syntax-check");
-        }
-        if opts.style_check {
-            cmd.arg("--style-check");
-        }
-        if let Some(format) = &opts.error_format {
-            cmd.arg(format!("--error-format={format}"));
-        }
-        if opts.report {
-            cmd.arg("--report");
-        }
-        if let Some(format) = &opts.report_format {
-            cmd.arg(format!("--report-format={format}"));
-        }
-        if let Some(file) = &opts.report_file {
-            cmd.arg(format!("--report-file={file}"));
-        }
-        if let Some(paths) = &opts.import_paths {
-            for path in paths {
-                cmd.arg(format!("--import-paths={path}"));
-            }
-        }
-        if let Some(config) = &opts.dscanner_config {
-            cmd.arg(format!("--dscanner-config={config}"));
+
+        fs::write(
+            temp.path().join(selections::FILE_NAME),
+            r#"{"fileVersion": 1, "versions": {"silly": "1.1.1", "vibe-d": "0.9.5"}}"#,
+        )
+        .unwrap();
+        let (report, result) = audit_with(&[]);
+        assert_eq!(result.unwrap_err(), "1 vulnerability found");
+        assert_eq!(
+            report,
+            "Package:   vibe-d\nVersion:   0.9.5\nTitle:     HTTP request smuggling\n\
+             ID:        DUB-2024-0001\nSolution:  Upgrade to >=0.9.8\n\n\
+             Warning:   unmaintained\nPackage:   silly\nVersion:   1.1.1\n\
+             Title:     silly is unmaintained\nID:        DUB-2024-0002\n\
+             Solution:  No fixed upgrade is available!\n"
+        );
+        let (json, _) = audit_with(&["--format=json"]);
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["database"]["advisory-count"], 2);
+        assert_eq!(
+            json["vulnerabilities"][0]["advisory"]["id"],
+            "DUB-2024-0001"
+        );
+        assert_eq!(json["warnings"][0]["package"], "silly");
+
+        fs::write(
+            temp.path().join(selections::FILE_NAME),
+            r#"{"fileVersion": 1, "versions": {"silly": "1.1.1", "vibe-d": "0.9.8"}}"#,
+        )
+        .unwrap();
+        assert!(audit_with(&[]).1.is_ok());
+        assert!(audit_with(&["--deny", "yanked"]).1.is_ok());
+        assert_eq!(
+            audit_with(&["--deny", "warnings"]).1.unwrap_err(),
+            "1 denied warning found"
+        );
+    }
+
+    #[test]
+    fn test_registry_sync_selections() {
+        use cargo_dub::registry::{PackageInfo, Synced};
+
+        let upstream_dir = TempDir::new().unwrap();
+        let upstream = Mirror::new(upstream_dir.path());
+        let core: PackageInfo = serde_json::from_value(serde_json::json!({
+            "name": "mir-core",
+            "versions": [{"version": "1.7.1"}, {"version": "1.7.3"}]
+        }))
+        .unwrap();
+        for version in &core.versions {
+            upstream.add(&core, version, b"zip").unwrap();
         }
-        build_dub_args(&mut cmd, &opts.options).unwrap();
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        thread::spawn(move || registry::serve(&upstream, listener));
 
-        let args: Vec<String> = cmd
-            .get_args()
-            .map(|s| s.to_string_lossy().into_owned())
-            .collect();
+        let temp = TempDir::new().unwrap();
+        let mirror = temp.path().join("mirror");
+        let err = registry_sync(&[], &mirror, &url, temp.path().to_str()).unwrap_err();
+        assert!(err.to_string().starts_with("No dub.selections.json in"));
+
+        fs::write(
+            temp.path().join(selections::FILE_NAME),
+            r#"{"fileVersion": 1, "versions": {"dlib": {"path": "../dlib"}, "mir-core": "1.7.1"}}"#,
+        )
+        .unwrap();
+        let synced = registry_sync(&[], &mirror, &url, temp.path().to_str()).unwrap();
         assert_eq!(
-            args,
-            vec![
-                "lint",
-                "my_package@1.0.0",
-                "--syntax-check",
-                "--style-check",
-                "--error-format=custom",
-                "--report",
-                "--report-format=json",
-                "--report-file=report.json",
-                "--import-paths=src",
-                "--dscanner-config=dscanner.ini",
-                "--yes"
+            synced,
+            [Synced {
+                package: "mir-core".to_string(),
+                version: "1.7.1".to_string(),
+                downloaded: true,
+            }]
+        );
+        assert!(Mirror::new(&mirror).contains("mir-core", "1.7.1"));
+        assert!(!Mirror::new(&mirror).contains("mir-core", "1.7.3"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_add_remove() {
+        let dub = FakeDub::new();
+        dub.run(&["add", "vibelog@1.0.0", "libdparse", "--yes"])
+            .unwrap();
+        dub.run(&["remove", "vibelog@1.0.0", "--force"]).unwrap();
+        assert_eq!(
+            dub.argv(),
+            [
+                "add vibelog@1.0.0 libdparse --yes",
+                "remove vibelog@1.0.0 --force"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_fetch() {
+        let dub = FakeDub::new();
+        dub.on("fetch")
+            .stderr("Error: Package vibelog not found\n")
+            .exit(2);
+        let outcome = dub
+            .run(&["fetch", "vibelog@1.0.0", "--cache=local", "--yes"])
+            .unwrap();
+        assert_eq!(outcome.code, Some(2));
+        assert_eq!(dub.argv(), ["fetch vibelog@1.0.0 --cache=local --yes"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_init() {
+        let dub = FakeDub::new();
+        dub.run(&[
+            "init",
+            "my_project",
+            "vibelog@1.0.0",
+            "--type=vibe-d",
+            "--non-interactive",
+            "--yes",
+        ])
+        .unwrap();
+        // No directory, no dependencies, and minimal flags
+        dub.run(&["init"]).unwrap();
+        assert_eq!(
+            dub.argv(),
+            [
+                "init my_project vibelog@1.0.0 --type=vibe.d --yes --non-interactive",
+                "init --type=minimal"
+            ]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_clean() {
+        let dub = FakeDub::new();
+        dub.run(&["clean", "my_package", "--force"]).unwrap();
+        dub.run(&["clean", "--all-packages"]).unwrap();
+        assert_eq!(
+            dub.argv(),
+            ["clean my_package --force", "clean --all-packages"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_execute_lint() {
+        let dub = FakeDub::new();
+        dub.run(&[
+            "lint",
+            "my_package@1.0.0",
+            "--syntax-check",
+            "--style-check",
+            "--error-format=custom",
+            "--report",
+            "--report-format=json",
+            "--report-file=report.json",
+            "--import-paths=src",
+            "--dscanner-config=dscanner.ini",
+            "--yes",
+        ])
+        .unwrap();
+        assert_eq!(
+            dub.argv(),
+            [
+                "lint my_package@1.0.0 --syntax-check --style-check --error-format=custom \
+                 --report --report-format=json --report-file=report.json --import-paths=src \
+                 --dscanner-config=dscanner.ini --yes"
             ]
         );
     }
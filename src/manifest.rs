@@ -0,0 +1,1454 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Typed model of a DUB package recipe (`dub.json` / `dub.sdl`).
+
+use crate::sdl;
+use serde_json::{Map, Value};
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Recipe file names in the order DUB looks for them.
+pub const RECIPE_FILES: [&str; 3] = ["dub.json", "dub.sdl", "package.json"];
+
+/// Build settings that hold a list of strings and accept a platform suffix.
+pub const LIST_SETTINGS: &[&str] = &[
+    "dflags",
+    "lflags",
+    "libs",
+    "sourceFiles",
+    "sourcePaths",
+    "excludedSourceFiles",
+    "injectSourceFiles",
+    "copyFiles",
+    "extraDependencyFiles",
+    "versions",
+    "debugVersions",
+    "importPaths",
+    "cImportPaths",
+    "stringImportPaths",
+    "preGenerateCommands",
+    "postGenerateCommands",
+    "preBuildCommands",
+    "postBuildCommands",
+    "preRunCommands",
+    "postRunCommands",
+    "buildRequirements",
+    "buildOptions",
+];
+
+/// Build settings that hold a single string.
+pub const STRING_SETTINGS: &[&str] = &[
+    "targetType",
+    "targetName",
+    "targetPath",
+    "workingDirectory",
+    "mainSourceFile",
+];
+
+/// Build settings DUB writes as `"NAME": {"VAR": "value"}` in JSON and as
+/// one `NAME "VAR" "value"` tag per variable in SDL.
+pub const ENVIRONMENT_SETTINGS: &[&str] = &[
+    "environments",
+    "buildEnvironments",
+    "runEnvironments",
+    "preGenerateEnvironments",
+    "postGenerateEnvironments",
+    "preBuildEnvironments",
+    "postBuildEnvironments",
+    "preRunEnvironments",
+    "postRunEnvironments",
+];
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, std::io::Error),
+    Json(serde_json::Error),
+    Sdl(sdl::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Failed to read '{}': {e}", path.display()),
+            Error::Json(e) => write!(f, "Invalid dub.json: {e}"),
+            Error::Sdl(e) => write!(f, "Invalid dub.sdl: {e}"),
+            Error::Invalid(msg) => f.write_str(msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+impl From<sdl::Error> for Error {
+    fn from(e: sdl::Error) -> Self {
+        Error::Sdl(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Recipe file format.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Format {
+    Json,
+    Sdl,
+}
+
+impl Format {
+    /// Guesses the format from a file extension.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "json" => Some(Format::Json),
+            "sdl" => Some(Format::Sdl),
+            _ => None,
+        }
+    }
+
+    pub fn file_name(self) -> &'static str {
+        match self {
+            Format::Json => "dub.json",
+            Format::Sdl => "dub.sdl",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TargetType {
+    Autodetect,
+    None,
+    Executable,
+    Library,
+    SourceLibrary,
+    StaticLibrary,
+    DynamicLibrary,
+}
+
+impl TargetType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TargetType::Autodetect => "autodetect",
+            TargetType::None => "none",
+            TargetType::Executable => "executable",
+            TargetType::Library => "library",
+            TargetType::SourceLibrary => "sourceLibrary",
+            TargetType::StaticLibrary => "staticLibrary",
+            TargetType::DynamicLibrary => "dynamicLibrary",
+        }
+    }
+}
+
+impl std::str::FromStr for TargetType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "autodetect" => TargetType::Autodetect,
+            "none" => TargetType::None,
+            "executable" => TargetType::Executable,
+            "library" => TargetType::Library,
+            "sourceLibrary" => TargetType::SourceLibrary,
+            "staticLibrary" => TargetType::StaticLibrary,
+            "dynamicLibrary" => TargetType::DynamicLibrary,
+            _ => return Err(Error::Invalid(format!("Unknown targetType '{s}'"))),
+        })
+    }
+}
+
+/// A dependency declaration.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Dependency {
+    pub name: String,
+    pub version: Option<String>,
+    pub path: Option<String>,
+    pub repository: Option<String>,
+    pub optional: bool,
+    pub default: bool,
+}
+
+/// A list setting such as `sourcePaths` or `dflags-linux-ldc`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ListSetting {
+    pub key: String,
+    pub platform: Option<String>,
+    pub values: Vec<String>,
+}
+
+/// Build settings shared by the package root, configurations and build types.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuildSettings {
+    pub target_type: Option<TargetType>,
+    pub target_name: Option<String>,
+    pub target_path: Option<String>,
+    pub working_directory: Option<String>,
+    pub main_source_file: Option<String>,
+    pub dependencies: Vec<Dependency>,
+    /// List settings in declaration order, one entry per key and platform.
+    pub lists: Vec<ListSetting>,
+    /// Settings without a typed field, such as `preBuildEnvironments`,
+    /// `x:ddoxTool` or `dependencies-posix`, in their JSON form and
+    /// declaration order.
+    pub extra: Map<String, Value>,
+}
+
+impl BuildSettings {
+    /// All values of a list setting, across every platform suffix.
+    pub fn list(&self, key: &str) -> Vec<&str> {
+        self.lists
+            .iter()
+            .filter(|l| l.key == key)
+            .flat_map(|l| l.values.iter().map(String::as_str))
+            .collect()
+    }
+
+    pub fn source_paths(&self) -> Vec<&str> {
+        self.list("sourcePaths")
+    }
+
+    pub fn import_paths(&self) -> Vec<&str> {
+        self.list("importPaths")
+    }
+
+    pub fn string_import_paths(&self) -> Vec<&str> {
+        self.list("stringImportPaths")
+    }
+
+    pub fn dependency(&self, name: &str) -> Option<&Dependency> {
+        self.dependencies.iter().find(|d| d.name == name)
+    }
+
+    /// Appends values to a list setting, merging with an existing entry.
+    pub fn push_list(&mut self, key: &str, platform: Option<&str>, values: Vec<String>) {
+        match self
+            .lists
+            .iter_mut()
+            .find(|l| l.key == key && l.platform.as_deref() == platform)
+        {
+            Some(list) => list.values.extend(values),
+            None => self.lists.push(ListSetting {
+                key: key.to_string(),
+                platform: platform.map(str::to_string),
+                values,
+            }),
+        }
+    }
+
+    fn string_setting(&self, key: &str) -> Option<&str> {
+        match key {
+            "targetType" => self.target_type.map(TargetType::as_str),
+            "targetName" => self.target_name.as_deref(),
+            "targetPath" => self.target_path.as_deref(),
+            "workingDirectory" => self.working_directory.as_deref(),
+            "mainSourceFile" => self.main_source_file.as_deref(),
+            _ => None,
+        }
+    }
+
+    fn set_string_setting(&mut self, key: &str, value: String) -> Result<()> {
+        match key {
+            "targetType" => self.target_type = Some(value.parse()?),
+            "targetName" => self.target_name = Some(value),
+            "targetPath" => self.target_path = Some(value),
+            "workingDirectory" => self.working_directory = Some(value),
+            "mainSourceFile" => self.main_source_file = Some(value),
+            _ => unreachable!("not a string setting: {key}"),
+        }
+        Ok(())
+    }
+}
+
+/// A named build configuration.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Configuration {
+    pub name: String,
+    pub platforms: Vec<String>,
+    pub settings: BuildSettings,
+}
+
+/// A custom build type.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct BuildType {
+    pub name: String,
+    pub settings: BuildSettings,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SubPackage {
+    /// A sub package living in its own directory.
+    Path(String),
+    /// A sub package declared inline.
+    Inline(Box<Manifest>),
+}
+
+/// A parsed package recipe.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Manifest {
+    pub name: String,
+    pub description: Option<String>,
+    pub homepage: Option<String>,
+    pub authors: Vec<String>,
+    pub copyright: Option<String>,
+    pub license: Option<String>,
+    pub version: Option<String>,
+    pub system_dependencies: Option<String>,
+    pub settings: BuildSettings,
+    pub configurations: Vec<Configuration>,
+    pub build_types: Vec<BuildType>,
+    pub sub_packages: Vec<SubPackage>,
+    pub sub_configurations: Vec<(String, String)>,
+    pub toolchain_requirements: Vec<(String, String)>,
+}
+
+impl Manifest {
+    /// Finds the recipe file in `dir`.
+    pub fn find(dir: &Path) -> Option<PathBuf> {
+        RECIPE_FILES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+    }
+
+    /// Reads a recipe file, picking the parser from its extension.
+    pub fn from_path(path: &Path) -> Result<Self> {
+        let format = Format::from_path(path)
+            .ok_or_else(|| Error::Invalid(format!("Unknown recipe format '{}'", path.display())))?;
+        let text = std::fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+        Self::parse(&text, format)
+    }
+
+    pub fn parse(text: &str, format: Format) -> Result<Self> {
+        match format {
+            Format::Json => Self::from_json_str(text),
+            Format::Sdl => Self::from_sdl_str(text),
+        }
+    }
+
+    pub fn from_json_str(text: &str) -> Result<Self> {
+        Self::from_json(&serde_json::from_str(text)?)
+    }
+
+    pub fn from_json(value: &Value) -> Result<Self> {
+        let object = as_object(value, "recipe")?;
+        let mut manifest = Manifest::default();
+        for (key, value) in object {
+            match key.as_str() {
+                "name" => manifest.name = as_string(value, key)?,
+                "description" => manifest.description = Some(as_string(value, key)?),
+                "homepage" => manifest.homepage = Some(as_string(value, key)?),
+                "copyright" => manifest.copyright = Some(as_string(value, key)?),
+                "license" => manifest.license = Some(as_string(value, key)?),
+                "version" => manifest.version = Some(as_string(value, key)?),
+                "systemDependencies" => manifest.system_dependencies = Some(as_string(value, key)?),
+                "authors" => manifest.authors = as_string_list(value, key)?,
+                "configurations" => {
+                    for config in as_array(value, key)? {
+                        manifest
+                            .configurations
+                            .push(configuration_from_json(config)?);
+                    }
+                }
+                "buildTypes" => {
+                    for (name, settings) in as_object(value, key)? {
+                        if is_comment(name) {
+                            continue;
+                        }
+                        let mut build_type = BuildType {
+                            name: name.clone(),
+                            ..Default::default()
+                        };
+                        for (key, value) in as_object(settings, name)? {
+                            settings_from_json(&mut build_type.settings, key, value)?;
+                        }
+                        manifest.build_types.push(build_type);
+                    }
+                }
+                "subPackages" => {
+                    for sub in as_array(value, key)? {
+                        manifest.sub_packages.push(match sub {
+                            Value::String(path) => SubPackage::Path(path.clone()),
+                            other => SubPackage::Inline(Box::new(Manifest::from_json(other)?)),
+                        });
+                    }
+                }
+                "subConfigurations" => {
+                    manifest.sub_configurations = as_string_map(value, key)?;
+                }
+                "toolchainRequirements" => {
+                    manifest.toolchain_requirements = as_string_map(value, key)?;
+                }
+                _ => settings_from_json(&mut manifest.settings, key, value)?,
+            }
+        }
+        Ok(manifest)
+    }
+
+    pub fn from_sdl_str(text: &str) -> Result<Self> {
+        Self::from_sdl(&sdl::parse(text)?.tags)
+    }
+
+    pub fn from_sdl(tags: &[sdl::Tag]) -> Result<Self> {
+        let mut manifest = Manifest::default();
+        for tag in tags {
+            match tag.name.as_str() {
+                "name" => manifest.name = single_string(tag)?,
+                "description" => manifest.description = Some(single_string(tag)?),
+                "homepage" => manifest.homepage = Some(single_string(tag)?),
+                "copyright" => manifest.copyright = Some(single_string(tag)?),
+                "license" => manifest.license = Some(single_string(tag)?),
+                "version" => manifest.version = Some(single_string(tag)?),
+                "systemDependencies" => manifest.system_dependencies = Some(single_string(tag)?),
+                "authors" => manifest.authors.extend(string_values(tag)?),
+                "configuration" => {
+                    let mut config = Configuration {
+                        name: single_string(tag)?,
+                        ..Default::default()
+                    };
+                    for child in &tag.children {
+                        if child.name == "platforms" {
+                            config.platforms.extend(string_values(child)?);
+                        } else {
+                            settings_from_sdl(&mut config.settings, child)?;
+                        }
+                    }
+                    manifest.configurations.push(config);
+                }
+                "buildType" => {
+                    let mut build_type = BuildType {
+                        name: single_string(tag)?,
+                        ..Default::default()
+                    };
+                    for child in &tag.children {
+                        settings_from_sdl(&mut build_type.settings, child)?;
+                    }
+                    manifest.build_types.push(build_type);
+                }
+                "subPackage" => manifest.sub_packages.push(if tag.children.is_empty() {
+                    SubPackage::Path(single_string(tag)?)
+                } else {
+                    SubPackage::Inline(Box::new(Manifest::from_sdl(&tag.children)?))
+                }),
+                "subConfiguration" => {
+                    let values = string_values(tag)?;
+                    let [dep, config] = <[String; 2]>::try_from(values).map_err(|_| {
+                        Error::Invalid("subConfiguration expects two values".to_string())
+                    })?;
+                    manifest.sub_configurations.push((dep, config));
+                }
+                "toolchainRequirements" => {
+                    for (key, value) in &tag.attributes {
+                        let value = value.as_str().ok_or_else(|| {
+                            Error::Invalid(format!("toolchainRequirements {key} must be a string"))
+                        })?;
+                        manifest
+                            .toolchain_requirements
+                            .push((key.clone(), value.to_string()));
+                    }
+                }
+                _ => settings_from_sdl(&mut manifest.settings, tag)?,
+            }
+        }
+        Ok(manifest)
+    }
+
+    /// Serializes the recipe to a JSON value in DUB's canonical key order.
+    pub fn to_json(&self) -> Value {
+        let mut object = Map::new();
+        object.insert("name".into(), self.name.clone().into());
+        for (key, value) in [
+            ("description", &self.description),
+            ("homepage", &self.homepage),
+        ] {
+            if let Some(value) = value {
+                object.insert(key.into(), value.clone().into());
+            }
+        }
+        if !self.authors.is_empty() {
+            object.insert("authors".into(), self.authors.clone().into());
+        }
+        for (key, value) in [
+            ("copyright", &self.copyright),
+            ("license", &self.license),
+            ("version", &self.version),
+            ("systemDependencies", &self.system_dependencies),
+        ] {
+            if let Some(value) = value {
+                object.insert(key.into(), value.clone().into());
+            }
+        }
+        settings_to_json(&self.settings, &mut object);
+        if !self.sub_configurations.is_empty() {
+            object.insert(
+                "subConfigurations".into(),
+                string_map_to_json(&self.sub_configurations),
+            );
+        }
+        if !self.toolchain_requirements.is_empty() {
+            object.insert(
+                "toolchainRequirements".into(),
+                string_map_to_json(&self.toolchain_requirements),
+            );
+        }
+        if !self.configurations.is_empty() {
+            let configs = self
+                .configurations
+                .iter()
+                .map(|config| {
+                    let mut object = Map::new();
+                    object.insert("name".into(), config.name.clone().into());
+                    if !config.platforms.is_empty() {
+                        object.insert("platforms".into(), config.platforms.clone().into());
+                    }
+                    settings_to_json(&config.settings, &mut object);
+                    Value::Object(object)
+                })
+                .collect();
+            object.insert("configurations".into(), Value::Array(configs));
+        }
+        if !self.build_types.is_empty() {
+            let mut types = Map::new();
+            for build_type in &self.build_types {
+                let mut object = Map::new();
+                settings_to_json(&build_type.settings, &mut object);
+                types.insert(build_type.name.clone(), Value::Object(object));
+            }
+            object.insert("buildTypes".into(), Value::Object(types));
+        }
+        if !self.sub_packages.is_empty() {
+            let subs = self
+                .sub_packages
+                .iter()
+                .map(|sub| match sub {
+                    SubPackage::Path(path) => Value::String(path.clone()),
+                    SubPackage::Inline(manifest) => manifest.to_json(),
+                })
+                .collect();
+            object.insert("subPackages".into(), Value::Array(subs));
+        }
+        Value::Object(object)
+    }
+
+    /// Serializes the recipe to SDLang tags in DUB's canonical order.
+    pub fn to_sdl(&self) -> Result<sdl::Document> {
+        let mut tags = vec![string_tag("name", &self.name)];
+        for (key, value) in [
+            ("description", &self.description),
+            ("homepage", &self.homepage),
+        ] {
+            if let Some(value) = value {
+                tags.push(string_tag(key, value));
+            }
+        }
+        if !self.authors.is_empty() {
+            tags.push(list_tag("authors", &self.authors, None));
+        }
+        for (key, value) in [
+            ("copyright", &self.copyright),
+            ("license", &self.license),
+            ("version", &self.version),
+            ("systemDependencies", &self.system_dependencies),
+        ] {
+            if let Some(value) = value {
+                tags.push(string_tag(key, value));
+            }
+        }
+        settings_to_sdl(&self.settings, &mut tags)?;
+        for (dep, config) in &self.sub_configurations {
+            let mut tag = sdl::Tag::new("subConfiguration");
+            tag.values = vec![
+                sdl::Value::String(dep.clone()),
+                sdl::Value::String(config.clone()),
+            ];
+            tags.push(tag);
+        }
+        if !self.toolchain_requirements.is_empty() {
+            let mut tag = sdl::Tag::new("toolchainRequirements");
+            tag.attributes = self
+                .toolchain_requirements
+                .iter()
+                .map(|(k, v)| (k.clone(), sdl::Value::String(v.clone())))
+                .collect();
+            tags.push(tag);
+        }
+        for config in &self.configurations {
+            let mut tag = string_tag("configuration", &config.name);
+            if !config.platforms.is_empty() {
+                tag.children
+                    .push(list_tag("platforms", &config.platforms, None));
+            }
+            settings_to_sdl(&config.settings, &mut tag.children)?;
+            tags.push(tag);
+        }
+        for build_type in &self.build_types {
+            let mut tag = string_tag("buildType", &build_type.name);
+            settings_to_sdl(&build_type.settings, &mut tag.children)?;
+            tags.push(tag);
+        }
+        for sub in &self.sub_packages {
+            tags.push(match sub {
+                SubPackage::Path(path) => string_tag("subPackage", path),
+                SubPackage::Inline(manifest) => sdl::Tag {
+                    name: "subPackage".into(),
+                    children: manifest.to_sdl()?.tags,
+                    ..Default::default()
+                },
+            });
+        }
+        Ok(sdl::Document {
+            tags,
+            trailing_comments: Vec::new(),
+        })
+    }
+
+    /// Every dependency declared at the root or in any configuration.
+    pub fn all_dependencies(&self) -> impl Iterator<Item = &Dependency> {
+        self.settings.dependencies.iter().chain(
+            self.configurations
+                .iter()
+                .flat_map(|c| c.settings.dependencies.iter()),
+        )
+    }
+
+    pub fn configuration(&self, name: &str) -> Option<&Configuration> {
+        self.configurations.iter().find(|c| c.name == name)
+    }
+
+    /// Field-level differences between two recipes, `self` being the old side.
+    pub fn diff(&self, other: &Manifest) -> Vec<FieldDiff> {
+        let mut diffs = Vec::new();
+        diff_values(String::new(), &self.to_json(), &other.to_json(), &mut diffs);
+        diffs
+    }
+
+    /// Reports problems DUB would reject or warn about.
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if self.name.is_empty() {
+            problems.push("missing package name".to_string());
+        } else if !self
+            .name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_')
+        {
+            problems.push(format!(
+                "package name '{}' must only contain lowercase letters, digits, '-' and '_'",
+                self.name
+            ));
+        }
+        for (i, config) in self.configurations.iter().enumerate() {
+            if config.name.is_empty() {
+                problems.push(format!("configuration #{} has no name", i + 1));
+            } else if self.configurations[..i]
+                .iter()
+                .any(|c| c.name == config.name)
+            {
+                problems.push(format!("duplicate configuration '{}'", config.name));
+            }
+        }
+        for dep in self.all_dependencies() {
+            if dep.version.is_none() && dep.path.is_none() && dep.repository.is_none() {
+                problems.push(format!(
+                    "dependency '{}' has no version, path or repository",
+                    dep.name
+                ));
+            }
+        }
+        for sub in &self.sub_packages {
+            if let SubPackage::Inline(manifest) = sub {
+                problems.extend(
+                    manifest
+                        .validate()
+                        .into_iter()
+                        .map(|p| format!("sub package '{}': {p}", manifest.name)),
+                );
+            }
+        }
+        problems
+    }
+}
+
+/// A recipe field whose value differs between two manifests.
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldDiff {
+    /// Location such as `configurations[unittest].versions`.
+    pub path: String,
+    /// Rendered old value, `None` when the field is absent.
+    pub old: Option<String>,
+    /// Rendered new value, `None` when the field is absent.
+    pub new: Option<String>,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |v: &Option<String>| v.clone().unwrap_or_else(|| "(missing)".to_string());
+        write!(
+            f,
+            "{}: {} -> {}",
+            self.path,
+            show(&self.old),
+            show(&self.new)
+        )
+    }
+}
+
+fn child_path(parent: &str, segment: &str) -> String {
+    if parent.is_empty() {
+        segment.to_string()
+    } else {
+        format!("{parent}.{segment}")
+    }
+}
+
+/// Labels array elements by their `name` when they have one.
+fn element_path(parent: &str, index: usize, value: &Value) -> String {
+    match value.get("name").and_then(Value::as_str) {
+        Some(name) => format!("{parent}[{name}]"),
+        None => format!("{parent}[{index}]"),
+    }
+}
+
+fn diff_values(path: String, old: &Value, new: &Value, diffs: &mut Vec<FieldDiff>) {
+    match (old, new) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, value) in a {
+                let path = child_path(&path, key);
+                match b.get(key) {
+                    Some(other) => diff_values(path, value, other, diffs),
+                    None => diffs.push(FieldDiff {
+                        path,
+                        old: Some(value.to_string()),
+                        new: None,
+                    }),
+                }
+            }
+            for (key, value) in b.iter().filter(|(key, _)| !a.contains_key(*key)) {
+                diffs.push(FieldDiff {
+                    path: child_path(&path, key),
+                    old: None,
+                    new: Some(value.to_string()),
+                });
+            }
+        }
+        (Value::Array(a), Value::Array(b))
+            if a.len() == b.len() && a.iter().chain(b).all(Value::is_object) =>
+        {
+            for (i, (x, y)) in a.iter().zip(b).enumerate() {
+                diff_values(element_path(&path, i, x), x, y, diffs);
+            }
+        }
+        (a, b) if a != b => diffs.push(FieldDiff {
+            path,
+            old: Some(a.to_string()),
+            new: Some(b.to_string()),
+        }),
+        _ => {}
+    }
+}
+
+/// JSON recipes carry comments as `"//"`-prefixed keys.
+fn is_comment(key: &str) -> bool {
+    key.starts_with("//")
+}
+
+pub(crate) fn as_object<'a>(value: &'a Value, key: &str) -> Result<&'a Map<String, Value>> {
+    value
+        .as_object()
+        .ok_or_else(|| Error::Invalid(format!("'{key}' must be an object")))
+}
+
+pub(crate) fn as_array<'a>(value: &'a Value, key: &str) -> Result<&'a Vec<Value>> {
+    value
+        .as_array()
+        .ok_or_else(|| Error::Invalid(format!("'{key}' must be an array")))
+}
+
+fn as_string(value: &Value, key: &str) -> Result<String> {
+    value
+        .as_str()
+        .map(str::to_string)
+        .ok_or_else(|| Error::Invalid(format!("'{key}' must be a string")))
+}
+
+fn as_string_list(value: &Value, key: &str) -> Result<Vec<String>> {
+    as_array(value, key)?
+        .iter()
+        .map(|v| as_string(v, key))
+        .collect()
+}
+
+fn as_string_map(value: &Value, key: &str) -> Result<Vec<(String, String)>> {
+    as_object(value, key)?
+        .iter()
+        .filter(|(k, _)| !is_comment(k))
+        .map(|(k, v)| Ok((k.clone(), as_string(v, k)?)))
+        .collect()
+}
+
+/// Splits `dflags-linux-ldc` into `("dflags", Some("linux-ldc"))`.
+pub fn split_platform(key: &str) -> (&str, Option<&str>) {
+    match key.split_once('-') {
+        Some((base, platform)) if !base.is_empty() => (base, Some(platform)),
+        _ => (key, None),
+    }
+}
+
+fn configuration_from_json(value: &Value) -> Result<Configuration> {
+    let mut config = Configuration::default();
+    for (key, value) in as_object(value, "configurations")? {
+        match key.as_str() {
+            "name" => config.name = as_string(value, key)?,
+            "platforms" => config.platforms = as_string_list(value, key)?,
+            _ => settings_from_json(&mut config.settings, key, value)?,
+        }
+    }
+    Ok(config)
+}
+
+fn settings_from_json(settings: &mut BuildSettings, key: &str, value: &Value) -> Result<()> {
+    if is_comment(key) {
+        return Ok(());
+    }
+    let (base, platform) = split_platform(key);
+    if base == "dependencies" && platform.is_none() {
+        for (name, spec) in as_object(value, key)? {
+            if !is_comment(name) {
+                settings
+                    .dependencies
+                    .push(dependency_from_json(name, spec)?);
+            }
+        }
+    } else if STRING_SETTINGS.contains(&base) {
+        settings.set_string_setting(base, as_string(value, key)?)?;
+    } else if LIST_SETTINGS.contains(&base) {
+        settings.push_list(base, platform, as_string_list(value, key)?);
+    } else {
+        if base == "dependencies" {
+            for (name, spec) in as_object(value, key)? {
+                if !is_comment(name) {
+                    dependency_from_json(name, spec)?;
+                }
+            }
+        }
+        settings.extra.insert(extra_key(key), value.clone());
+    }
+    Ok(())
+}
+
+/// DUB reads the ddox settings from JSON under a `-` or an `x:` prefix and
+/// writes SDL with `x:`; the model keeps the `x:` spelling.
+fn extra_key(key: &str) -> String {
+    match key.strip_prefix('-') {
+        Some(name @ ("ddoxFilterArgs" | "ddoxTool")) => format!("x:{name}"),
+        _ => key.to_string(),
+    }
+}
+
+fn dependency_from_json(name: &str, spec: &Value) -> Result<Dependency> {
+    let mut dep = Dependency {
+        name: name.to_string(),
+        ..Default::default()
+    };
+    match spec {
+        Value::String(version) => dep.version = Some(version.clone()),
+        Value::Object(attrs) => {
+            for (attr, value) in attrs {
+                let value = match value {
+                    Value::String(s) => sdl::Value::String(s.clone()),
+                    Value::Bool(b) => sdl::Value::Bool(*b),
+                    _ => sdl::Value::Null,
+                };
+                set_dependency_attribute(&mut dep, attr, &value)?;
+            }
+        }
+        _ => {
+            return Err(Error::Invalid(format!(
+                "dependency '{name}' must be a string or an object"
+            )))
+        }
+    }
+    Ok(dep)
+}
+
+fn dependency_from_sdl(tag: &sdl::Tag) -> Result<Dependency> {
+    let mut dep = Dependency {
+        name: single_string(tag)?,
+        ..Default::default()
+    };
+    for (attr, value) in &tag.attributes {
+        if attr != "platform" {
+            set_dependency_attribute(&mut dep, attr, value)?;
+        }
+    }
+    Ok(dep)
+}
+
+/// Applies a dependency attribute, failing the same way for both formats
+/// when its value has the wrong type.
+fn set_dependency_attribute(dep: &mut Dependency, attr: &str, value: &sdl::Value) -> Result<()> {
+    let string = || {
+        value.as_str().map(str::to_string).ok_or_else(|| {
+            Error::Invalid(format!(
+                "dependency '{}': '{attr}' must be a string",
+                dep.name
+            ))
+        })
+    };
+    let boolean = || {
+        value.as_bool().ok_or_else(|| {
+            Error::Invalid(format!(
+                "dependency '{}': '{attr}' must be a boolean",
+                dep.name
+            ))
+        })
+    };
+    match attr {
+        "version" => dep.version = Some(string()?),
+        "path" => dep.path = Some(string()?),
+        "repository" => dep.repository = Some(string()?),
+        "optional" => dep.optional = boolean()?,
+        "default" => dep.default = boolean()?,
+        _ => {}
+    }
+    Ok(())
+}
+
+fn dependency_to_json(dep: &Dependency) -> Value {
+    let simple = dep.path.is_none() && dep.repository.is_none() && !dep.optional && !dep.default;
+    match &dep.version {
+        Some(version) if simple => Value::String(version.clone()),
+        _ => {
+            let mut attrs = Map::new();
+            for (key, value) in [
+                ("version", &dep.version),
+                ("path", &dep.path),
+                ("repository", &dep.repository),
+            ] {
+                if let Some(value) = value {
+                    attrs.insert(key.into(), value.clone().into());
+                }
+            }
+            if dep.optional {
+                attrs.insert("optional".into(), true.into());
+            }
+            if dep.default {
+                attrs.insert("default".into(), true.into());
+            }
+            Value::Object(attrs)
+        }
+    }
+}
+
+fn dependency_to_sdl(dep: &Dependency) -> sdl::Tag {
+    let mut tag = string_tag("dependency", &dep.name);
+    for (key, value) in [
+        ("version", &dep.version),
+        ("path", &dep.path),
+        ("repository", &dep.repository),
+    ] {
+        if let Some(value) = value {
+            tag.attributes
+                .push((key.into(), sdl::Value::String(value.clone())));
+        }
+    }
+    if dep.optional {
+        tag.attributes
+            .push(("optional".into(), sdl::Value::Bool(true)));
+    }
+    if dep.default {
+        tag.attributes
+            .push(("default".into(), sdl::Value::Bool(true)));
+    }
+    tag
+}
+
+/// Merges an SDL tag for a setting without a typed field into `object`,
+/// using the JSON form DUB gives it: environment variables collect into
+/// one object per setting and platform, dependencies with a `platform`
+/// attribute into `dependencies-PLATFORM`, a configuration's
+/// `subConfiguration` tags into `subConfigurations` like the recipe's own,
+/// and other tags become their value, their values as an array or their
+/// children as an object.
+pub fn setting_from_sdl(tag: &sdl::Tag, object: &mut Map<String, Value>) -> Result<()> {
+    let name = tag.name.as_str();
+    let platform = tag.attribute("platform").and_then(sdl::Value::as_str);
+    let key = |base: &str| match platform {
+        Some(platform) => format!("{base}-{platform}"),
+        None => base.to_string(),
+    };
+    let entry = |object: &mut Map<String, Value>, key: String| -> Result<Map<String, Value>> {
+        match object.remove(&key) {
+            Some(Value::Object(existing)) => Ok(existing),
+            Some(_) => Err(Error::Invalid(format!("'{key}' is declared twice"))),
+            None => Ok(Map::new()),
+        }
+    };
+    if ENVIRONMENT_SETTINGS.contains(&name) {
+        let [variable, value] = <[String; 2]>::try_from(string_values(tag)?)
+            .map_err(|_| Error::Invalid(format!("'{name}' expects a variable name and a value")))?;
+        let key = key(name);
+        let mut variables = entry(object, key.clone())?;
+        variables.insert(variable, Value::String(value));
+        object.insert(key, Value::Object(variables));
+    } else if name == "dependency" {
+        let dep = dependency_from_sdl(tag)?;
+        let key = key("dependencies");
+        let mut deps = entry(object, key.clone())?;
+        deps.insert(dep.name.clone(), dependency_to_json(&dep));
+        object.insert(key, Value::Object(deps));
+    } else if name == "subConfiguration" {
+        let [dep, config] = <[String; 2]>::try_from(string_values(tag)?)
+            .map_err(|_| Error::Invalid("subConfiguration expects two values".to_string()))?;
+        let key = "subConfigurations".to_string();
+        let mut configs = entry(object, key.clone())?;
+        configs.insert(dep, Value::String(config));
+        object.insert(key, Value::Object(configs));
+    } else {
+        if let Some((attr, _)) = tag.attributes.iter().find(|(attr, _)| attr != "platform") {
+            return Err(Error::Invalid(format!(
+                "'{name}' has an unsupported attribute '{attr}'"
+            )));
+        }
+        let value = if !tag.children.is_empty() {
+            let mut children = Map::new();
+            for child in &tag.children {
+                setting_from_sdl(child, &mut children)?;
+            }
+            Value::Object(children)
+        } else if let ([value], false) = (tag.values.as_slice(), name == "x:ddoxFilterArgs") {
+            sdl_value_to_json(value)
+        } else {
+            Value::Array(tag.values.iter().map(sdl_value_to_json).collect())
+        };
+        let key = key(name);
+        match (object.get_mut(&key), value) {
+            (Some(Value::Array(existing)), Value::Array(values)) => existing.extend(values),
+            (_, value) => {
+                object.insert(key, value);
+            }
+        }
+    }
+    Ok(())
+}
+
+/// SDL tags for a setting without a typed field, the inverse of
+/// [`setting_from_sdl`].
+pub fn setting_to_sdl(key: &str, value: &Value) -> Result<Vec<sdl::Tag>> {
+    let (base, platform) = split_platform(key);
+    let with_platform = |mut tag: sdl::Tag| {
+        if let Some(platform) = platform {
+            tag.attributes
+                .push(("platform".into(), sdl::Value::String(platform.to_string())));
+        }
+        tag
+    };
+    if ENVIRONMENT_SETTINGS.contains(&base) {
+        let mut tags = Vec::new();
+        for (variable, value) in as_object(value, key)? {
+            if is_comment(variable) {
+                continue;
+            }
+            let value = value.as_str().ok_or_else(|| {
+                Error::Invalid(format!("'{key}': variable '{variable}' must be a string"))
+            })?;
+            let mut tag = string_tag(base, variable);
+            tag.values.push(sdl::Value::String(value.to_string()));
+            tags.push(with_platform(tag));
+        }
+        return Ok(tags);
+    }
+    if base == "dependencies" && platform.is_some() {
+        let mut tags = Vec::new();
+        for (name, spec) in as_object(value, key)? {
+            if !is_comment(name) {
+                tags.push(with_platform(dependency_to_sdl(&dependency_from_json(
+                    name, spec,
+                )?)));
+            }
+        }
+        return Ok(tags);
+    }
+    if key == "subConfigurations" {
+        let mut tags = Vec::new();
+        for (dep, config) in as_string_map(value, key)? {
+            let mut tag = string_tag("subConfiguration", &dep);
+            tag.values.push(sdl::Value::String(config));
+            tags.push(tag);
+        }
+        return Ok(tags);
+    }
+    let mut tag = sdl::Tag::new(extra_key(key));
+    match value {
+        Value::Array(items) => {
+            for item in items {
+                tag.values.push(json_value_to_sdl(item, key)?);
+            }
+        }
+        Value::Object(object) => {
+            for (key, value) in object.iter().filter(|(key, _)| !is_comment(key)) {
+                tag.children.extend(setting_to_sdl(key, value)?);
+            }
+        }
+        scalar => tag.values.push(json_value_to_sdl(scalar, key)?),
+    }
+    Ok(vec![tag])
+}
+
+pub(crate) fn sdl_value_to_json(value: &sdl::Value) -> Value {
+    match value {
+        sdl::Value::String(s) => Value::String(s.clone()),
+        sdl::Value::Int(i) => Value::from(*i),
+        sdl::Value::Float(x) => Value::from(*x),
+        sdl::Value::Bool(b) => Value::Bool(*b),
+        sdl::Value::Null => Value::Null,
+    }
+}
+
+pub(crate) fn json_value_to_sdl(value: &Value, key: &str) -> Result<sdl::Value> {
+    Ok(match value {
+        Value::String(s) => sdl::Value::String(s.clone()),
+        Value::Bool(b) => sdl::Value::Bool(*b),
+        Value::Null => sdl::Value::Null,
+        Value::Number(n) => match n.as_i64() {
+            Some(i) => sdl::Value::Int(i),
+            None => sdl::Value::Float(n.as_f64().unwrap_or_default()),
+        },
+        other => {
+            return Err(Error::Invalid(format!(
+                "'{key}': cannot represent {other} as an SDL value"
+            )))
+        }
+    })
+}
+
+fn string_values(tag: &sdl::Tag) -> Result<Vec<String>> {
+    tag.values
+        .iter()
+        .map(|v| {
+            v.as_str()
+                .map(str::to_string)
+                .ok_or_else(|| Error::Invalid(format!("'{}' expects string values", tag.name)))
+        })
+        .collect()
+}
+
+fn single_string(tag: &sdl::Tag) -> Result<String> {
+    match tag.values.as_slice() {
+        [sdl::Value::String(s)] => Ok(s.clone()),
+        _ => Err(Error::Invalid(format!(
+            "'{}' expects a single string value",
+            tag.name
+        ))),
+    }
+}
+
+fn settings_from_sdl(settings: &mut BuildSettings, tag: &sdl::Tag) -> Result<()> {
+    let name = tag.name.as_str();
+    let platform = tag.attribute("platform").and_then(sdl::Value::as_str);
+    if name == "dependency" && platform.is_none() {
+        settings.dependencies.push(dependency_from_sdl(tag)?);
+    } else if STRING_SETTINGS.contains(&name) {
+        settings.set_string_setting(name, single_string(tag)?)?;
+    } else if LIST_SETTINGS.contains(&name) {
+        settings.push_list(name, platform, string_values(tag)?);
+    } else {
+        setting_from_sdl(tag, &mut settings.extra)?;
+    }
+    Ok(())
+}
+
+fn string_map_to_json(pairs: &[(String, String)]) -> Value {
+    Value::Object(
+        pairs
+            .iter()
+            .map(|(k, v)| (k.clone(), Value::String(v.clone())))
+            .collect(),
+    )
+}
+
+fn settings_to_json(settings: &BuildSettings, object: &mut Map<String, Value>) {
+    for key in STRING_SETTINGS {
+        if let Some(value) = settings.string_setting(key) {
+            object.insert(key.to_string(), value.into());
+        }
+    }
+    if !settings.dependencies.is_empty() {
+        let deps = settings
+            .dependencies
+            .iter()
+            .map(|dep| (dep.name.clone(), dependency_to_json(dep)))
+            .collect();
+        object.insert("dependencies".into(), Value::Object(deps));
+    }
+    for list in &settings.lists {
+        let key = match &list.platform {
+            Some(platform) => format!("{}-{platform}", list.key),
+            None => list.key.clone(),
+        };
+        object.insert(key, list.values.clone().into());
+    }
+    for (key, value) in &settings.extra {
+        object.insert(key.clone(), value.clone());
+    }
+}
+
+pub(crate) fn string_tag(name: &str, value: &str) -> sdl::Tag {
+    let mut tag = sdl::Tag::new(name);
+    tag.values.push(sdl::Value::String(value.to_string()));
+    tag
+}
+
+fn list_tag(name: &str, values: &[String], platform: Option<&str>) -> sdl::Tag {
+    let mut tag = sdl::Tag::new(name);
+    tag.values = values.iter().cloned().map(sdl::Value::String).collect();
+    if let Some(platform) = platform {
+        tag.attributes
+            .push(("platform".into(), sdl::Value::String(platform.to_string())));
+    }
+    tag
+}
+
+fn settings_to_sdl(settings: &BuildSettings, tags: &mut Vec<sdl::Tag>) -> Result<()> {
+    for key in STRING_SETTINGS {
+        if let Some(value) = settings.string_setting(key) {
+            tags.push(string_tag(key, value));
+        }
+    }
+    tags.extend(settings.dependencies.iter().map(dependency_to_sdl));
+    for list in &settings.lists {
+        tags.push(list_tag(&list.key, &list.values, list.platform.as_deref()));
+    }
+    for (key, value) in &settings.extra {
+        tags.extend(setting_to_sdl(key, value)?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    const JSON: &str = r#"{
+        "name": "app",
+        "authors": ["Jane Doe"],
+        "license": "MIT",
+        "targetType": "executable",
+        "dependencies": {
+            "vibe-d": "~>0.9.5",
+            "local": { "path": "../local", "optional": true }
+        },
+        "sourcePaths": ["source"],
+        "dflags-ldc": ["-mcpu=native"],
+        "configurations": [
+            { "name": "default" },
+            { "name": "unittest", "platforms": ["posix"], "versions": ["Testing"] }
+        ],
+        "buildTypes": { "profile-gc": { "buildOptions": ["debugInfo"] } },
+        "subPackages": ["./sub", { "name": "inline", "targetType": "library" }]
+    }"#;
+
+    const SDL: &str = r#"name "app"
+authors "Jane Doe"
+license "MIT"
+targetType "executable"
+dependency "vibe-d" version="~>0.9.5"
+dependency "local" path="../local" optional=true
+sourcePaths "source"
+dflags "-mcpu=native" platform="ldc"
+configuration "default"
+configuration "unittest" {
+	platforms "posix"
+	versions "Testing"
+}
+buildType "profile-gc" {
+	buildOptions "debugInfo"
+}
+subPackage "./sub"
+subPackage {
+	name "inline"
+	targetType "library"
+}
+"#;
+
+    #[test]
+    fn test_parse_json() {
+        let manifest = Manifest::from_json_str(JSON).unwrap();
+        assert_eq!(manifest.name, "app");
+        assert_eq!(manifest.settings.target_type, Some(TargetType::Executable));
+        assert_eq!(manifest.settings.dependencies.len(), 2);
+        let local = manifest.settings.dependency("local").unwrap();
+        assert_eq!(local.path.as_deref(), Some("../local"));
+        assert!(local.optional);
+        assert_eq!(manifest.settings.source_paths(), vec!["source"]);
+        assert_eq!(manifest.settings.lists[1].platform.as_deref(), Some("ldc"));
+        assert_eq!(manifest.configurations[1].platforms, vec!["posix"]);
+        assert_eq!(manifest.build_types[0].name, "profile-gc");
+        assert_eq!(manifest.sub_packages.len(), 2);
+    }
+
+    #[test]
+    fn test_json_and_sdl_agree() {
+        let json = Manifest::from_json_str(JSON).unwrap();
+        let sdl = Manifest::from_sdl_str(SDL).unwrap();
+        assert_eq!(json, sdl);
+    }
+
+    #[test]
+    fn test_serialize_round_trip() {
+        let manifest = Manifest::from_sdl_str(SDL).unwrap();
+        let json = serde_json::to_string(&manifest.to_json()).unwrap();
+        assert_eq!(Manifest::from_json_str(&json).unwrap(), manifest);
+        let sdl = sdl::to_string(&manifest.to_sdl().unwrap());
+        assert_eq!(Manifest::from_sdl_str(&sdl).unwrap(), manifest);
+    }
+
+    #[test]
+    fn test_repeated_sdl_lists_merge() {
+        let manifest =
+            Manifest::from_sdl_str("name \"x\"\nsourcePaths \"a\"\nsourcePaths \"b\"\n").unwrap();
+        assert_eq!(manifest.settings.lists.len(), 1);
+        assert_eq!(manifest.settings.source_paths(), vec!["a", "b"]);
+    }
+
+    #[test]
+    fn test_invalid_recipes() {
+        assert!(Manifest::from_json_str(r#"{"name": 1}"#).is_err());
+        assert!(Manifest::from_json_str(r#"{"targetType": "binary"}"#).is_err());
+        assert!(Manifest::from_sdl_str("name \"a\" \"b\"").is_err());
+    }
+
+    #[test]
+    fn test_unmodeled_settings_survive() {
+        let json = r#"{
+            "name": "app",
+            "preBuildEnvironments": {"CC": "clang", "OPT": "-O2"},
+            "environments-posix": {"LANG": "C"},
+            "-ddoxFilterArgs": ["--min-protection=Public"],
+            "x:ddoxTool": "scod",
+            "dependencies-windows": {"winapi": {"version": "~>1.0", "optional": true}},
+            "configurations": [{"name": "ci", "x:note": "local", "runEnvironments": {"CI": "1"},
+                "subConfigurations": {"vibe-d": "vibe-core"}}]
+        }"#;
+        let sdl = "name \"app\"\n\
+                   preBuildEnvironments \"CC\" \"clang\"\n\
+                   preBuildEnvironments \"OPT\" \"-O2\"\n\
+                   environments \"LANG\" \"C\" platform=\"posix\"\n\
+                   x:ddoxFilterArgs \"--min-protection=Public\"\n\
+                   x:ddoxTool \"scod\"\n\
+                   dependency \"winapi\" version=\"~>1.0\" optional=true platform=\"windows\"\n\
+                   configuration \"ci\" {\n\
+                   \tx:note \"local\"\n\
+                   \trunEnvironments \"CI\" \"1\"\n\
+                   \tsubConfiguration \"vibe-d\" \"vibe-core\"\n\
+                   }\n";
+        let from_json = Manifest::from_json_str(json).unwrap();
+        let from_sdl = Manifest::from_sdl_str(sdl).unwrap();
+        assert_eq!(from_json.diff(&from_sdl), []);
+        assert_eq!(from_json, from_sdl);
+        assert_eq!(
+            from_json.settings.extra.keys().collect::<Vec<_>>(),
+            [
+                "preBuildEnvironments",
+                "environments-posix",
+                "x:ddoxFilterArgs",
+                "x:ddoxTool",
+                "dependencies-windows"
+            ]
+        );
+        assert_eq!(
+            from_json.configurations[0].settings.extra["x:note"],
+            "local"
+        );
+
+        assert_eq!(sdl::to_string(&from_json.to_sdl().unwrap()), sdl);
+        let json = serde_json::to_string(&from_json.to_json()).unwrap();
+        assert_eq!(Manifest::from_json_str(&json).unwrap(), from_json);
+    }
+
+    #[test]
+    fn test_dependency_attribute_errors_agree() {
+        for (json, sdl, message) in [
+            (
+                r#"{"name": "a", "dependencies": {"b": {"version": 1}}}"#,
+                "name \"a\"\ndependency \"b\" version=1\n",
+                "dependency 'b': 'version' must be a string",
+            ),
+            (
+                r#"{"name": "a", "dependencies": {"b": {"path": "../b", "optional": "yes"}}}"#,
+                "name \"a\"\ndependency \"b\" path=\"../b\" optional=\"yes\"\n",
+                "dependency 'b': 'optional' must be a boolean",
+            ),
+        ] {
+            let json = Manifest::from_json_str(json).unwrap_err().to_string();
+            let sdl = Manifest::from_sdl_str(sdl).unwrap_err().to_string();
+            assert_eq!((json.as_str(), sdl.as_str()), (message, message));
+        }
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = Manifest::from_json_str(JSON).unwrap();
+        assert!(old.diff(&old.clone()).is_empty());
+
+        let mut new = old.clone();
+        new.license = None;
+        new.configurations[1].settings.lists[0].values = vec!["Other".into()];
+        new.settings.dependencies[0].version = Some("~>0.10".into());
+        new.settings
+            .extra
+            .insert("preBuildEnvironments".into(), json!({"CC": "gcc"}));
+        new.configurations[1]
+            .settings
+            .extra
+            .insert("x:ddoxTool".into(), "scod".into());
+        let diffs: Vec<String> = old.diff(&new).iter().map(|d| d.to_string()).collect();
+        assert_eq!(
+            diffs,
+            vec![
+                "license: \"MIT\" -> (missing)",
+                "dependencies.vibe-d: \"~>0.9.5\" -> \"~>0.10\"",
+                "configurations[unittest].versions: [\"Testing\"] -> [\"Other\"]",
+                "configurations[unittest].x:ddoxTool: (missing) -> \"scod\"",
+                "preBuildEnvironments: (missing) -> {\"CC\":\"gcc\"}",
+            ]
+        );
+
+        // Settings without a typed field are compared too.
+        let with_env = |value: &str| {
+            Manifest::from_json_str(&format!(
+                r#"{{"name": "app", "preBuildEnvironments": {{"CC": "{value}"}}}}"#
+            ))
+            .unwrap()
+        };
+        let diffs: Vec<String> = with_env("clang")
+            .diff(&with_env("gcc"))
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(diffs, ["preBuildEnvironments.CC: \"clang\" -> \"gcc\""]);
+    }
+
+    #[test]
+    fn test_validate() {
+        let manifest = Manifest::from_json_str(
+            r#"{"name": "Bad Name", "dependencies": {"x": {}},
+                "configurations": [{"name": "a"}, {"name": "a"}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            manifest.validate(),
+            vec![
+                "package name 'Bad Name' must only contain lowercase letters, digits, '-' and '_'",
+                "duplicate configuration 'a'",
+                "dependency 'x' has no version, path or repository",
+            ]
+        );
+        assert!(Manifest::from_json_str(JSON).unwrap().validate().is_empty());
+    }
+}
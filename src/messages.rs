@@ -0,0 +1,293 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Cargo-compatible JSON messages for `--message-format=json`.
+//!
+//! The shapes follow `cargo build --message-format=json` closely enough for
+//! consumers written against Cargo's output (such as the `cargo_metadata`
+//! crate) to read them.
+
+use crate::describe::Package;
+use crate::diagnostics::{Diagnostic, Level, Location};
+use crate::Manifest;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// One line of output, tagged by `reason`.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(tag = "reason", rename_all = "kebab-case")]
+pub enum Message {
+    CompilerMessage {
+        package_id: String,
+        manifest_path: PathBuf,
+        target: Target,
+        message: CompilerMessage,
+    },
+    CompilerArtifact {
+        package_id: String,
+        manifest_path: PathBuf,
+        target: Target,
+        profile: Profile,
+        features: Vec<String>,
+        filenames: Vec<PathBuf>,
+        executable: Option<PathBuf>,
+        fresh: bool,
+    },
+    BuildFinished {
+        success: bool,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Target {
+    pub kind: Vec<String>,
+    pub crate_types: Vec<String>,
+    pub name: String,
+    pub src_path: PathBuf,
+    pub edition: String,
+    pub doc: bool,
+    pub doctest: bool,
+    pub test: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Profile {
+    pub opt_level: String,
+    pub debuginfo: u32,
+    pub debug_assertions: bool,
+    pub overflow_checks: bool,
+    pub test: bool,
+}
+
+/// A diagnostic in rustc's JSON shape.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct CompilerMessage {
+    #[serde(rename = "$message_type")]
+    pub message_type: &'static str,
+    pub message: String,
+    pub code: Option<Code>,
+    pub level: &'static str,
+    pub spans: Vec<Span>,
+    pub children: Vec<CompilerMessage>,
+    pub rendered: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Code {
+    pub code: String,
+    pub explanation: Option<String>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Span {
+    pub file_name: String,
+    pub byte_start: u32,
+    pub byte_end: u32,
+    pub line_start: u32,
+    pub line_end: u32,
+    pub column_start: u32,
+    pub column_end: u32,
+    pub is_primary: bool,
+    pub text: Vec<String>,
+    pub label: Option<String>,
+    pub suggested_replacement: Option<String>,
+    pub suggestion_applicability: Option<String>,
+    pub expansion: Option<String>,
+}
+
+/// Cargo-style package id: `path+file:///dir#name@version`.
+pub fn package_id(package: &Package) -> String {
+    let path = package.path.display().to_string();
+    let path = path.trim_end_matches(['/', '\\']);
+    format!("path+file://{path}#{}@{}", package.name, package.version)
+}
+
+/// The recipe of `package`, or where it would be.
+fn manifest_path(package: &Package) -> PathBuf {
+    Manifest::find(&package.path).unwrap_or_else(|| package.path.join("dub.json"))
+}
+
+impl Target {
+    pub fn of(package: &Package) -> Self {
+        let kind = match package.target_type.as_str() {
+            "executable" => "bin",
+            "library" => "lib",
+            "staticLibrary" => "staticlib",
+            "dynamicLibrary" => "cdylib",
+            other => other,
+        };
+        let name = if package.target_name.is_empty() {
+            &package.name
+        } else {
+            &package.target_name
+        };
+        let src_path = match package.main_source_file.as_str() {
+            "" => package.path.clone(),
+            main => package.resolve(main),
+        };
+        Self {
+            kind: vec![kind.to_string()],
+            crate_types: vec![kind.to_string()],
+            name: name.clone(),
+            src_path,
+            edition: "2021".to_string(),
+            doc: false,
+            doctest: false,
+            test: true,
+        }
+    }
+}
+
+impl Profile {
+    /// Approximates a Cargo profile from a DUB build type.
+    pub fn of(build_type: &str) -> Self {
+        let release = build_type.starts_with("release");
+        let debuginfo = matches!(build_type, "debug" | "release-debug" | "plain")
+            || build_type.starts_with("unittest");
+        Self {
+            opt_level: if release { "3" } else { "0" }.to_string(),
+            debuginfo: if debuginfo { 2 } else { 0 },
+            debug_assertions: !release,
+            overflow_checks: false,
+            test: build_type.starts_with("unittest"),
+        }
+    }
+}
+
+impl Span {
+    fn primary(location: &Location) -> Self {
+        let column = location.column.unwrap_or(1);
+        Self {
+            file_name: location.file.clone(),
+            byte_start: 0,
+            byte_end: 0,
+            line_start: location.line,
+            line_end: location.line,
+            column_start: column,
+            column_end: column + 1,
+            is_primary: true,
+            text: Vec::new(),
+            label: None,
+            suggested_replacement: None,
+            suggestion_applicability: None,
+            expansion: None,
+        }
+    }
+}
+
+impl CompilerMessage {
+    /// Converts a parsed diagnostic. `rendered` is the text a human would see.
+    pub fn from_diagnostic(diagnostic: &Diagnostic, rendered: String) -> Self {
+        let (level, code) = match diagnostic.level {
+            Level::Error => ("error", None),
+            Level::Warning => ("warning", None),
+            Level::Deprecation => (
+                "warning",
+                Some(Code {
+                    code: "deprecated".to_string(),
+                    explanation: None,
+                }),
+            ),
+        };
+        let children = diagnostic
+            .notes
+            .iter()
+            .map(|note| CompilerMessage {
+                message_type: "diagnostic",
+                message: note.message.clone(),
+                code: None,
+                level: "note",
+                spans: note.location.iter().map(Span::primary).collect(),
+                children: Vec::new(),
+                rendered: None,
+            })
+            .collect();
+        Self {
+            message_type: "diagnostic",
+            message: diagnostic.message.clone(),
+            code,
+            level,
+            spans: diagnostic.location.iter().map(Span::primary).collect(),
+            children,
+            rendered: Some(rendered),
+        }
+    }
+}
+
+impl Message {
+    pub fn compiler_message(package: &Package, message: CompilerMessage) -> Self {
+        Message::CompilerMessage {
+            package_id: package_id(package),
+            manifest_path: manifest_path(package),
+            target: Target::of(package),
+            message,
+        }
+    }
+
+    /// The artifact message for `package`, if it produces a file.
+    pub fn artifact(package: &Package, build_type: &str) -> Option<Self> {
+        let path = package.artifact_path()?;
+        let executable = (package.target_type == "executable").then(|| path.clone());
+        Some(Message::CompilerArtifact {
+            package_id: package_id(package),
+            manifest_path: manifest_path(package),
+            target: Target::of(package),
+            profile: Profile::of(build_type),
+            features: Vec::new(),
+            filenames: vec![path],
+            executable,
+            fresh: false,
+        })
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("messages serialize")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::describe::tests::DESCRIBE;
+    use crate::describe::Description;
+    use crate::diagnostics::parse_line;
+
+    #[test]
+    fn test_compiler_message() {
+        let desc = Description::from_json(DESCRIBE).unwrap();
+        let line = "/home/u/.dub/packages/mir-core/1.7.1/mir-core/source/mir/utility.d(3,1): \
+                    Deprecation: `x` is deprecated";
+        let diagnostic = parse_line(line).unwrap();
+        let package = desc
+            .package_for_file(&diagnostic.location.as_ref().unwrap().file)
+            .unwrap();
+        assert_eq!(package.name, "mir-core");
+        let message = CompilerMessage::from_diagnostic(&diagnostic, line.to_string());
+        let json: serde_json::Value =
+            serde_json::from_str(&Message::compiler_message(package, message).to_json()).unwrap();
+        assert_eq!(json["reason"], "compiler-message");
+        assert_eq!(
+            json["package_id"],
+            "path+file:///home/u/.dub/packages/mir-core/1.7.1/mir-core#mir-core@1.7.1"
+        );
+        assert_eq!(json["target"]["kind"][0], "staticlib");
+        assert_eq!(json["message"]["level"], "warning");
+        assert_eq!(json["message"]["code"]["code"], "deprecated");
+        assert_eq!(json["message"]["spans"][0]["line_start"], 3);
+    }
+
+    #[test]
+    fn test_artifact_and_finished() {
+        let desc = Description::from_json(DESCRIBE).unwrap();
+        let artifact = Message::artifact(desc.root().unwrap(), "release").unwrap();
+        let json: serde_json::Value = serde_json::from_str(&artifact.to_json()).unwrap();
+        assert_eq!(json["reason"], "compiler-artifact");
+        assert_eq!(json["filenames"][0], "/work/dlib/lib/libdlib.a");
+        assert_eq!(json["executable"], serde_json::Value::Null);
+        assert_eq!(json["profile"]["opt_level"], "3");
+        assert_eq!(
+            Message::BuildFinished { success: true }.to_json(),
+            r#"{"reason":"build-finished","success":true}"#
+        );
+    }
+}
@@ -0,0 +1,225 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! How far the versions locked in `dub.selections.json` are behind the
+//! registry, like `cargo outdated`.
+
+use crate::describe::render;
+use crate::registry::PackageInfo;
+use crate::selections::{base_name, Selection, Selections};
+use crate::version::{Version, VersionReq};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// Whether the root package depends on a package itself.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Kind {
+    Normal,
+    Transitive,
+}
+
+impl fmt::Display for Kind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Kind::Normal => "Normal",
+            Kind::Transitive => "Transitive",
+        })
+    }
+}
+
+/// A locked package with a newer release available.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct Outdated {
+    pub name: String,
+    pub current: String,
+    /// The newest release every requirement on the package accepts.
+    pub compatible: Option<String>,
+    pub latest: Option<String>,
+    pub kind: Kind,
+}
+
+/// Compares every registry version in `selections` with `infos`.
+/// Requirements come from `direct`, the root recipe's dependencies, and
+/// from the registry's recipe of each selected package.
+pub fn report(
+    selections: &Selections,
+    direct: &BTreeMap<String, String>,
+    infos: &BTreeMap<String, PackageInfo>,
+) -> Vec<Outdated> {
+    let locked: BTreeMap<&str, &str> = selections
+        .versions
+        .iter()
+        .filter_map(|(name, selection)| match selection {
+            Selection::Version(version) if !version.starts_with('~') => {
+                Some((name.as_str(), version.as_str()))
+            }
+            _ => None,
+        })
+        .collect();
+
+    let mut requirements: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (name, requirement) in direct {
+        requirements
+            .entry(base_name(name))
+            .or_default()
+            .push(requirement);
+    }
+    for (name, version) in &locked {
+        let Some(release) = infos.get(*name).and_then(|info| info.version(version)) else {
+            continue;
+        };
+        for (dependency, requirement) in release.requirements() {
+            let dependency = base_name(dependency);
+            if dependency != *name {
+                requirements
+                    .entry(dependency)
+                    .or_default()
+                    .push(requirement);
+            }
+        }
+    }
+
+    let mut outdated = Vec::new();
+    for (name, current) in locked {
+        let (Some(info), Ok(version)) = (infos.get(name), current.parse::<Version>()) else {
+            continue;
+        };
+        let accepted: Vec<VersionReq> = requirements
+            .get(name)
+            .into_iter()
+            .flatten()
+            .filter_map(|requirement| requirement.parse().ok())
+            .collect();
+        let compatible = info
+            .versions
+            .iter()
+            .filter_map(|v| Some((v.version.parse::<Version>().ok()?, &v.version)))
+            .filter(|(candidate, _)| accepted.iter().all(|req| req.matches(candidate)))
+            .filter(|(candidate, _)| !candidate.is_prerelease() || *candidate == version)
+            .max_by(|a, b| a.0.cmp(&b.0));
+        let latest = info
+            .latest()
+            .and_then(|v| Some((v.version.parse().ok()?, &v.version)));
+        let newer = |found: &Option<(Version, &String)>| {
+            found.as_ref().is_some_and(|(found, _)| *found > version)
+        };
+        if newer(&compatible) || newer(&latest) {
+            outdated.push(Outdated {
+                name: name.to_string(),
+                current: current.to_string(),
+                compatible: compatible.map(|(_, v)| v.clone()),
+                latest: latest.map(|(_, v)| v.clone()),
+                kind: if direct.keys().any(|d| base_name(d) == name) {
+                    Kind::Normal
+                } else {
+                    Kind::Transitive
+                },
+            });
+        }
+    }
+    outdated
+}
+
+/// The report as columns; `---` marks a column matching the current version.
+pub fn table(outdated: &[Outdated]) -> String {
+    let rows = outdated.iter().map(|o| {
+        let show = |version: &Option<String>| match version {
+            Some(version) if *version == o.current => "---".to_string(),
+            Some(version) => version.clone(),
+            None => "-".to_string(),
+        };
+        [
+            o.name.clone(),
+            o.current.clone(),
+            show(&o.compatible),
+            show(&o.latest),
+            o.kind.to_string(),
+        ]
+    });
+    render(["Name", "Current", "Compatible", "Latest", "Kind"], rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_report() {
+        let selections = Selections::from_json_str(
+            r#"{"fileVersion": 1, "versions": {"mir-core": "1.6.0", "mir-algorithm": "3.20.0",
+                "silly": "1.1.1", "dlib": {"path": "../dlib"}, "vibe-d": "~master"}}"#,
+        )
+        .unwrap();
+        let direct = BTreeMap::from([
+            ("mir-algorithm".to_string(), "~>3.20".to_string()),
+            ("silly".to_string(), ">=1.0.0".to_string()),
+        ]);
+        let infos: BTreeMap<String, PackageInfo> = serde_json::from_value(json!({
+            "mir-algorithm": {"name": "mir-algorithm", "versions": [
+                {"version": "3.20.0", "dependencies": {"mir-core": "~>1.6.0"}},
+                {"version": "3.22.1", "dependencies": {"mir-core": "~>1.7.0"}},
+                {"version": "4.0.0"}
+            ]},
+            "mir-core": {"name": "mir-core", "versions": [
+                {"version": "1.6.0"}, {"version": "1.6.3"}, {"version": "1.7.1"}
+            ]},
+            "silly": {"name": "silly", "versions": [
+                {"version": "1.1.1"}, {"version": "1.2.0-beta.1"}
+            ]}
+        }))
+        .unwrap();
+
+        let outdated = report(&selections, &direct, &infos);
+        assert_eq!(
+            outdated,
+            [
+                Outdated {
+                    name: "mir-algorithm".to_string(),
+                    current: "3.20.0".to_string(),
+                    compatible: Some("3.22.1".to_string()),
+                    latest: Some("4.0.0".to_string()),
+                    kind: Kind::Normal,
+                },
+                Outdated {
+                    name: "mir-core".to_string(),
+                    current: "1.6.0".to_string(),
+                    compatible: Some("1.6.3".to_string()),
+                    latest: Some("1.7.1".to_string()),
+                    kind: Kind::Transitive,
+                },
+            ]
+        );
+        assert_eq!(
+            table(&outdated),
+            "Name           Current  Compatible  Latest  Kind\n\
+             mir-algorithm  3.20.0   3.22.1      4.0.0   Normal\n\
+             mir-core       1.6.0    1.6.3       1.7.1   Transitive\n"
+        );
+    }
+
+    #[test]
+    fn test_only_latest_newer() {
+        let selections =
+            Selections::from_json_str(r#"{"fileVersion": 1, "versions": {"silly": "1.1.1"}}"#)
+                .unwrap();
+        let direct = BTreeMap::from([("silly".to_string(), "~>1.1.0".to_string())]);
+        let infos: BTreeMap<String, PackageInfo> = serde_json::from_value(json!({
+            "silly": {"name": "silly", "versions": [{"version": "1.1.1"}, {"version": "2.0.0"}]}
+        }))
+        .unwrap();
+        let outdated = report(&selections, &direct, &infos);
+        assert_eq!(
+            table(&outdated),
+            "Name   Current  Compatible  Latest  Kind\n\
+             silly  1.1.1    ---         2.0.0   Normal\n"
+        );
+        assert_eq!(
+            serde_json::to_value(&outdated).unwrap(),
+            json!([{"name": "silly", "current": "1.1.1", "compatible": "1.1.1",
+                    "latest": "2.0.0", "kind": "normal"}])
+        );
+    }
+}
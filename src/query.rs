@@ -0,0 +1,346 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! A subset of jq for picking values out of JSON such as `dub describe`
+//! output: paths (`.targets[0].buildSettings`, `.packages[]`), pipes and
+//! `select(PATH == VALUE)`.
+
+use serde_json::Value;
+use std::fmt;
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum Error {
+    /// The query could not be parsed; `column` is 1-based.
+    Syntax {
+        query: String,
+        column: usize,
+        expected: &'static str,
+    },
+    /// `[]` applied to a scalar.
+    Iterate(&'static str),
+    /// `.key` or `[N]` applied to a value it cannot index.
+    Index { kind: &'static str, with: String },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Syntax {
+                query,
+                column,
+                expected,
+            } => write!(
+                f,
+                "Invalid query '{query}': expected {expected} at column {column}"
+            ),
+            Error::Iterate(kind) => write!(f, "Cannot iterate over {kind}"),
+            Error::Index { kind, with } => write!(f, "Cannot index {kind} with {with}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+#[derive(Clone, Debug, PartialEq)]
+enum Step {
+    Key(String),
+    /// Negative indices count from the end.
+    Index(i64),
+    Iterate,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Filter {
+    Path(Vec<Step>),
+    Select(Vec<Step>, Value),
+}
+
+/// Evaluates `query` against `value`, returning every output in order.
+/// Like jq, missing keys and out-of-range indices yield `null`.
+pub fn query(value: &Value, query: &str) -> Result<Vec<Value>> {
+    let filters = Parser { query, pos: 0 }.pipeline()?;
+    let mut values = vec![value.clone()];
+    for filter in &filters {
+        let mut next = Vec::new();
+        for value in values {
+            match filter {
+                Filter::Path(steps) => next.extend(walk(value, steps)?),
+                Filter::Select(steps, expected) => {
+                    let matched = walk(value.clone(), steps)?.contains(expected);
+                    if matched {
+                        next.push(value);
+                    }
+                }
+            }
+        }
+        values = next;
+    }
+    Ok(values)
+}
+
+fn walk(value: Value, steps: &[Step]) -> Result<Vec<Value>> {
+    let Some((step, rest)) = steps.split_first() else {
+        return Ok(vec![value]);
+    };
+    let children = match (step, value) {
+        (Step::Key(_) | Step::Index(_), Value::Null) => vec![Value::Null],
+        (Step::Key(key), Value::Object(mut map)) => {
+            vec![map.remove(key).unwrap_or(Value::Null)]
+        }
+        (Step::Index(index), Value::Array(mut items)) => {
+            let len = items.len() as i64;
+            let index = if *index < 0 { len + index } else { *index };
+            let item = (0..len)
+                .contains(&index)
+                .then(|| items.swap_remove(index as usize));
+            vec![item.unwrap_or(Value::Null)]
+        }
+        (Step::Iterate, Value::Array(items)) => items,
+        (Step::Iterate, Value::Object(map)) => map.into_iter().map(|(_, v)| v).collect(),
+        (Step::Iterate, value) => return Err(Error::Iterate(kind(&value))),
+        (Step::Key(key), value) => {
+            return Err(Error::Index {
+                kind: kind(&value),
+                with: format!("\"{key}\""),
+            })
+        }
+        (Step::Index(index), value) => {
+            return Err(Error::Index {
+                kind: kind(&value),
+                with: index.to_string(),
+            })
+        }
+    };
+    let mut values = Vec::new();
+    for child in children {
+        values.extend(walk(child, rest)?);
+    }
+    Ok(values)
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(_) => "number",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+struct Parser<'a> {
+    query: &'a str,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn rest(&self) -> &str {
+        &self.query[self.pos..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.pos += rest.len() - rest.trim_start().len();
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        let found = self.rest().starts_with(token);
+        if found {
+            self.pos += token.len();
+        }
+        found
+    }
+
+    fn expect(&mut self, token: &str, expected: &'static str) -> Result<()> {
+        if self.eat(token) {
+            Ok(())
+        } else {
+            Err(self.error(expected))
+        }
+    }
+
+    fn error(&self, expected: &'static str) -> Error {
+        Error::Syntax {
+            query: self.query.to_string(),
+            column: self.query[..self.pos].chars().count() + 1,
+            expected,
+        }
+    }
+
+    fn pipeline(&mut self) -> Result<Vec<Filter>> {
+        let mut filters = Vec::new();
+        loop {
+            self.skip_whitespace();
+            let filter = if self.eat("select(") {
+                self.skip_whitespace();
+                let path = self.path()?;
+                self.skip_whitespace();
+                self.expect("==", "'=='")?;
+                self.skip_whitespace();
+                let literal = self.literal()?;
+                self.skip_whitespace();
+                self.expect(")", "')'")?;
+                Filter::Select(path, literal)
+            } else {
+                Filter::Path(self.path()?)
+            };
+            filters.push(filter);
+            self.skip_whitespace();
+            if self.rest().is_empty() {
+                return Ok(filters);
+            }
+            self.expect("|", "'|' or the end of the query")?;
+        }
+    }
+
+    fn path(&mut self) -> Result<Vec<Step>> {
+        self.expect(".", "a path starting with '.'")?;
+        let mut steps = Vec::new();
+        // The key right after the leading dot needs no second dot.
+        let mut dotted = true;
+        loop {
+            if dotted && self.rest().starts_with('"') {
+                steps.push(Step::Key(self.string()?));
+            } else if dotted && self.rest().starts_with(is_ident) {
+                steps.push(Step::Key(self.ident()));
+            } else if dotted && !steps.is_empty() {
+                return Err(self.error("a key"));
+            } else if self.eat("[") {
+                steps.push(self.bracket()?);
+            } else {
+                return Ok(steps);
+            }
+            dotted = self.eat(".");
+        }
+    }
+
+    fn bracket(&mut self) -> Result<Step> {
+        if self.eat("]") {
+            return Ok(Step::Iterate);
+        }
+        let step = if self.rest().starts_with('"') {
+            Step::Key(self.string()?)
+        } else {
+            let len = self
+                .rest()
+                .char_indices()
+                .find(|&(i, c)| !(c.is_ascii_digit() || (i == 0 && c == '-')))
+                .map_or(self.rest().len(), |(i, _)| i);
+            let index = self.rest()[..len]
+                .parse()
+                .map_err(|_| self.error("an index, a quoted key or ']'"))?;
+            self.pos += len;
+            Step::Index(index)
+        };
+        self.expect("]", "']'")?;
+        Ok(step)
+    }
+
+    fn ident(&mut self) -> String {
+        let len = self
+            .rest()
+            .find(|c: char| !is_ident(c))
+            .unwrap_or(self.rest().len());
+        let ident = self.rest()[..len].to_string();
+        self.pos += len;
+        ident
+    }
+
+    fn string(&mut self) -> Result<String> {
+        match self.literal()? {
+            Value::String(s) => Ok(s),
+            _ => Err(self.error("a string")),
+        }
+    }
+
+    /// A JSON string, number, boolean or null.
+    fn literal(&mut self) -> Result<Value> {
+        let rest = self.rest();
+        let mut values = serde_json::Deserializer::from_str(rest).into_iter::<Value>();
+        let value = if rest.starts_with('"') {
+            let value = values.next().and_then(|value| value.ok());
+            self.pos += values.byte_offset();
+            value
+        } else {
+            let len = rest
+                .find(|c: char| c.is_whitespace() || c == ')')
+                .unwrap_or(rest.len());
+            let value = serde_json::from_str(&rest[..len]).ok();
+            self.pos += len;
+            value
+        };
+        value.ok_or_else(|| self.error("a JSON string, number, boolean or null"))
+    }
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_paths() {
+        let doc = json!({
+            "rootPackage": "app",
+            "targets": [
+                {"rootPackage": "app", "buildSettings": {"importPaths": ["/a/source/", "/b/"]}},
+                {"rootPackage": "lib", "buildSettings": {"importPaths": ["/b/"]}}
+            ]
+        });
+        assert_eq!(query(&doc, ".").unwrap(), vec![doc.clone()]);
+        assert_eq!(query(&doc, ".rootPackage").unwrap(), [json!("app")]);
+        assert_eq!(query(&doc, ".\"rootPackage\"").unwrap(), [json!("app")]);
+        assert_eq!(
+            query(&doc, ".targets[].rootPackage").unwrap(),
+            [json!("app"), json!("lib")]
+        );
+        assert_eq!(
+            query(&doc, ".targets[-1][\"rootPackage\"]").unwrap(),
+            [json!("lib")]
+        );
+        assert_eq!(
+            query(
+                &doc,
+                ".targets[] | select(.rootPackage == \"app\") | .buildSettings.importPaths[]"
+            )
+            .unwrap(),
+            [json!("/a/source/"), json!("/b/")]
+        );
+        assert_eq!(query(&doc, ".missing.key").unwrap(), [Value::Null]);
+        assert_eq!(query(&doc, ".targets[5]").unwrap(), [Value::Null]);
+    }
+
+    #[test]
+    fn test_errors() {
+        let doc = json!({"name": "app", "libs": ["z"]});
+        assert_eq!(
+            query(&doc, ".name[]").unwrap_err().to_string(),
+            "Cannot iterate over string"
+        );
+        assert_eq!(
+            query(&doc, ".libs.first").unwrap_err().to_string(),
+            "Cannot index array with \"first\""
+        );
+        assert_eq!(
+            query(&doc, "name").unwrap_err().to_string(),
+            "Invalid query 'name': expected a path starting with '.' at column 1"
+        );
+        assert_eq!(
+            query(&doc, ".libs[x]").unwrap_err().to_string(),
+            "Invalid query '.libs[x]': expected an index, a quoted key or ']' at column 7"
+        );
+        assert_eq!(
+            query(&doc, ".libs | select(.x = 1)")
+                .unwrap_err()
+                .to_string(),
+            "Invalid query '.libs | select(.x = 1)': expected '==' at column 19"
+        );
+    }
+}
@@ -0,0 +1,942 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! DUB registry access and a file-based mirror of it.
+//!
+//! A mirror directory holds `index/<name>.json`, the registry's package
+//! information restricted to the mirrored versions, and
+//! `packages/<name>/<version>.zip`. [`serve`] answers the subset of the
+//! registry API DUB's `--registry` uses from such a directory.
+
+use crate::selections::base_name;
+use crate::version::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use std::{fs, thread};
+
+/// The public registry.
+pub const DEFAULT_URL: &str = "https://code.dlang.org/";
+
+/// How long cached API responses are used without asking the registry.
+pub const CACHE_TTL: Duration = Duration::from_secs(10 * 60);
+
+#[derive(Debug)]
+pub enum Error {
+    Http {
+        url: String,
+        message: String,
+    },
+    Io(PathBuf, io::Error),
+    Json(String, serde_json::Error),
+    NotFound(String),
+    /// No version of a package satisfies a requirement.
+    Unsatisfiable {
+        package: String,
+        requirement: String,
+    },
+    /// A package name or version that cannot be used as a file name.
+    InvalidName(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http { url, message } => write!(f, "Request to {url} failed: {message}"),
+            Error::Io(path, e) => write!(f, "Failed to access '{}': {e}", path.display()),
+            Error::Json(source, e) => write!(f, "Invalid package information from {source}: {e}"),
+            Error::NotFound(package) => write!(f, "Package '{package}' not found in the registry"),
+            Error::Unsatisfiable {
+                package,
+                requirement,
+            } => write!(f, "No version of '{package}' satisfies '{requirement}'"),
+            Error::InvalidName(name) => write!(f, "Invalid package name or version '{name}'"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A package as the registry describes it. Fields not modelled here are
+/// kept so that mirrored information round-trips.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct PackageInfo {
+    pub name: String,
+    #[serde(default)]
+    pub versions: Vec<VersionInfo>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+/// One release or branch of a package, with its recipe fields.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+pub struct VersionInfo {
+    pub version: String,
+    #[serde(default, skip_serializing_if = "Map::is_empty")]
+    pub dependencies: Map<String, Value>,
+    #[serde(flatten)]
+    pub extra: Map<String, Value>,
+}
+
+impl VersionInfo {
+    /// Dependencies with their version requirement; those giving none,
+    /// such as path dependencies, require `*`.
+    pub fn requirements(&self) -> Vec<(&str, &str)> {
+        self.dependencies
+            .iter()
+            .map(|(name, spec)| {
+                let requirement = match spec {
+                    Value::String(version) => version.as_str(),
+                    spec => spec.get("version").and_then(Value::as_str).unwrap_or("*"),
+                };
+                (name.as_str(), requirement)
+            })
+            .collect()
+    }
+
+    fn field(&self, key: &str) -> Option<&str> {
+        self.extra.get(key).and_then(Value::as_str)
+    }
+
+    pub fn license(&self) -> Option<&str> {
+        self.field("license")
+    }
+
+    /// Names of the recipe's configurations, in declaration order.
+    pub fn configurations(&self) -> Vec<&str> {
+        self.extra
+            .get("configurations")
+            .and_then(Value::as_array)
+            .into_iter()
+            .flatten()
+            .filter_map(|config| config.get("name").and_then(Value::as_str))
+            .collect()
+    }
+}
+
+impl PackageInfo {
+    pub fn version(&self, version: &str) -> Option<&VersionInfo> {
+        self.versions.iter().find(|v| v.version == version)
+    }
+
+    /// The newest release, ignoring branches and pre-releases when a
+    /// stable release exists.
+    pub fn latest(&self) -> Option<&VersionInfo> {
+        let releases = || {
+            self.versions
+                .iter()
+                .filter_map(|v| Some((v.version.parse::<Version>().ok()?, v)))
+        };
+        releases()
+            .filter(|(version, _)| !version.is_prerelease())
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .or_else(|| releases().max_by(|a, b| a.0.cmp(&b.0)))
+            .map(|(_, v)| v)
+    }
+
+    /// The newest release satisfying `requirement`; a branch requirement
+    /// such as `~master` selects that branch.
+    pub fn best_match(&self, requirement: &str) -> Option<&VersionInfo> {
+        if requirement.starts_with('~') && !requirement.starts_with("~>") {
+            return self.version(requirement);
+        }
+        let requirement: VersionReq = requirement.parse().ok()?;
+        self.versions
+            .iter()
+            .filter_map(|v| Some((v.version.parse::<Version>().ok()?, v)))
+            .filter(|(version, _)| requirement.matches(version))
+            .max_by(|a, b| a.0.cmp(&b.0))
+            .map(|(_, v)| v)
+    }
+
+    /// Web address of the package's repository, from the registry's
+    /// `{"kind", "owner", "project"}` record or the recipe's homepage.
+    pub fn repository_url(&self) -> Option<String> {
+        let repository = self.extra.get("repository");
+        let part = |key: &str| repository?.get(key)?.as_str();
+        let host = match part("kind") {
+            Some("github") => Some("https://github.com"),
+            Some("gitlab") => Some("https://gitlab.com"),
+            Some("bitbucket") => Some("https://bitbucket.org"),
+            _ => None,
+        };
+        match (host, part("owner"), part("project")) {
+            (Some(host), Some(owner), Some(project)) => Some(format!("{host}/{owner}/{project}")),
+            _ => repository
+                .and_then(Value::as_str)
+                .or_else(|| self.latest()?.field("homepage"))
+                .map(str::to_string),
+        }
+    }
+
+    /// A short description, from the package or its latest release.
+    pub fn description(&self) -> &str {
+        self.extra
+            .get("description")
+            .or_else(|| self.latest()?.extra.get("description"))
+            .and_then(Value::as_str)
+            .unwrap_or("")
+    }
+}
+
+/// Percent-encodes `text` for use in a URL query or path segment.
+pub fn encode(text: &str) -> String {
+    let mut out = String::new();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                out.push(byte as char)
+            }
+            _ => out.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    out
+}
+
+/// Reverses [`encode`]; `+` in queries stands for a space.
+fn decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => match text
+                .get(i + 1..i + 3)
+                .map(|hex| u8::from_str_radix(hex, 16))
+            {
+                Some(Ok(byte)) => {
+                    out.push(byte);
+                    i += 2;
+                }
+                _ => out.push(b'%'),
+            },
+            b'+' => out.push(b' '),
+            byte => out.push(byte),
+        }
+        i += 1;
+    }
+    String::from_utf8_lossy(&out).into_owned()
+}
+
+/// A package found by [`Client::search`].
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct SearchResult {
+    pub name: String,
+    pub version: String,
+    pub description: String,
+}
+
+/// Client for a DUB registry's HTTP API.
+#[derive(Clone, Debug)]
+pub struct Client {
+    base: String,
+    agent: ureq::Agent,
+    cache: Option<PathBuf>,
+}
+
+impl Client {
+    /// `base` is the registry root, e.g. `https://code.dlang.org/`.
+    pub fn new(base: &str) -> Self {
+        let base = format!("{}/", base.trim_end_matches('/'));
+        let agent = ureq::AgentBuilder::new()
+            .timeout(Duration::from_secs(60))
+            .build();
+        Self {
+            base,
+            agent,
+            cache: None,
+        }
+    }
+
+    /// Keeps API responses in `dir` for [`CACHE_TTL`], and falls back to
+    /// older ones when the registry cannot be reached.
+    pub fn with_cache(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.cache = Some(dir.into());
+        self
+    }
+
+    pub fn base(&self) -> &str {
+        &self.base
+    }
+
+    /// GETs `path` below the registry root; `None` when it is not found.
+    fn get(&self, path: &str) -> Result<Option<ureq::Response>> {
+        let url = format!("{}{path}", self.base);
+        match self.agent.get(&url).call() {
+            Ok(response) => Ok(Some(response)),
+            Err(ureq::Error::Status(404, _)) => Ok(None),
+            Err(e) => Err(Error::Http {
+                url,
+                message: e.to_string(),
+            }),
+        }
+    }
+
+    /// Fetches `path` below the registry root as text, through the cache.
+    pub fn get_text(&self, path: &str) -> Result<Option<String>> {
+        let url = format!("{}{path}", self.base);
+        let cached = self.cache.as_ref().map(|dir| {
+            let key: String = Sha256::digest(url.as_bytes())
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+            dir.join(format!("{key}.json"))
+        });
+        let age = cached.as_ref().and_then(|file| {
+            let modified = fs::metadata(file).and_then(|m| m.modified()).ok()?;
+            Some(
+                SystemTime::now()
+                    .duration_since(modified)
+                    .unwrap_or_default(),
+            )
+        });
+        if let (Some(file), Some(age)) = (&cached, age) {
+            if age < CACHE_TTL {
+                if let Ok(text) = fs::read_to_string(file) {
+                    return Ok(Some(text));
+                }
+            }
+        }
+
+        let fetched = self.get(path).and_then(|response| match response {
+            Some(response) => response.into_string().map(Some).map_err(|e| Error::Http {
+                url,
+                message: e.to_string(),
+            }),
+            None => Ok(None),
+        });
+        match (fetched, &cached) {
+            (Ok(Some(text)), Some(file)) => {
+                // The cache is an optimization; failing to write it is not an error.
+                let _ = fs::create_dir_all(file.parent().expect("cache files have a parent"))
+                    .and_then(|()| fs::write(file, &text));
+                Ok(Some(text))
+            }
+            (Err(e @ Error::Http { .. }), Some(file)) => {
+                fs::read_to_string(file).map(Some).or(Err(e))
+            }
+            (fetched, _) => fetched,
+        }
+    }
+
+    /// Packages whose name or description matches `query`.
+    pub fn search(&self, query: &str) -> Result<Vec<SearchResult>> {
+        let path = format!("api/packages/search?q={}", encode(query));
+        let text = self.get_text(&path)?.unwrap_or_else(|| "[]".to_string());
+        serde_json::from_str(&text).map_err(|e| Error::Json(format!("{}{path}", self.base), e))
+    }
+
+    /// Everything the registry knows about `package`.
+    pub fn info(&self, package: &str) -> Result<PackageInfo> {
+        let path = format!("api/packages/{}/info", encode(base_name(package)));
+        let text = self
+            .get_text(&path)?
+            .ok_or_else(|| Error::NotFound(package.to_string()))?;
+        serde_json::from_str(&text).map_err(|e| Error::Json(format!("{}{path}", self.base), e))
+    }
+
+    /// Information on `packages`, and with `include_dependencies` on
+    /// everything they may depend on. Unknown packages are left out.
+    pub fn infos(
+        &self,
+        packages: &[&str],
+        include_dependencies: bool,
+    ) -> Result<BTreeMap<String, PackageInfo>> {
+        let names = serde_json::to_string(packages).expect("names serialize");
+        let path = format!(
+            "api/packages/infos?packages={}&include_dependencies={include_dependencies}&minimize=true",
+            encode(&names)
+        );
+        let text = self
+            .get_text(&path)?
+            .ok_or_else(|| Error::NotFound(packages.join(", ")))?;
+        serde_json::from_str(&text).map_err(|e| Error::Json(format!("{}{path}", self.base), e))
+    }
+
+    /// The zip archive of a package version.
+    pub fn download(&self, package: &str, version: &str) -> Result<Vec<u8>> {
+        let path = format!("packages/{}/{}.zip", encode(package), encode(version));
+        let url = format!("{}{path}", self.base);
+        let response = self
+            .get(&path)?
+            .ok_or_else(|| Error::NotFound(format!("{package}@{version}")))?;
+        let mut archive = Vec::new();
+        response
+            .into_reader()
+            .read_to_end(&mut archive)
+            .map_err(|e| Error::Http {
+                url,
+                message: e.to_string(),
+            })?;
+        Ok(archive)
+    }
+}
+
+/// A registry mirror stored in a directory.
+#[derive(Clone, Debug)]
+pub struct Mirror {
+    dir: PathBuf,
+}
+
+/// Whether `name` can be used as a single path component inside the mirror:
+/// non-empty, without separators, not `.` and without `..`.
+fn is_file_name(name: &str) -> bool {
+    !name.is_empty() && !name.contains(['/', '\\']) && !name.contains("..") && name != "."
+}
+
+impl Mirror {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    fn index_path(&self, package: &str) -> PathBuf {
+        self.dir.join("index").join(format!("{package}.json"))
+    }
+
+    pub fn archive_path(&self, package: &str, version: &str) -> PathBuf {
+        self.dir
+            .join("packages")
+            .join(package)
+            .join(format!("{version}.zip"))
+    }
+
+    pub fn info(&self, package: &str) -> Result<Option<PackageInfo>> {
+        if !is_file_name(package) {
+            return Ok(None);
+        }
+        let path = self.index_path(package);
+        match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map(Some)
+                .map_err(|e| Error::Json(path.display().to_string(), e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::Io(path, e)),
+        }
+    }
+
+    /// Every mirrored package, by name.
+    pub fn packages(&self) -> Result<Vec<PackageInfo>> {
+        let index = self.dir.join("index");
+        let entries = match fs::read_dir(&index) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(Error::Io(index, e)),
+        };
+        let mut names: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                name.strip_suffix(".json").map(str::to_string)
+            })
+            .collect();
+        names.sort();
+        let mut packages = Vec::new();
+        for name in names {
+            packages.extend(self.info(&name)?);
+        }
+        Ok(packages)
+    }
+
+    pub fn contains(&self, package: &str, version: &str) -> bool {
+        is_file_name(package)
+            && is_file_name(version)
+            && self.archive_path(package, version).is_file()
+    }
+
+    /// The archive of a mirrored version, or `None` when it is missing or
+    /// the name or version would escape the mirror directory.
+    pub fn archive(&self, package: &str, version: &str) -> Result<Option<Vec<u8>>> {
+        if !is_file_name(package) || !is_file_name(version) {
+            return Ok(None);
+        }
+        let path = self.archive_path(package, version);
+        match fs::read(&path) {
+            Ok(body) => Ok(Some(body)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::Io(path, e)),
+        }
+    }
+
+    /// Stores the archive of `version` and adds the version to the index.
+    pub fn add(&self, info: &PackageInfo, version: &VersionInfo, archive: &[u8]) -> Result<()> {
+        for name in [&info.name, &version.version] {
+            if !is_file_name(name) {
+                return Err(Error::InvalidName(name.clone()));
+            }
+        }
+        let path = self.archive_path(&info.name, &version.version);
+        let write = |path: &Path, contents: &[u8]| {
+            fs::create_dir_all(path.parent().expect("mirror paths have a parent"))
+                .and_then(|()| fs::write(path, contents))
+                .map_err(|e| Error::Io(path.to_path_buf(), e))
+        };
+        write(&path, archive)?;
+
+        let mut indexed = self.info(&info.name)?.unwrap_or_else(|| PackageInfo {
+            versions: Vec::new(),
+            ..info.clone()
+        });
+        indexed.extra.clone_from(&info.extra);
+        indexed.versions.retain(|v| v.version != version.version);
+        indexed.versions.push(version.clone());
+        let text = serde_json::to_string_pretty(&indexed).expect("package info serializes");
+        write(&self.index_path(&info.name), text.as_bytes())
+    }
+}
+
+/// A package version copied by [`sync`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Synced {
+    pub package: String,
+    pub version: String,
+    /// False when the mirror already had it.
+    pub downloaded: bool,
+}
+
+/// Copies the requested `(package, version)` pairs into `mirror`; a
+/// missing version means the latest release. With `dependencies`, the
+/// newest version satisfying each dependency's requirement is copied too.
+pub fn sync(
+    client: &Client,
+    mirror: &Mirror,
+    requests: &[(String, Option<String>)],
+    dependencies: bool,
+) -> Result<Vec<Synced>> {
+    let names: Vec<&str> = requests.iter().map(|(name, _)| base_name(name)).collect();
+    let mut infos = client.infos(&names, dependencies)?;
+
+    // Explicit versions are taken as given, requirements resolved once
+    // the package's information is at hand.
+    let mut queue: Vec<(String, Option<String>, Option<String>)> = requests
+        .iter()
+        .map(|(name, version)| (base_name(name).to_string(), version.clone(), None))
+        .rev()
+        .collect();
+    let mut done = BTreeSet::new();
+    let mut synced = Vec::new();
+    while let Some((name, version, requirement)) = queue.pop() {
+        if !infos.contains_key(&name) {
+            infos.extend(client.infos(&[&name], true)?);
+        }
+        let info = infos
+            .get(&name)
+            .ok_or_else(|| Error::NotFound(name.clone()))?;
+        let release = match (&version, &requirement) {
+            (Some(version), _) => info
+                .version(version)
+                .ok_or_else(|| Error::NotFound(format!("{name}@{version}")))?,
+            (None, Some(requirement)) => {
+                info.best_match(requirement)
+                    .ok_or_else(|| Error::Unsatisfiable {
+                        package: name.clone(),
+                        requirement: requirement.clone(),
+                    })?
+            }
+            (None, None) => info.latest().ok_or_else(|| Error::NotFound(name.clone()))?,
+        };
+        if !done.insert((name.clone(), release.version.clone())) {
+            continue;
+        }
+        if dependencies {
+            for (dependency, requirement) in release.requirements() {
+                let dependency = base_name(dependency);
+                // Sub-packages depend on their parent at the same version.
+                if dependency != name {
+                    queue.push((dependency.to_string(), None, Some(requirement.to_string())));
+                }
+            }
+        }
+        let downloaded = !mirror.contains(&name, &release.version);
+        if downloaded {
+            let archive = client.download(&name, &release.version)?;
+            mirror.add(info, release, &archive)?;
+        }
+        synced.push(Synced {
+            package: name.clone(),
+            version: release.version.clone(),
+            downloaded,
+        });
+    }
+    synced.sort_by(|a, b| (&a.package, &a.version).cmp(&(&b.package, &b.version)));
+    Ok(synced)
+}
+
+/// An HTTP response from the mirror server.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    fn json(value: &impl Serialize) -> Self {
+        Self {
+            status: 200,
+            content_type: "application/json",
+            body: serde_json::to_vec(value).expect("responses serialize"),
+        }
+    }
+
+    fn error(status: u16, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "application/json",
+            body: serde_json::to_vec(&serde_json::json!({ "statusMessage": message }))
+                .expect("errors serialize"),
+        }
+    }
+}
+
+/// Answers a GET request for `target` (path and query) from `mirror`:
+///
+/// - `/api/packages/infos?packages=[...]&include_dependencies=true`
+/// - `/api/packages/<name>/info`
+/// - `/api/packages/search?q=<text>`
+/// - `/packages/<name>/<version>.zip`
+pub fn respond(mirror: &Mirror, target: &str) -> Response {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    let params: BTreeMap<String, String> = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .map(|(key, value)| (decode(key), decode(value)))
+        .collect();
+    let segments: Vec<String> = path.trim_matches('/').split('/').map(decode).collect();
+    // Decoding may reveal separators (`..%2F`) that would leave the mirror.
+    if segments.iter().any(|segment| !is_file_name(segment)) {
+        return Response::error(400, "Invalid path");
+    }
+    let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+    let result = match segments.as_slice() {
+        ["api", "packages", "infos"] => infos(mirror, &params),
+        ["api", "packages", "search"] => search(mirror, params.get("q").map_or("", |q| q)),
+        ["api", "packages", name, "info"] => match mirror.info(name) {
+            Ok(Some(info)) => Ok(Response::json(&info)),
+            Ok(None) => Ok(Response::error(404, "Package not found")),
+            Err(e) => Err(e),
+        },
+        ["packages", name, archive] => match archive.strip_suffix(".zip") {
+            Some(version) => mirror.archive(name, version).map(|archive| match archive {
+                Some(body) => Response {
+                    status: 200,
+                    content_type: "application/zip",
+                    body,
+                },
+                None => Response::error(404, "Package version not found"),
+            }),
+            None => Ok(Response::error(404, "Not found")),
+        },
+        _ => Ok(Response::error(404, "Not found")),
+    };
+    result.unwrap_or_else(|e| Response::error(500, &e.to_string()))
+}
+
+fn infos(mirror: &Mirror, params: &BTreeMap<String, String>) -> Result<Response> {
+    let Ok(requested) =
+        serde_json::from_str::<Vec<String>>(params.get("packages").map_or("[]", |p| p))
+    else {
+        return Ok(Response::error(400, "Invalid packages parameter"));
+    };
+    let with_dependencies = params
+        .get("include_dependencies")
+        .is_some_and(|v| v == "true");
+    let mut found = BTreeMap::new();
+    let mut queue: Vec<String> = requested
+        .iter()
+        .map(|name| base_name(name).to_string())
+        .collect();
+    while let Some(name) = queue.pop() {
+        if found.contains_key(&name) {
+            continue;
+        }
+        let Some(info) = mirror.info(&name)? else {
+            continue;
+        };
+        if with_dependencies {
+            for version in &info.versions {
+                for (dependency, _) in version.requirements() {
+                    queue.push(base_name(dependency).to_string());
+                }
+            }
+        }
+        found.insert(name, info);
+    }
+    Ok(Response::json(&found))
+}
+
+fn search(mirror: &Mirror, query: &str) -> Result<Response> {
+    let query = query.to_lowercase();
+    let results: Vec<Value> = mirror
+        .packages()?
+        .iter()
+        .filter(|info| {
+            info.name.to_lowercase().contains(&query)
+                || info.description().to_lowercase().contains(&query)
+        })
+        .map(|info| {
+            serde_json::json!({
+                "name": info.name,
+                "description": info.description(),
+                "version": info.latest().map_or("", |v| v.version.as_str()),
+            })
+        })
+        .collect();
+    Ok(Response::json(&results))
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        _ => "Internal Server Error",
+    }
+}
+
+fn handle(mirror: &Mirror, stream: TcpStream) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // Headers are not needed; read them so the client sees a full exchange.
+    let mut header = String::new();
+    while reader.read_line(&mut header)? > 0 && header.trim_end() != "" {
+        header.clear();
+    }
+    let mut parts = request_line.split_whitespace();
+    let response = match (parts.next(), parts.next()) {
+        (Some("GET" | "HEAD"), Some(target)) => respond(mirror, target),
+        _ => Response::error(405, "Only GET is supported"),
+    };
+    let mut stream = stream;
+    write!(
+        stream,
+        "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        reason(response.status),
+        response.content_type,
+        response.body.len()
+    )?;
+    if !request_line.starts_with("HEAD ") {
+        stream.write_all(&response.body)?;
+    }
+    stream.flush()
+}
+
+/// Serves `mirror` on `listener` until the process exits, one thread per
+/// connection.
+pub fn serve(mirror: &Mirror, listener: TcpListener) -> io::Result<()> {
+    for stream in listener.incoming() {
+        let stream = stream?;
+        let mirror = mirror.clone();
+        thread::spawn(move || {
+            let _ = handle(&mirror, stream);
+        });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    fn package(name: &str, versions: &[(&str, Value)]) -> PackageInfo {
+        serde_json::from_value(json!({
+            "name": name,
+            "description": format!("The {name} package"),
+            "versions": versions
+                .iter()
+                .map(|(version, dependencies)| json!({"version": version, "dependencies": dependencies}))
+                .collect::<Vec<_>>(),
+        }))
+        .unwrap()
+    }
+
+    /// A mirror with `app` depending on `mir-core` and both its releases.
+    fn fixture() -> (TempDir, Mirror) {
+        let temp = TempDir::new().unwrap();
+        let mirror = Mirror::new(temp.path());
+        let app = package("app", &[("1.0.0", json!({"mir-core": ">=1.6.0"}))]);
+        let core = package("mir-core", &[("1.6.0", json!({})), ("1.7.1", json!({}))]);
+        mirror.add(&app, &app.versions[0], b"app zip").unwrap();
+        for version in &core.versions {
+            mirror
+                .add(&core, version, version.version.as_bytes())
+                .unwrap();
+        }
+        (temp, mirror)
+    }
+
+    fn body(response: &Response) -> Value {
+        serde_json::from_slice(&response.body).unwrap()
+    }
+
+    #[test]
+    fn test_respond() {
+        let (_temp, mirror) = fixture();
+
+        let infos = respond(
+            &mirror,
+            "/api/packages/infos?packages=%5B%22app%22%5D&include_dependencies=true&minimize=true",
+        );
+        assert_eq!(infos.status, 200);
+        let infos = body(&infos);
+        assert_eq!(
+            infos.as_object().unwrap().keys().collect::<Vec<_>>(),
+            ["app", "mir-core"]
+        );
+        assert_eq!(infos["mir-core"]["versions"][1]["version"], "1.7.1");
+        let infos = body(&respond(&mirror, "/api/packages/infos?packages=[\"app\"]"));
+        assert_eq!(infos.as_object().unwrap().len(), 1);
+
+        let info = body(&respond(
+            &mirror,
+            "/api/packages/mir-core/info?minimize=true",
+        ));
+        assert_eq!(info["description"], "The mir-core package");
+
+        let archive = respond(&mirror, "/packages/mir-core/1.6.0.zip");
+        assert_eq!(archive.content_type, "application/zip");
+        assert_eq!(archive.body, b"1.6.0");
+
+        let found = body(&respond(&mirror, "/api/packages/search?q=MIR"));
+        assert_eq!(
+            found,
+            json!([{"name": "mir-core", "description": "The mir-core package", "version": "1.7.1"}])
+        );
+
+        for missing in [
+            "/packages/mir-core/9.9.9.zip",
+            "/api/packages/nope/info",
+            "/index.html",
+        ] {
+            assert_eq!(respond(&mirror, missing).status, 404, "{missing}");
+        }
+        assert_eq!(
+            respond(&mirror, "/api/packages/infos?packages=app").status,
+            400
+        );
+
+        // Decoded segments must not escape the mirror directory.
+        let outside = mirror.dir.parent().unwrap().join("secret");
+        fs::create_dir_all(&outside).unwrap();
+        fs::write(outside.join("key.zip"), "secret").unwrap();
+        fs::write(outside.join("key.json"), r#"{"name": "key"}"#).unwrap();
+        for traversal in [
+            "/packages/..%2F..%2Fsecret/key.zip",
+            "/packages/..%5C..%5Csecret/key.zip",
+            "/packages/mir-core/..%2F..%2F..%2Fsecret%2Fkey.zip",
+            "/packages/%2E%2E/key.zip",
+            "/api/packages/..%2F..%2Fsecret%2Fkey/info",
+            "/packages//1.6.0.zip",
+        ] {
+            let response = respond(&mirror, traversal);
+            assert_eq!(response.status, 400, "{traversal}");
+            assert_ne!(response.body, b"secret");
+        }
+        let infos = respond(
+            &mirror,
+            "/api/packages/infos?packages=%5B%22..%2F..%2Fsecret%2Fkey%22%5D",
+        );
+        assert_eq!(body(&infos), json!({}));
+        let evil = package("../evil", &[("1.0.0", json!({}))]);
+        assert_eq!(
+            mirror
+                .add(&evil, &evil.versions[0], b"")
+                .unwrap_err()
+                .to_string(),
+            "Invalid package name or version '../evil'"
+        );
+    }
+
+    #[test]
+    fn test_sync() {
+        let (_upstream_dir, upstream) = fixture();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve(&upstream, listener));
+        let client = Client::new(&format!("http://{addr}"));
+
+        let temp = TempDir::new().unwrap();
+        let mirror = Mirror::new(temp.path());
+        let synced = sync(&client, &mirror, &[("app".to_string(), None)], true).unwrap();
+        assert_eq!(
+            synced,
+            [
+                Synced {
+                    package: "app".to_string(),
+                    version: "1.0.0".to_string(),
+                    downloaded: true,
+                },
+                Synced {
+                    package: "mir-core".to_string(),
+                    version: "1.7.1".to_string(),
+                    downloaded: true,
+                },
+            ]
+        );
+        assert_eq!(
+            fs::read(mirror.archive_path("mir-core", "1.7.1")).unwrap(),
+            b"1.7.1"
+        );
+        // Only mirrored versions are indexed.
+        let core = mirror.info("mir-core").unwrap().unwrap();
+        assert_eq!(core.versions.len(), 1);
+        assert_eq!(core.description(), "The mir-core package");
+
+        let request = [("mir-core".to_string(), Some("1.6.0".to_string()))];
+        let synced = sync(&client, &mirror, &request, false).unwrap();
+        assert!(synced[0].downloaded);
+        let synced = sync(&client, &mirror, &request, false).unwrap();
+        assert!(!synced[0].downloaded);
+        assert_eq!(mirror.info("mir-core").unwrap().unwrap().versions.len(), 2);
+
+        let err = sync(&client, &mirror, &[("nope".to_string(), None)], true).unwrap_err();
+        assert_eq!(err.to_string(), "Package 'nope' not found in the registry");
+    }
+
+    #[test]
+    fn test_cached_search() {
+        let (upstream_dir, upstream) = fixture();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        thread::spawn(move || serve(&upstream, listener));
+        let cache = TempDir::new().unwrap();
+        let client = Client::new(&format!("http://{addr}/")).with_cache(cache.path());
+
+        let names = |found: Vec<SearchResult>| -> Vec<String> {
+            found.into_iter().map(|p| p.name).collect()
+        };
+        assert_eq!(names(client.search("mir").unwrap()), ["mir-core"]);
+        assert_eq!(fs::read_dir(cache.path()).unwrap().count(), 1);
+
+        // Within the TTL the registry is not asked again.
+        let mir_ion = package("mir-ion", &[("2.0.0", json!({}))]);
+        Mirror::new(upstream_dir.path())
+            .add(&mir_ion, &mir_ion.versions[0], b"zip")
+            .unwrap();
+        assert_eq!(names(client.search("mir").unwrap()), ["mir-core"]);
+        let uncached = Client::new(&format!("http://{addr}/"));
+        assert_eq!(
+            names(uncached.search("mir").unwrap()),
+            ["mir-core", "mir-ion"]
+        );
+
+        assert_eq!(client.info("mir-core:traits").unwrap().versions.len(), 2);
+        assert_eq!(
+            client.info("nope").unwrap_err().to_string(),
+            "Package 'nope' not found in the registry"
+        );
+    }
+}
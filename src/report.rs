@@ -0,0 +1,252 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Machine-readable test reports for CI.
+
+use crate::unittest::{Report, TestStatus};
+use std::fmt::{self, Write as _};
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ReportFormat {
+    Json,
+    Junit,
+    Tap,
+}
+
+/// A `--report` argument: `FORMAT` (written to stdout) or `FORMAT=FILE`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ReportSpec {
+    pub format: ReportFormat,
+    pub path: Option<PathBuf>,
+}
+
+impl FromStr for ReportSpec {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let (format, path) = match s.split_once('=') {
+            Some((format, path)) => (format, Some(PathBuf::from(path))),
+            None => (s, None),
+        };
+        let format = match format {
+            "json" => ReportFormat::Json,
+            "junit" => ReportFormat::Junit,
+            "tap" => ReportFormat::Tap,
+            _ => {
+                return Err(format!(
+                    "unknown report format '{format}' (expected json, junit or tap)"
+                ))
+            }
+        };
+        Ok(Self { format, path })
+    }
+}
+
+impl fmt::Display for ReportFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ReportFormat::Json => "json",
+            ReportFormat::Junit => "junit",
+            ReportFormat::Tap => "tap",
+        })
+    }
+}
+
+/// Renders `report` for the package `suite`.
+pub fn render(format: ReportFormat, report: &Report, suite: &str) -> String {
+    match format {
+        ReportFormat::Json => {
+            let mut json = serde_json::to_string_pretty(report).expect("reports serialize");
+            json.push('\n');
+            json
+        }
+        ReportFormat::Junit => junit(report, suite),
+        ReportFormat::Tap => tap(report),
+    }
+}
+
+/// JUnit XML as understood by Jenkins, GitLab and GitHub test reporters.
+/// Tests a runner only counted are reflected in the totals, not as cases.
+pub fn junit(report: &Report, suite: &str) -> String {
+    let tests = report.passed + report.failed + report.ignored;
+    let time = report.duration.map_or(0.0, |d| d.as_secs_f64());
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    let totals = format!(
+        "tests=\"{tests}\" failures=\"{}\" skipped=\"{}\" time=\"{time:.3}\"",
+        report.failed, report.ignored
+    );
+    let _ = writeln!(out, "<testsuites {totals}>");
+    let _ = writeln!(out, "  <testsuite name=\"{}\" {totals}>", escape(suite));
+    for case in &report.cases {
+        let _ = write!(
+            out,
+            "    <testcase name=\"{}\" classname=\"{}\"",
+            escape(&case.name),
+            escape(case.module.as_deref().unwrap_or(suite))
+        );
+        if let Some(duration) = case.duration {
+            let _ = write!(out, " time=\"{:.3}\"", duration.as_secs_f64());
+        }
+        match (case.status, &case.message) {
+            (TestStatus::Ok, _) => out.push_str("/>\n"),
+            (TestStatus::Ignored, _) => out.push_str(">\n      <skipped/>\n    </testcase>\n"),
+            (TestStatus::Failed, message) => {
+                let message = message.as_deref().unwrap_or("test failed");
+                let first = message.lines().next().unwrap_or_default();
+                let _ = write!(
+                    out,
+                    ">\n      <failure message=\"{}\">{}</failure>\n    </testcase>\n",
+                    escape(first),
+                    escape(message)
+                );
+            }
+        }
+    }
+    out.push_str("  </testsuite>\n</testsuites>\n");
+    out
+}
+
+/// [TAP version 13](https://testanything.org/tap-version-13-specification.html),
+/// with failures described in a YAML block.
+pub fn tap(report: &Report) -> String {
+    let mut out = format!("TAP version 13\n1..{}\n", report.cases.len());
+    for (i, case) in report.cases.iter().enumerate() {
+        let number = i + 1;
+        let time = case
+            .duration
+            .map(|d| format!(" # time={:.3}ms", d.as_secs_f64() * 1000.0))
+            .unwrap_or_default();
+        match case.status {
+            TestStatus::Ok => {
+                let _ = writeln!(out, "ok {number} - {}{time}", case.name);
+            }
+            TestStatus::Ignored => {
+                let _ = writeln!(out, "ok {number} - {} # SKIP", case.name);
+            }
+            TestStatus::Failed => {
+                let _ = writeln!(out, "not ok {number} - {}{time}", case.name);
+                out.push_str("  ---\n");
+                let message = case.message.as_deref().unwrap_or("test failed");
+                out.push_str("  message: |\n");
+                for line in message.lines() {
+                    let _ = writeln!(out, "    {line}");
+                }
+                if let Some(module) = &case.module {
+                    let _ = writeln!(out, "  module: {module}");
+                }
+                if let Some(duration) = case.duration {
+                    let _ = writeln!(out, "  duration_ms: {:.3}", duration.as_secs_f64() * 1000.0);
+                }
+                out.push_str("  ...\n");
+            }
+        }
+    }
+    out
+}
+
+pub(crate) fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::unittest::{Runner, TestCase};
+    use std::time::Duration;
+
+    fn report() -> Report {
+        Report {
+            runner: Runner::Silly,
+            cases: vec![
+                TestCase {
+                    name: "app.adds".to_string(),
+                    module: Some("app".to_string()),
+                    status: TestStatus::Ok,
+                    message: None,
+                    duration: Some(Duration::from_millis(2)),
+                },
+                TestCase {
+                    name: "app.divides".to_string(),
+                    module: Some("app".to_string()),
+                    status: TestStatus::Failed,
+                    message: Some("1 != 0 <x>".to_string()),
+                    duration: None,
+                },
+            ],
+            passed: 1,
+            failed: 1,
+            duration: Some(Duration::from_millis(5)),
+            ..Report::default()
+        }
+    }
+
+    #[test]
+    fn test_spec() {
+        assert_eq!(
+            "junit=out/tests.xml".parse::<ReportSpec>().unwrap(),
+            ReportSpec {
+                format: ReportFormat::Junit,
+                path: Some(PathBuf::from("out/tests.xml")),
+            }
+        );
+        assert_eq!("json".parse::<ReportSpec>().unwrap().path, None);
+        assert!("xml".parse::<ReportSpec>().is_err());
+    }
+
+    #[test]
+    fn test_junit() {
+        assert_eq!(
+            junit(&report(), "app"),
+            r#"<?xml version="1.0" encoding="UTF-8"?>
+<testsuites tests="2" failures="1" skipped="0" time="0.005">
+  <testsuite name="app" tests="2" failures="1" skipped="0" time="0.005">
+    <testcase name="app.adds" classname="app" time="0.002"/>
+    <testcase name="app.divides" classname="app">
+      <failure message="1 != 0 &lt;x&gt;">1 != 0 &lt;x&gt;</failure>
+    </testcase>
+  </testsuite>
+</testsuites>
+"#
+        );
+    }
+
+    #[test]
+    fn test_tap() {
+        assert_eq!(
+            render(ReportFormat::Tap, &report(), "app"),
+            "TAP version 13
+1..2
+ok 1 - app.adds # time=2.000ms
+not ok 2 - app.divides
+  ---
+  message: |
+    1 != 0 <x>
+  module: app
+  ...
+"
+        );
+    }
+
+    #[test]
+    fn test_json() {
+        let json: serde_json::Value =
+            serde_json::from_str(&render(ReportFormat::Json, &report(), "app")).unwrap();
+        assert_eq!(json["runner"], "silly");
+        assert_eq!(json["cases"][1]["status"], "failed");
+        assert_eq!(json["cases"][0]["duration"], 0.002);
+        assert_eq!(json["duration"], 0.005);
+    }
+}
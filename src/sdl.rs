@@ -0,0 +1,540 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Minimal SDLang reader and writer, covering the subset used by `dub.sdl`.
+
+use std::fmt::{self, Write as _};
+
+/// A single SDLang value.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Value {
+    String(String),
+    Int(i64),
+    Float(f64),
+    Bool(bool),
+    Null,
+}
+
+impl Value {
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Value::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::String(s) => {
+                f.write_char('"')?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c => f.write_char(c)?,
+                    }
+                }
+                f.write_char('"')
+            }
+            Value::Int(i) => write!(f, "{i}"),
+            Value::Float(x) => write!(f, "{x:?}"),
+            Value::Bool(b) => write!(f, "{b}"),
+            Value::Null => f.write_str("null"),
+        }
+    }
+}
+
+/// A tag with its values, attributes, children and the comments preceding it.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Tag {
+    pub name: String,
+    pub values: Vec<Value>,
+    pub attributes: Vec<(String, Value)>,
+    pub children: Vec<Tag>,
+    /// Comment lines directly above the tag, without the comment markers.
+    pub comments: Vec<String>,
+    /// Comment at the end of the tag's line, or of its `{` line for a block.
+    pub inline_comment: Option<String>,
+    /// Comment lines after the last child, before the closing brace.
+    pub trailing_comments: Vec<String>,
+}
+
+impl Tag {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&Value> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value)
+    }
+}
+
+/// A parsed SDLang document: the anonymous root tag's children.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Document {
+    pub tags: Vec<Tag>,
+    /// Comment lines at the end of the document.
+    pub trailing_comments: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Error {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub fn parse(input: &str) -> Result<Document, Error> {
+    let mut parser = Parser {
+        chars: input.chars().collect(),
+        pos: 0,
+        line: 1,
+        pending_comments: Vec::new(),
+    };
+    let (tags, trailing_comments) = parser.tags(false)?;
+    Ok(Document {
+        tags,
+        trailing_comments,
+    })
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    line: usize,
+    pending_comments: Vec<String>,
+}
+
+impl Parser {
+    fn error<T>(&self, message: impl Into<String>) -> Result<T, Error> {
+        Err(Error {
+            line: self.line,
+            message: message.into(),
+        })
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    fn peek_at(&self, offset: usize) -> Option<char> {
+        self.chars.get(self.pos + offset).copied()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += 1;
+        if c == '\n' {
+            self.line += 1;
+        }
+        Some(c)
+    }
+
+    fn line_comment(&mut self) -> String {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c == '\n' {
+                break;
+            }
+            text.push(c);
+            self.bump();
+        }
+        text.trim().to_string()
+    }
+
+    /// Skips blanks and comments inside a tag line; stops at newlines.
+    fn skip_inline(&mut self) -> Result<(), Error> {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r') => {
+                    self.bump();
+                }
+                Some('\\') if matches!(self.peek_at(1), Some('\n' | '\r')) => {
+                    self.bump();
+                    while let Some(' ' | '\t' | '\r' | '\n') = self.peek() {
+                        if self.bump() == Some('\n') {
+                            break;
+                        }
+                    }
+                }
+                Some('/') if self.peek_at(1) == Some('*') => self.block_comment()?,
+                Some('/') if self.peek_at(1) == Some('/') => {
+                    self.pos += 2;
+                    let text = self.line_comment();
+                    self.pending_comments.push(text);
+                }
+                Some('#') => {
+                    self.bump();
+                    let text = self.line_comment();
+                    self.pending_comments.push(text);
+                }
+                Some('-') if self.peek_at(1) == Some('-') => {
+                    self.pos += 2;
+                    let text = self.line_comment();
+                    self.pending_comments.push(text);
+                }
+                _ => return Ok(()),
+            }
+        }
+    }
+
+    /// Comments met since the tag began, all on its line, joined into one.
+    fn take_inline_comment(&mut self) -> Option<String> {
+        let comments = std::mem::take(&mut self.pending_comments);
+        (!comments.is_empty()).then(|| comments.join(" "))
+    }
+
+    fn block_comment(&mut self) -> Result<(), Error> {
+        self.pos += 2;
+        let mut text = String::new();
+        loop {
+            match self.bump() {
+                None => return self.error("unterminated block comment"),
+                Some('*') if self.peek() == Some('/') => {
+                    self.bump();
+                    break;
+                }
+                Some(c) => text.push(c),
+            }
+        }
+        self.pending_comments
+            .extend(text.lines().map(|l| l.trim().to_string()));
+        Ok(())
+    }
+
+    fn tags(&mut self, nested: bool) -> Result<(Vec<Tag>, Vec<String>), Error> {
+        let mut tags = Vec::new();
+        loop {
+            self.skip_inline()?;
+            match self.peek() {
+                None if nested => return self.error("missing closing '}'"),
+                None => break,
+                Some('\n' | ';') => {
+                    self.bump();
+                }
+                Some('}') if nested => {
+                    self.bump();
+                    break;
+                }
+                Some('}') => return self.error("unexpected '}'"),
+                Some(_) => {
+                    let comments = std::mem::take(&mut self.pending_comments);
+                    let mut tag = self.tag()?;
+                    tag.comments = comments;
+                    tags.push(tag);
+                }
+            }
+        }
+        Ok((tags, std::mem::take(&mut self.pending_comments)))
+    }
+
+    fn tag(&mut self) -> Result<Tag, Error> {
+        let mut tag = Tag::default();
+        if self.peek().is_some_and(is_ident_start) {
+            let ident = self.identifier();
+            if !matches!(ident.as_str(), "true" | "false" | "on" | "off" | "null") {
+                tag.name = ident;
+                if self.peek() == Some(':') {
+                    self.bump();
+                    tag.name.push(':');
+                    tag.name.push_str(&self.identifier());
+                }
+            } else {
+                self.pos -= ident.chars().count();
+                tag.name = "content".to_string();
+            }
+        } else {
+            tag.name = "content".to_string();
+        }
+
+        loop {
+            self.skip_inline()?;
+            match self.peek() {
+                None | Some('\n' | ';' | '}') => {
+                    tag.inline_comment = self.take_inline_comment();
+                    break;
+                }
+                Some('{') => {
+                    self.bump();
+                    self.skip_inline()?;
+                    tag.inline_comment = self.take_inline_comment();
+                    let (children, trailing) = self.tags(true)?;
+                    tag.children = children;
+                    tag.trailing_comments = trailing;
+                    break;
+                }
+                Some(c) if is_ident_start(c) => {
+                    let start = self.pos;
+                    let ident = self.identifier();
+                    if self.peek() == Some('=') {
+                        self.bump();
+                        let value = self.value()?;
+                        tag.attributes.push((ident, value));
+                    } else {
+                        self.pos = start;
+                        if !tag.attributes.is_empty() {
+                            return self.error("values must come before attributes");
+                        }
+                        tag.values.push(self.value()?);
+                    }
+                }
+                Some(_) => {
+                    if !tag.attributes.is_empty() {
+                        return self.error("values must come before attributes");
+                    }
+                    tag.values.push(self.value()?);
+                }
+            }
+        }
+        Ok(tag)
+    }
+
+    fn identifier(&mut self) -> String {
+        let mut ident = String::new();
+        while let Some(c) = self.peek() {
+            if ident.is_empty() && !is_ident_start(c) || !is_ident_char(c) {
+                break;
+            }
+            ident.push(c);
+            self.bump();
+        }
+        ident
+    }
+
+    fn value(&mut self) -> Result<Value, Error> {
+        match self.peek() {
+            Some('"') => self.quoted_string().map(Value::String),
+            Some('`') => {
+                self.bump();
+                let mut s = String::new();
+                loop {
+                    match self.bump() {
+                        None => return self.error("unterminated raw string"),
+                        Some('`') => break,
+                        Some(c) => s.push(c),
+                    }
+                }
+                Ok(Value::String(s))
+            }
+            Some(c) if c.is_ascii_digit() || c == '-' || c == '+' || c == '.' => self.number(),
+            Some(c) if is_ident_start(c) => match self.identifier().as_str() {
+                "true" | "on" => Ok(Value::Bool(true)),
+                "false" | "off" => Ok(Value::Bool(false)),
+                "null" => Ok(Value::Null),
+                other => self.error(format!("unexpected identifier '{other}'")),
+            },
+            Some(c) => self.error(format!("unexpected character '{c}'")),
+            None => self.error("unexpected end of input"),
+        }
+    }
+
+    fn quoted_string(&mut self) -> Result<String, Error> {
+        self.bump();
+        let mut s = String::new();
+        loop {
+            match self.bump() {
+                None | Some('\n') => return self.error("unterminated string"),
+                Some('"') => return Ok(s),
+                Some('\\') => match self.bump() {
+                    Some('n') => s.push('\n'),
+                    Some('r') => s.push('\r'),
+                    Some('t') => s.push('\t'),
+                    Some('"') => s.push('"'),
+                    Some('\\') => s.push('\\'),
+                    Some('\r' | '\n') => {
+                        while let Some(' ' | '\t' | '\r' | '\n') = self.peek() {
+                            self.bump();
+                        }
+                    }
+                    Some(c) => return self.error(format!("invalid escape '\\{c}'")),
+                    None => return self.error("unterminated string"),
+                },
+                Some(c) => s.push(c),
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Value, Error> {
+        let mut text = String::new();
+        while let Some(c) = self.peek() {
+            if c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | '+' | '_') {
+                text.push(c);
+                self.bump();
+            } else {
+                break;
+            }
+        }
+        let trimmed = text.trim_end_matches(['L', 'l']);
+        if let Ok(i) = trimmed.parse() {
+            return Ok(Value::Int(i));
+        }
+        let trimmed = text.trim_end_matches(['f', 'F', 'd', 'D']);
+        match trimmed.parse() {
+            Ok(x) => Ok(Value::Float(x)),
+            Err(_) => self.error(format!("unsupported value '{text}'")),
+        }
+    }
+}
+
+fn is_ident_start(c: char) -> bool {
+    c.is_alphabetic() || c == '_'
+}
+
+fn is_ident_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | '$')
+}
+
+/// Renders a document back to SDLang text, one tag per line with tab indentation.
+pub fn to_string(doc: &Document) -> String {
+    let mut out = String::new();
+    for tag in &doc.tags {
+        write_tag(&mut out, tag, 0);
+    }
+    write_comments(&mut out, &doc.trailing_comments, 0);
+    out
+}
+
+fn write_comments(out: &mut String, comments: &[String], depth: usize) {
+    for comment in comments {
+        push_indent(out, depth);
+        if comment.is_empty() {
+            out.push_str("//\n");
+        } else {
+            let _ = writeln!(out, "// {comment}");
+        }
+    }
+}
+
+fn push_indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push('\t');
+    }
+}
+
+fn write_tag(out: &mut String, tag: &Tag, depth: usize) {
+    write_comments(out, &tag.comments, depth);
+    push_indent(out, depth);
+    let anonymous = tag.name == "content" && !tag.values.is_empty();
+    if !anonymous {
+        out.push_str(&tag.name);
+    }
+    for (i, value) in tag.values.iter().enumerate() {
+        if i > 0 || !anonymous {
+            out.push(' ');
+        }
+        let _ = write!(out, "{value}");
+    }
+    for (key, value) in &tag.attributes {
+        let _ = write!(out, " {key}={value}");
+    }
+    let inline = |out: &mut String| {
+        if let Some(comment) = &tag.inline_comment {
+            let _ = write!(out, " // {comment}");
+        }
+    };
+    if !tag.children.is_empty() || !tag.trailing_comments.is_empty() {
+        out.push_str(" {");
+        inline(out);
+        out.push('\n');
+        for child in &tag.children {
+            write_tag(out, child, depth + 1);
+        }
+        write_comments(out, &tag.trailing_comments, depth + 1);
+        push_indent(out, depth);
+        out.push('}');
+    } else {
+        inline(out);
+    }
+    out.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_values_and_attributes() {
+        let doc = parse(
+            r#"name "app"
+authors "Jane" "John"
+dependency "vibe-d" version="~>0.9.5" optional=true
+dflags "-O" platform="ldc" // trailing comment
+"#,
+        )
+        .unwrap();
+        assert_eq!(doc.tags.len(), 4);
+        assert_eq!(doc.tags[0].name, "name");
+        assert_eq!(doc.tags[1].values.len(), 2);
+        assert_eq!(
+            doc.tags[2].attribute("version"),
+            Some(&Value::String("~>0.9.5".into()))
+        );
+        assert_eq!(doc.tags[2].attribute("optional"), Some(&Value::Bool(true)));
+        assert_eq!(
+            doc.tags[3].inline_comment.as_deref(),
+            Some("trailing comment")
+        );
+        assert!(doc.trailing_comments.is_empty());
+    }
+
+    #[test]
+    fn test_parse_children_and_comments() {
+        let doc = parse(
+            r#"# the package
+name "app"
+/* configurations */
+configuration "unittest" {
+	targetType "executable" ; versions "Testing"
+	-- nothing else
+}
+"#,
+        )
+        .unwrap();
+        assert_eq!(doc.tags[0].comments, vec!["the package"]);
+        let config = &doc.tags[1];
+        assert_eq!(config.comments, vec!["configurations"]);
+        assert_eq!(config.children.len(), 2);
+        assert_eq!(config.trailing_comments, vec!["nothing else"]);
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        assert_eq!(parse("name \"app").unwrap_err().line, 1);
+        assert!(parse("configuration \"x\" {\n").is_err());
+        assert!(parse("dependency version=\"1\" \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input = "// header\nname \"app\" // inline\nconfiguration \"lib\" { // block\n\
+                     \ttargetType \"library\"\n\t// end\n}\n";
+        let doc = parse(input).unwrap();
+        assert_eq!(to_string(&doc), input);
+        assert_eq!(parse(&to_string(&doc)).unwrap(), doc);
+    }
+}
@@ -0,0 +1,329 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! `dub.selections.json`, the lockfile in which DUB records the version,
+//! path or repository chosen for every dependency.
+
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+pub const FILE_NAME: &str = "dub.selections.json";
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Json(serde_json::Error),
+    Invalid(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Failed to read '{}': {e}", path.display()),
+            Error::Json(e) => write!(f, "Invalid {FILE_NAME}: {e}"),
+            Error::Invalid(msg) => write!(f, "Invalid {FILE_NAME}: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json(e)
+    }
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// What a package is locked to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Selection {
+    /// A registry release such as `1.7.1`, or a branch like `~master`.
+    Version(String),
+    /// A directory, relative to the package.
+    Path(String),
+    /// A commit of a git repository, e.g. `git+https://host/repo.git`.
+    Repository { repository: String, version: String },
+}
+
+impl Selection {
+    /// The selected version, if the selection names one.
+    pub fn version(&self) -> Option<&str> {
+        match self {
+            Selection::Version(version) | Selection::Repository { version, .. } => Some(version),
+            Selection::Path(_) => None,
+        }
+    }
+
+    /// `v1.7.1`, `~master`, or the path or repository in parentheses.
+    fn label(&self) -> String {
+        match self {
+            Selection::Version(version) if version.starts_with('~') => version.clone(),
+            Selection::Version(version) => format!("v{version}"),
+            selection => format!("({selection})"),
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        match self {
+            Selection::Version(version) => Value::from(version.as_str()),
+            Selection::Path(path) => serde_json::json!({ "path": path }),
+            Selection::Repository {
+                repository,
+                version,
+            } => serde_json::json!({ "repository": repository, "version": version }),
+        }
+    }
+
+    fn from_json(name: &str, value: &Value) -> Result<Self> {
+        let field = |key: &str| value.get(key).and_then(Value::as_str).map(str::to_string);
+        let invalid = || Error::Invalid(format!("unsupported selection for '{name}': {value}"));
+        if let Some(version) = value.as_str() {
+            return Ok(Selection::Version(version.to_string()));
+        }
+        match (field("path"), field("repository"), field("version")) {
+            (Some(path), None, _) => Ok(Selection::Path(path)),
+            (None, Some(repository), Some(version)) => Ok(Selection::Repository {
+                repository,
+                version,
+            }),
+            (None, None, Some(version)) => Ok(Selection::Version(version)),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+impl fmt::Display for Selection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Selection::Version(version) => f.write_str(version),
+            Selection::Path(path) => write!(f, "path {path}"),
+            Selection::Repository {
+                repository,
+                version,
+            } => write!(f, "{repository}#{version}"),
+        }
+    }
+}
+
+/// The parsed lockfile.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Selections {
+    pub file_version: u64,
+    pub versions: BTreeMap<String, Selection>,
+    /// Other top-level keys, such as `inheritable`, written back as read.
+    pub extra: Map<String, Value>,
+}
+
+impl Selections {
+    /// Reads `dir/dub.selections.json`, or returns `None` when there is none.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(FILE_NAME);
+        match fs::read_to_string(&path) {
+            Ok(text) => Self::from_json_str(&text).map(Some),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::Io(path, e)),
+        }
+    }
+
+    pub fn from_json_str(text: &str) -> Result<Self> {
+        let mut value: Value = serde_json::from_str(text)?;
+        let file_version = value
+            .get("fileVersion")
+            .and_then(Value::as_u64)
+            .ok_or_else(|| Error::Invalid("missing fileVersion".to_string()))?;
+        let mut versions = BTreeMap::new();
+        if let Some(entries) = value.get("versions").and_then(Value::as_object) {
+            for (name, selection) in entries {
+                versions.insert(name.clone(), Selection::from_json(name, selection)?);
+            }
+        }
+        let mut extra = match value.take() {
+            Value::Object(object) => object,
+            _ => Map::new(),
+        };
+        extra.remove("fileVersion");
+        extra.remove("versions");
+        Ok(Self {
+            file_version,
+            versions,
+            extra,
+        })
+    }
+
+    /// The selection for `package`; sub-packages such as `vibe-d:http`
+    /// share their parent's entry.
+    pub fn get(&self, package: &str) -> Option<&Selection> {
+        self.versions.get(base_name(package))
+    }
+
+    /// The lockfile as DUB writes it: tab-indented, one package per line.
+    pub fn to_json(&self) -> String {
+        let mut out = format!("{{\n\t\"fileVersion\": {},\n", self.file_version);
+        for (key, value) in &self.extra {
+            out.push_str(&format!("\t{}: {value},\n", Value::from(key.as_str())));
+        }
+        out.push_str("\t\"versions\": {\n");
+        let entries: Vec<String> = self
+            .versions
+            .iter()
+            .map(|(name, selection)| {
+                format!(
+                    "\t\t{}: {}",
+                    Value::from(name.as_str()),
+                    selection.to_json()
+                )
+            })
+            .collect();
+        if !entries.is_empty() {
+            out.push_str(&entries.join(",\n"));
+            out.push('\n');
+        }
+        out.push_str("\t}\n}\n");
+        out
+    }
+
+    /// Writes `dir/dub.selections.json`.
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(FILE_NAME);
+        fs::write(&path, self.to_json()).map_err(|e| Error::Io(path, e))
+    }
+}
+
+/// The package a sub-package such as `vibe-d:http` belongs to.
+pub fn base_name(package: &str) -> &str {
+    package.split(':').next().unwrap_or(package)
+}
+
+/// A package whose selection was added, removed or changed, displayed as
+/// `cargo update` reports it: `    Updating mir-core v1.7.1 -> v1.7.3`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Change {
+    pub name: String,
+    pub old: Option<Selection>,
+    pub new: Option<Selection>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = &self.name;
+        match (&self.old, &self.new) {
+            (Some(old), Some(new)) => write!(
+                f,
+                "{:>12} {name} {} -> {}",
+                "Updating",
+                old.label(),
+                new.label()
+            ),
+            (None, Some(new)) => write!(f, "{:>12} {name} {}", "Adding", new.label()),
+            (Some(old), None) => write!(f, "{:>12} {name} {}", "Removing", old.label()),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+/// Packages whose selection differs between `old` and `new`, by name.
+pub fn diff(old: &Selections, new: &Selections) -> Vec<Change> {
+    let names: std::collections::BTreeSet<&String> =
+        old.versions.keys().chain(new.versions.keys()).collect();
+    names
+        .into_iter()
+        .filter(|name| old.versions.get(*name) != new.versions.get(*name))
+        .map(|name| Change {
+            name: name.clone(),
+            old: old.versions.get(name).cloned(),
+            new: new.versions.get(name).cloned(),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_selections() {
+        let selections = Selections::from_json_str(
+            r#"{
+                "fileVersion": 1,
+                "versions": {
+                    "mir-core": "1.7.1",
+                    "vibe-d": {"version": "0.9.8"},
+                    "dlib": {"path": "../dlib"},
+                    "silly": {"repository": "git+https://gitlab.com/AntonMeep/silly.git", "version": "a1b2c3"}
+                }
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(selections.file_version, 1);
+        assert_eq!(
+            selections.get("vibe-d:http"),
+            Some(&Selection::Version("0.9.8".to_string()))
+        );
+        assert_eq!(
+            selections.get("dlib"),
+            Some(&Selection::Path("../dlib".to_string()))
+        );
+        let silly = selections.get("silly").unwrap();
+        assert_eq!(silly.version(), Some("a1b2c3"));
+        assert_eq!(
+            silly.to_string(),
+            "git+https://gitlab.com/AntonMeep/silly.git#a1b2c3"
+        );
+
+        let err =
+            Selections::from_json_str(r#"{"fileVersion": 1, "versions": {"x": 3}}"#).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid dub.selections.json: unsupported selection for 'x': 3"
+        );
+    }
+
+    #[test]
+    fn test_write_selections() {
+        let text = "{\n\
+                    \t\"fileVersion\": 1,\n\
+                    \t\"inheritable\": true,\n\
+                    \t\"versions\": {\n\
+                    \t\t\"dlib\": {\"path\":\"../dlib\"},\n\
+                    \t\t\"mir-core\": \"1.7.1\",\n\
+                    \t\t\"silly\": {\"repository\":\"git+https://host/silly.git\",\"version\":\"a1b2c3\"}\n\
+                    \t}\n\
+                    }\n";
+        let selections = Selections::from_json_str(text).unwrap();
+        assert_eq!(selections.extra["inheritable"], true);
+        assert_eq!(selections.to_json(), text);
+        assert_eq!(
+            Selections::default().to_json(),
+            "{\n\t\"fileVersion\": 0,\n\t\"versions\": {\n\t}\n}\n"
+        );
+    }
+
+    #[test]
+    fn test_diff() {
+        let old = Selections::from_json_str(
+            r#"{"fileVersion": 1, "versions": {"mir-core": "1.7.1", "dlib": {"path": "../dlib"},
+                "vibe-d": "~master"}}"#,
+        )
+        .unwrap();
+        let new = Selections::from_json_str(
+            r#"{"fileVersion": 1, "versions": {"mir-core": "1.7.3", "silly": "1.1.1",
+                "vibe-d": "~master"}}"#,
+        )
+        .unwrap();
+        let lines: Vec<String> = diff(&old, &new).iter().map(|c| c.to_string()).collect();
+        assert_eq!(
+            lines,
+            [
+                "    Removing dlib (path ../dlib)",
+                "    Updating mir-core v1.7.1 -> v1.7.3",
+                "      Adding silly v1.1.1",
+            ]
+        );
+        assert!(diff(&new, &new).is_empty());
+    }
+}
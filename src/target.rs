@@ -0,0 +1,246 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Mapping of Rust target triples to DUB `--arch` values and LDC `-mtriple`s.
+
+use crate::toolchain::Family;
+use std::env;
+use std::fmt;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The triple does not have the `arch-vendor-os[-env]` shape.
+    Malformed(String),
+    Unsupported {
+        triple: String,
+        family: Option<Family>,
+        reason: String,
+    },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Malformed(triple) => write!(f, "Invalid target triple '{triple}'"),
+            Error::Unsupported {
+                triple,
+                family: Some(family),
+                reason,
+            } => write!(
+                f,
+                "Target '{triple}' is not supported with {family}: {reason}"
+            ),
+            Error::Unsupported {
+                triple,
+                family: None,
+                reason,
+            } => write!(f, "Target '{triple}' is not supported: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// A Rust target triple such as `aarch64-unknown-linux-gnu`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Target {
+    pub triple: String,
+    /// Architecture as written, e.g. `i686`, `armv7`, `riscv64gc`.
+    pub arch: String,
+    pub vendor: String,
+    pub os: String,
+    pub env: Option<String>,
+}
+
+impl Target {
+    /// Splits a triple. The vendor may be omitted for a few two-part
+    /// triples Rust accepts (`wasm32-wasi`), in which case it is `unknown`.
+    pub fn parse(triple: &str) -> Result<Self> {
+        let parts: Vec<&str> = triple.split('-').collect();
+        if parts.iter().any(|p| p.is_empty()) {
+            return Err(Error::Malformed(triple.to_string()));
+        }
+        let (arch, vendor, os, env) = match parts.as_slice() {
+            [arch, os] => (*arch, "unknown", *os, None),
+            [arch, vendor, os] => (*arch, *vendor, *os, None),
+            [arch, vendor, os, env] => (*arch, *vendor, *os, Some(env.to_string())),
+            _ => return Err(Error::Malformed(triple.to_string())),
+        };
+        Ok(Self {
+            triple: triple.to_string(),
+            arch: arch.to_string(),
+            vendor: vendor.to_string(),
+            os: os.to_string(),
+            env,
+        })
+    }
+
+    /// The target cargo-dub itself runs on.
+    pub fn host() -> Self {
+        let (arch, os) = (env::consts::ARCH, env::consts::OS);
+        Self {
+            triple: format!("{arch}-{os}"),
+            arch: arch.to_string(),
+            vendor: "unknown".to_string(),
+            os: os.to_string(),
+            env: None,
+        }
+    }
+
+    /// Architecture in `std::env::consts::ARCH` terms (`x86`, `arm`, `riscv64`).
+    pub fn arch_family(&self) -> &str {
+        let arch = self.arch.as_str();
+        match arch {
+            "i386" | "i586" | "i686" | "x86" => "x86",
+            "arm64" => "aarch64",
+            _ if arch.starts_with("armv") || arch.starts_with("thumbv") => "arm",
+            _ if arch.starts_with("riscv64") => "riscv64",
+            _ if arch.starts_with("riscv32") => "riscv32",
+            _ => arch,
+        }
+    }
+
+    /// Operating system in `std::env::consts::OS` terms.
+    pub fn os_family(&self) -> &str {
+        match self.os.as_str() {
+            "darwin" | "macos" | "macosx" => "macos",
+            os => os,
+        }
+    }
+
+    fn is_host(&self) -> bool {
+        let host = Self::host();
+        self.arch_family() == host.arch_family() && self.os_family() == host.os_family()
+    }
+
+    /// The LLVM triple LDC expects through `-mtriple`. Rust's RISC-V names
+    /// carry ISA extensions (`riscv64gc`) that LLVM spells as target features.
+    pub fn llvm_triple(&self) -> String {
+        let arch = match self.arch_family() {
+            "riscv64" | "riscv32" => self.arch_family(),
+            _ => &self.arch,
+        };
+        let mut triple = format!("{arch}-{}-{}", self.vendor, self.os);
+        if let Some(env) = &self.env {
+            triple.push('-');
+            triple.push_str(env);
+        }
+        triple
+    }
+
+    /// The `--arch` value DUB needs to build for this target with `family`,
+    /// or `None` when the compiler's default already matches.
+    ///
+    /// LDC cross-compiles to anything LLVM knows: DUB forwards a full triple
+    /// as `-mtriple`. DMD and GDC only switch between 32- and 64-bit x86 on
+    /// the host OS; other targets need a cross GDC given with `--compiler`.
+    pub fn dub_arch(&self, family: Option<Family>) -> Result<Option<String>> {
+        let unsupported = |reason: String| Error::Unsupported {
+            triple: self.triple.clone(),
+            family,
+            reason,
+        };
+        match family {
+            Some(Family::Ldc) => Ok(Some(self.llvm_triple())),
+            _ if self.is_host() => Ok(None),
+            Some(family @ (Family::Dmd | Family::Gdc)) => {
+                let host = Self::host();
+                if self.os_family() != host.os_family() {
+                    return Err(unsupported(format!(
+                        "{family} only builds for the host OS ({})",
+                        host.os_family()
+                    )));
+                }
+                match (self.arch_family(), host.arch_family()) {
+                    ("x86" | "x86_64", "x86" | "x86_64") => {
+                        Ok(Some(self.arch_family().to_string()))
+                    }
+                    _ => Err(unsupported(match family {
+                        Family::Gdc => format!(
+                            "use a GDC built for it, e.g. --compiler={}-gdc",
+                            self.llvm_triple().replace("-unknown", "")
+                        ),
+                        _ => "dmd only targets x86 and x86_64".to_string(),
+                    })),
+                }
+            }
+            None => Err(unsupported(
+                "cross-compiling requires LDC; pass --compiler=ldc2 or set DC".to_string(),
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Target {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.triple)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let target = Target::parse("aarch64-unknown-linux-gnu").unwrap();
+        assert_eq!(target.arch, "aarch64");
+        assert_eq!(target.os, "linux");
+        assert_eq!(target.env.as_deref(), Some("gnu"));
+        assert_eq!(Target::parse("wasm32-wasi").unwrap().vendor, "unknown");
+        assert_eq!(
+            Target::parse("x86_64").unwrap_err().to_string(),
+            "Invalid target triple 'x86_64'"
+        );
+        assert!(Target::parse("x86_64--linux").is_err());
+    }
+
+    #[test]
+    fn test_llvm_triple() {
+        for (rust, llvm) in [
+            ("x86_64-unknown-linux-gnu", "x86_64-unknown-linux-gnu"),
+            ("riscv64gc-unknown-linux-gnu", "riscv64-unknown-linux-gnu"),
+            (
+                "armv7-unknown-linux-gnueabihf",
+                "armv7-unknown-linux-gnueabihf",
+            ),
+            ("aarch64-apple-darwin", "aarch64-apple-darwin"),
+            ("wasm32-unknown-unknown", "wasm32-unknown-unknown"),
+        ] {
+            assert_eq!(Target::parse(rust).unwrap().llvm_triple(), llvm);
+        }
+        let i686 = Target::parse("i686-pc-windows-msvc").unwrap();
+        assert_eq!(i686.arch_family(), "x86");
+        assert_eq!(i686.os_family(), "windows");
+    }
+
+    #[test]
+    fn test_dub_arch() {
+        let target = Target::parse("aarch64-unknown-linux-musl").unwrap();
+        assert_eq!(
+            target.dub_arch(Some(Family::Ldc)).unwrap().as_deref(),
+            Some("aarch64-unknown-linux-musl")
+        );
+
+        let host = Target::host();
+        assert_eq!(host.dub_arch(Some(Family::Dmd)).unwrap(), None);
+        assert_eq!(host.dub_arch(None).unwrap(), None);
+
+        let foreign = Target::parse("wasm32-unknown-unknown").unwrap();
+        assert_eq!(
+            foreign.dub_arch(Some(Family::Dmd)).unwrap_err().to_string(),
+            format!(
+                "Target 'wasm32-unknown-unknown' is not supported with dmd: \
+                 dmd only builds for the host OS ({})",
+                env::consts::OS
+            )
+        );
+        assert!(foreign
+            .dub_arch(None)
+            .unwrap_err()
+            .to_string()
+            .contains("requires LDC"));
+    }
+}
@@ -0,0 +1,644 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Discovery of D compilers and DUB.
+
+use crate::version::{Version, VersionReq};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::{env, fs};
+
+/// Oldest DUB release cargo-dub is tested against.
+pub const DUB_MINIMUM: Version = Version::new(1, 30, 0);
+
+/// Compiler binaries looked for, with the family each belongs to.
+pub const COMPILERS: [(&str, Family); 5] = [
+    ("dmd", Family::Dmd),
+    ("ldc2", Family::Ldc),
+    ("ldmd2", Family::Ldc),
+    ("gdc", Family::Gdc),
+    ("gdmd", Family::Gdc),
+];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Family {
+    Dmd,
+    Ldc,
+    Gdc,
+}
+
+impl Family {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Family::Dmd => "dmd",
+            Family::Ldc => "ldc",
+            Family::Gdc => "gdc",
+        }
+    }
+
+    /// Maps a compiler name or path (`ldmd2`, `/opt/gdc-13`) to its family.
+    pub fn of(compiler: &str) -> Option<Family> {
+        let name = Path::new(compiler).file_stem()?.to_str()?;
+        if name.starts_with("ldc") || name.starts_with("ldmd") {
+            Some(Family::Ldc)
+        } else if name.starts_with("gdc") || name.starts_with("gdmd") {
+            Some(Family::Gdc)
+        } else if name.starts_with("dmd") {
+            Some(Family::Dmd)
+        } else {
+            None
+        }
+    }
+}
+
+impl fmt::Display for Family {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A D compiler found on this machine.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Compiler {
+    pub name: String,
+    pub family: Family,
+    pub path: PathBuf,
+    /// The compiler's own release, e.g. LDC 1.36.0.
+    pub version: Option<Version>,
+    /// The DMD frontend it is based on, e.g. 2.106.1.
+    pub frontend: Option<Version>,
+}
+
+/// The DUB executable and its version.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Dub {
+    pub path: PathBuf,
+    pub version: Option<Version>,
+}
+
+impl Dub {
+    /// Fails when DUB is older than [`DUB_MINIMUM`]. Unknown versions pass.
+    pub fn check_minimum(&self) -> Result<(), String> {
+        match &self.version {
+            Some(version) if *version < DUB_MINIMUM => Err(format!(
+                "DUB {version} found at '{}', but cargo-dub requires DUB {DUB_MINIMUM} or newer",
+                self.path.display()
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Everything [`discover`] found.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Toolchain {
+    pub compilers: Vec<Compiler>,
+    pub dub: Option<Dub>,
+}
+
+/// Locates an executable by name on `PATH`, or returns the path as given.
+pub fn find_executable(name: &str) -> Option<PathBuf> {
+    let path = Path::new(name);
+    if path.components().count() > 1 {
+        return path.is_file().then(|| path.to_path_buf());
+    }
+    let exe = format!("{name}{}", env::consts::EXE_SUFFIX);
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(&exe))
+        .find(|candidate| candidate.is_file())
+}
+
+/// Directories where the official installers put compilers, besides `PATH`.
+pub fn install_roots() -> Vec<PathBuf> {
+    let mut roots = Vec::new();
+    let home = env::var_os("HOME")
+        .or_else(|| env::var_os("USERPROFILE"))
+        .map(PathBuf::from);
+    // install.sh layout: ~/dlang/dmd-2.106.0/linux/bin64, ~/dlang/ldc-1.36.0/bin
+    if let Some(dlang) = home.map(|h| h.join("dlang")) {
+        if let Ok(entries) = fs::read_dir(&dlang) {
+            let mut installs: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+            installs.sort();
+            for install in installs.into_iter().rev() {
+                for sub in [
+                    "bin",
+                    "linux/bin64",
+                    "linux/bin",
+                    "osx/bin",
+                    "freebsd/bin64",
+                ] {
+                    roots.push(install.join(sub));
+                }
+            }
+        }
+    }
+    if cfg!(windows) {
+        roots.extend(
+            [
+                r"C:\D\dmd2\windows\bin64",
+                r"C:\D\dmd2\windows\bin",
+                r"C:\ldc2\bin",
+            ]
+            .map(PathBuf::from),
+        );
+    } else {
+        roots.extend(
+            [
+                "/usr/local/bin",
+                "/usr/bin",
+                "/opt/homebrew/bin",
+                "/snap/bin",
+            ]
+            .map(PathBuf::from),
+        );
+    }
+    roots.into_iter().filter(|dir| dir.is_dir()).collect()
+}
+
+fn version_output(path: &Path) -> Option<String> {
+    let output = Command::new(path)
+        .arg("--version")
+        .stdin(Stdio::null())
+        .output()
+        .ok()?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    Some(text)
+}
+
+/// Extracts the compiler and frontend versions from `--version` output.
+pub fn parse_compiler_version(family: Family, text: &str) -> (Option<Version>, Option<Version>) {
+    let first_line = text.lines().next().unwrap_or_default();
+    match family {
+        Family::Dmd => {
+            let version = Version::find_in(first_line);
+            (version.clone(), version)
+        }
+        Family::Ldc => {
+            // LDC - the LLVM D compiler (1.36.0):
+            //   based on DMD v2.106.1 and LLVM 17.0.6
+            let version = Version::find_in(first_line);
+            let frontend = text
+                .lines()
+                .find_map(|line| line.trim().strip_prefix("based on DMD"))
+                .and_then(Version::find_in);
+            (version, frontend)
+        }
+        Family::Gdc => {
+            // gdc (Ubuntu 13.2.0-4ubuntu3) 13.2.0
+            let version = first_line
+                .rsplit(' ')
+                .find_map(|word| word.parse::<Version>().ok());
+            let frontend = version.as_ref().and_then(gdc_frontend);
+            (version, frontend)
+        }
+    }
+}
+
+/// Frontend version shipped with each GCC major release.
+fn gdc_frontend(gcc: &Version) -> Option<Version> {
+    let (minor, patch) = match gcc.major {
+        9..=11 => (76, 1),
+        12 => (100, 0),
+        13 => (103, 1),
+        14 => (108, 0),
+        15 => (111, 0),
+        _ => return None,
+    };
+    Some(Version::new(2, minor, patch))
+}
+
+/// Probes a compiler binary.
+pub fn probe_compiler(name: &str, family: Family, path: &Path) -> Option<Compiler> {
+    let text = version_output(path)?;
+    let (version, frontend) = parse_compiler_version(family, &text);
+    Some(Compiler {
+        name: name.to_string(),
+        family,
+        path: path.to_path_buf(),
+        version,
+        frontend,
+    })
+}
+
+/// Probes a compiler given by name or path, as passed to `--compiler` or `DC`.
+pub fn probe(compiler: &str) -> Option<Compiler> {
+    let family = Family::of(compiler)?;
+    let path = find_executable(compiler)?;
+    let name = Path::new(compiler).file_stem()?.to_str()?;
+    probe_compiler(name, family, &path)
+}
+
+/// Locates DUB on `PATH` and reads its version.
+pub fn find_dub() -> Option<Dub> {
+    let path = find_executable("dub")?;
+    let version = version_output(&path).as_deref().and_then(Version::find_in);
+    Some(Dub { path, version })
+}
+
+/// Finds every compiler on `PATH` and in the usual install roots.
+pub fn discover() -> Toolchain {
+    let mut dirs: Vec<PathBuf> = env::var_os("PATH")
+        .map(|path| env::split_paths(&path).collect())
+        .unwrap_or_default();
+    dirs.extend(install_roots());
+
+    let mut seen = HashSet::new();
+    let mut compilers = Vec::new();
+    for dir in dirs {
+        for (name, family) in COMPILERS {
+            let path = dir.join(format!("{name}{}", env::consts::EXE_SUFFIX));
+            if !path.is_file() {
+                continue;
+            }
+            let canonical = fs::canonicalize(&path).unwrap_or_else(|_| path.clone());
+            if !seen.insert(canonical) {
+                continue;
+            }
+            if let Some(compiler) = probe_compiler(name, family, &path) {
+                compilers.push(compiler);
+            }
+        }
+    }
+    Toolchain {
+        compilers,
+        dub: find_dub(),
+    }
+}
+
+impl Toolchain {
+    /// Renders the toolchain as an aligned table.
+    pub fn table(&self) -> String {
+        let show = |v: &Option<Version>| v.as_ref().map_or("-".to_string(), Version::to_string);
+        let mut rows = vec![[
+            "NAME".to_string(),
+            "FAMILY".to_string(),
+            "VERSION".to_string(),
+            "FRONTEND".to_string(),
+            "PATH".to_string(),
+        ]];
+        for c in &self.compilers {
+            rows.push([
+                c.name.clone(),
+                c.family.to_string(),
+                show(&c.version),
+                show(&c.frontend),
+                c.path.display().to_string(),
+            ]);
+        }
+        if let Some(dub) = &self.dub {
+            rows.push([
+                "dub".to_string(),
+                "-".to_string(),
+                show(&dub.version),
+                "-".to_string(),
+                dub.path.display().to_string(),
+            ]);
+        }
+        render_table(&rows)
+    }
+}
+
+/// Left-aligns rows into columns separated by two spaces.
+pub fn render_table<const N: usize>(rows: &[[String; N]]) -> String {
+    let mut widths = [0; N];
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let mut out = String::new();
+    for row in rows {
+        let line: Vec<String> = row
+            .iter()
+            .zip(widths)
+            .map(|(cell, width)| format!("{cell:width$}"))
+            .collect();
+        out.push_str(line.join("  ").trim_end());
+        out.push('\n');
+    }
+    out
+}
+
+/// File pinning the toolchain of a project, searched upwards from the working directory.
+pub const TOOLCHAIN_FILE: &str = "d-toolchain.toml";
+
+/// What to do when the installed toolchain does not satisfy the pin.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnMismatch {
+    #[default]
+    Error,
+    Warn,
+}
+
+/// The `[toolchain]` table of `d-toolchain.toml`.
+///
+/// ```toml
+/// [toolchain]
+/// compiler = "ldc"          # dmd, ldc, gdc, or a binary name such as ldmd2
+/// version = "~>1.36"        # the compiler's own version
+/// frontend = ">=2.105.0"
+/// dub = ">=1.34.0"
+/// on-mismatch = "warn"      # default: error
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "kebab-case", default, deny_unknown_fields)]
+pub struct Pin {
+    pub compiler: Option<String>,
+    pub version: Option<VersionReq>,
+    pub frontend: Option<VersionReq>,
+    pub dub: Option<VersionReq>,
+    pub on_mismatch: OnMismatch,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ToolchainFile {
+    toolchain: Pin,
+}
+
+/// Outcome of checking a [`Pin`] against the installed toolchain.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Resolution {
+    /// The compiler to build with, when one satisfies the pin.
+    pub compiler: Option<Compiler>,
+    pub problems: Vec<String>,
+}
+
+impl Pin {
+    /// Loads the nearest `d-toolchain.toml` above `start`.
+    pub fn find(start: &Path) -> Result<Option<(PathBuf, Pin)>, String> {
+        let Some(path) = start
+            .ancestors()
+            .map(|dir| dir.join(TOOLCHAIN_FILE))
+            .find(|path| path.is_file())
+        else {
+            return Ok(None);
+        };
+        let text = fs::read_to_string(&path)
+            .map_err(|e| format!("Failed to read '{}': {e}", path.display()))?;
+        let file: ToolchainFile =
+            toml::from_str(&text).map_err(|e| format!("Invalid '{}': {e}", path.display()))?;
+        Ok(Some((path, file.toolchain)))
+    }
+
+    /// Human-readable summary such as `ldc ~>1.36 (frontend >=2.105.0)`.
+    pub fn summary(&self) -> String {
+        let mut text = self
+            .compiler
+            .clone()
+            .unwrap_or_else(|| "any compiler".into());
+        if let Some(version) = &self.version {
+            text.push_str(&format!(" {version}"));
+        }
+        if let Some(frontend) = &self.frontend {
+            text.push_str(&format!(" (frontend {frontend})"));
+        }
+        text
+    }
+
+    pub fn matches(&self, compiler: &Compiler) -> bool {
+        let name_ok = match self.compiler.as_deref() {
+            None => true,
+            Some(wanted @ ("dmd" | "ldc" | "gdc")) => compiler.family.as_str() == wanted,
+            Some(wanted) => compiler.name == wanted,
+        };
+        let satisfies = |req: &Option<VersionReq>, version: &Option<Version>| match (req, version) {
+            (None, _) => true,
+            (Some(req), Some(version)) => req.matches(version),
+            (Some(_), None) => false,
+        };
+        name_ok
+            && satisfies(&self.version, &compiler.version)
+            && satisfies(&self.frontend, &compiler.frontend)
+    }
+
+    /// The newest matching compiler; earlier entries win ties, so `ldc2`
+    /// is preferred over `ldmd2` from the same install.
+    pub fn select<'a>(&self, compilers: &'a [Compiler]) -> Option<&'a Compiler> {
+        compilers
+            .iter()
+            .filter(|c| self.matches(c))
+            .fold(None, |best: Option<&Compiler>, c| match best {
+                Some(best) if best.version >= c.version => Some(best),
+                _ => Some(c),
+            })
+    }
+
+    /// Describes why `dub` does not satisfy the pinned DUB requirement, if it doesn't.
+    pub fn check_dub(&self, dub: Option<&Version>) -> Option<String> {
+        let req = self.dub.as_ref()?;
+        match dub {
+            Some(version) if req.matches(version) => None,
+            Some(version) => Some(format!("DUB {version} does not satisfy {req}")),
+            None => Some(format!("DUB {req} is required, but its version is unknown")),
+        }
+    }
+
+    /// Checks the pin. An `explicit` compiler (from `--compiler`, `DC` or
+    /// Cargo metadata) is verified; otherwise one is chosen from `compilers`.
+    pub fn resolve(
+        &self,
+        explicit: Option<&Compiler>,
+        compilers: &[Compiler],
+        dub: Option<&Version>,
+    ) -> Resolution {
+        let mut resolution = Resolution {
+            compiler: None,
+            problems: self.check_dub(dub).into_iter().collect(),
+        };
+        match explicit {
+            Some(compiler) if self.matches(compiler) => {
+                resolution.compiler = Some(compiler.clone());
+            }
+            Some(compiler) => resolution.problems.push(format!(
+                "{} {} at '{}' does not satisfy {}",
+                compiler.name,
+                compiler
+                    .version
+                    .as_ref()
+                    .map_or("(unknown version)".to_string(), Version::to_string),
+                compiler.path.display(),
+                self.summary()
+            )),
+            None => match self.select(compilers) {
+                Some(compiler) => resolution.compiler = Some(compiler.clone()),
+                None => {
+                    let found: Vec<String> = compilers
+                        .iter()
+                        .map(|c| {
+                            let version = c.version.as_ref().map(Version::to_string);
+                            format!("{} {}", c.name, version.unwrap_or_else(|| "?".into()))
+                        })
+                        .collect();
+                    resolution.problems.push(format!(
+                        "no installed compiler satisfies {} (found: {})",
+                        self.summary(),
+                        if found.is_empty() {
+                            "none".to_string()
+                        } else {
+                            found.join(", ")
+                        }
+                    ));
+                }
+            },
+        }
+        resolution
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_compiler_versions() {
+        assert_eq!(
+            parse_compiler_version(
+                Family::Dmd,
+                "DMD64 D Compiler v2.106.0\nCopyright (C) 1999-2023 by The D Language Foundation"
+            ),
+            (Some(Version::new(2, 106, 0)), Some(Version::new(2, 106, 0)))
+        );
+        assert_eq!(
+            parse_compiler_version(
+                Family::Ldc,
+                "LDC - the LLVM D compiler (1.36.0):\n  based on DMD v2.106.1 and LLVM 17.0.6\n  built with LDC - the LLVM D compiler (1.36.0)"
+            ),
+            (Some(Version::new(1, 36, 0)), Some(Version::new(2, 106, 1)))
+        );
+        assert_eq!(
+            parse_compiler_version(
+                Family::Gdc,
+                "gdc (Ubuntu 13.2.0-4ubuntu3) 13.2.0\nCopyright (C) 2023 Free Software Foundation"
+            ),
+            (Some(Version::new(13, 2, 0)), Some(Version::new(2, 103, 1)))
+        );
+        assert_eq!(parse_compiler_version(Family::Dmd, ""), (None, None));
+    }
+
+    #[test]
+    fn test_family_of() {
+        assert_eq!(Family::of("ldmd2"), Some(Family::Ldc));
+        assert_eq!(Family::of("/usr/bin/gdc-13"), Some(Family::Gdc));
+        assert_eq!(Family::of("dmd.exe"), Some(Family::Dmd));
+        assert_eq!(Family::of("rustc"), None);
+    }
+
+    #[test]
+    fn test_dub_minimum() {
+        let dub = |version: &str| Dub {
+            path: PathBuf::from("/usr/bin/dub"),
+            version: Some(version.parse().unwrap()),
+        };
+        assert!(dub("1.30.0").check_minimum().is_ok());
+        assert_eq!(
+            dub("1.29.2").check_minimum().unwrap_err(),
+            "DUB 1.29.2 found at '/usr/bin/dub', but cargo-dub requires DUB 1.30.0 or newer"
+        );
+    }
+
+    fn compiler(name: &str, family: Family, version: &str) -> Compiler {
+        Compiler {
+            name: name.into(),
+            family,
+            path: PathBuf::from(format!("/opt/bin/{name}")),
+            version: Some(version.parse().unwrap()),
+            frontend: None,
+        }
+    }
+
+    #[test]
+    fn test_pin_resolve() {
+        let pin: Pin = toml::from_str::<ToolchainFile>(
+            "[toolchain]\ncompiler = \"ldc\"\nversion = \"~>1.35\"\ndub = \">=1.34.0\"\n",
+        )
+        .unwrap()
+        .toolchain;
+        assert_eq!(pin.on_mismatch, OnMismatch::Error);
+        let compilers = vec![
+            compiler("dmd", Family::Dmd, "2.106.0"),
+            compiler("ldc2", Family::Ldc, "1.36.0"),
+            compiler("ldmd2", Family::Ldc, "1.36.0"),
+            compiler("ldc2", Family::Ldc, "1.35.0"),
+        ];
+        let dub = Version::new(1, 35, 0);
+
+        let resolution = pin.resolve(None, &compilers, Some(&dub));
+        assert!(resolution.problems.is_empty());
+        assert_eq!(resolution.compiler, Some(compilers[1].clone()));
+
+        let resolution = pin.resolve(Some(&compilers[0]), &compilers, None);
+        assert_eq!(resolution.compiler, None);
+        assert_eq!(
+            resolution.problems,
+            vec![
+                "DUB >=1.34.0 is required, but its version is unknown",
+                "dmd 2.106.0 at '/opt/bin/dmd' does not satisfy ldc >=1.35.0 <2.0.0",
+            ]
+        );
+
+        let resolution = pin.resolve(None, &compilers[..1], Some(&dub));
+        assert_eq!(
+            resolution.problems,
+            vec!["no installed compiler satisfies ldc >=1.35.0 <2.0.0 (found: dmd 2.106.0)"]
+        );
+    }
+
+    #[test]
+    fn test_pin_find() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let nested = temp.path().join("a/b");
+        fs::create_dir_all(&nested).unwrap();
+        assert_eq!(Pin::find(&nested).unwrap(), None);
+
+        fs::write(
+            temp.path().join(TOOLCHAIN_FILE),
+            "[toolchain]\ncompiler = \"ldmd2\"\non-mismatch = \"warn\"\n",
+        )
+        .unwrap();
+        let (path, pin) = Pin::find(&nested).unwrap().unwrap();
+        assert_eq!(path, temp.path().join(TOOLCHAIN_FILE));
+        assert_eq!(pin.compiler.as_deref(), Some("ldmd2"));
+        assert_eq!(pin.on_mismatch, OnMismatch::Warn);
+        assert!(pin.matches(&compiler("ldmd2", Family::Ldc, "1.0.0")));
+        assert!(!pin.matches(&compiler("ldc2", Family::Ldc, "1.0.0")));
+
+        fs::write(
+            temp.path().join(TOOLCHAIN_FILE),
+            "[toolchain]\nversion = \"x\"\n",
+        )
+        .unwrap();
+        assert!(Pin::find(&nested)
+            .unwrap_err()
+            .contains("invalid version 'x'"));
+    }
+
+    #[test]
+    fn test_table() {
+        let toolchain = Toolchain {
+            compilers: vec![Compiler {
+                name: "ldc2".into(),
+                family: Family::Ldc,
+                path: PathBuf::from("/opt/ldc/bin/ldc2"),
+                version: Some(Version::new(1, 36, 0)),
+                frontend: None,
+            }],
+            dub: Some(Dub {
+                path: PathBuf::from("/usr/bin/dub"),
+                version: None,
+            }),
+        };
+        assert_eq!(
+            toolchain.table(),
+            "NAME  FAMILY  VERSION  FRONTEND  PATH\n\
+             ldc2  ldc     1.36.0   -         /opt/ldc/bin/ldc2\n\
+             dub   -       -        -         /usr/bin/dub\n"
+        );
+    }
+}
@@ -0,0 +1,434 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Dependency graph of a package for `cargo dub tree`, built from
+//! `dub describe` output and the lockfile and rendered as an indented tree,
+//! Graphviz DOT or JSON.
+
+use crate::describe::Description;
+use crate::selections::{Selection, Selections};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt::{self, Write as _};
+use std::str::FromStr;
+
+/// Where a package in the graph comes from.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Source {
+    Registry,
+    Path { path: String },
+    Git { repository: String },
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Source::Registry => Ok(()),
+            Source::Path { path } => write!(f, "({path})"),
+            Source::Git { repository } => write!(f, "({repository})"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Node {
+    pub name: String,
+    pub version: String,
+    pub target_type: String,
+    pub source: Source,
+    /// Names of the packages this one depends on, in recipe order.
+    pub dependencies: Vec<String>,
+}
+
+impl Node {
+    /// `name v1.2.3 (source)`, as `cargo tree` labels packages.
+    fn package(&self) -> String {
+        let mut label = self.name.clone();
+        match self.version.as_str() {
+            "" => {}
+            branch if branch.starts_with('~') => label = format!("{label} {branch}"),
+            version => label = format!("{label} v{version}"),
+        }
+        if self.source != Source::Registry {
+            label = format!("{label} {}", self.source);
+        }
+        label
+    }
+}
+
+/// A `--format` template for tree labels.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Template(String);
+
+impl Template {
+    const HELP: &'static str = "{p} package, {n} name, {v} version, {s} source, {t} target type";
+
+    fn render(&self, node: &Node) -> String {
+        let mut out = String::new();
+        let mut rest = self.0.as_str();
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            // Validated when parsed.
+            let (key, tail) = rest[start + 1..].split_once('}').unwrap_or_default();
+            match key {
+                "p" => out.push_str(&node.package()),
+                "n" => out.push_str(&node.name),
+                "v" => out.push_str(&node.version),
+                "s" => out.push_str(&node.source.to_string()),
+                _ => out.push_str(&node.target_type),
+            }
+            rest = tail;
+        }
+        out + rest
+    }
+}
+
+impl Default for Template {
+    fn default() -> Self {
+        Template("{p}".to_string())
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, String> {
+        let mut rest = s;
+        while let Some(start) = rest.find('{') {
+            let key = rest[start + 1..].split_once('}').map(|(key, _)| key);
+            match key {
+                Some("p" | "n" | "v" | "s" | "t") => rest = &rest[start + 3..],
+                Some(key) => {
+                    return Err(format!(
+                        "unknown placeholder '{{{key}}}' (expected {})",
+                        Template::HELP
+                    ))
+                }
+                None => return Err("unterminated placeholder".to_string()),
+            }
+        }
+        Ok(Template(s.to_string()))
+    }
+}
+
+/// Packages of a build and the edges between them.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Graph {
+    pub root: String,
+    pub nodes: BTreeMap<String, Node>,
+}
+
+impl Graph {
+    /// The active packages of `description`, with sources taken from the
+    /// lockfile. Dependencies outside the build, such as unselected
+    /// optional ones, are left out.
+    pub fn new(description: &Description, selections: Option<&Selections>) -> Self {
+        let active: BTreeSet<&str> = description
+            .active_packages()
+            .map(|p| p.name.as_str())
+            .collect();
+        let nodes = description
+            .active_packages()
+            .map(|package| {
+                let path = || package.path.display().to_string();
+                let source = match selections.and_then(|s| s.get(&package.name)) {
+                    _ if package.name == description.root_package => Source::Path { path: path() },
+                    Some(Selection::Path(_)) => Source::Path { path: path() },
+                    Some(Selection::Repository { repository, .. }) => Source::Git {
+                        repository: repository.clone(),
+                    },
+                    _ => Source::Registry,
+                };
+                let node = Node {
+                    name: package.name.clone(),
+                    version: package.version.clone(),
+                    target_type: package.target_type.clone(),
+                    source,
+                    dependencies: package
+                        .dependencies
+                        .iter()
+                        .filter(|d| active.contains(d.as_str()))
+                        .cloned()
+                        .collect(),
+                };
+                (package.name.clone(), node)
+            })
+            .collect();
+        Self {
+            root: description.root_package.clone(),
+            nodes,
+        }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.nodes.contains_key(name)
+    }
+
+    /// Packages depending directly on `name`, sorted.
+    pub fn dependents(&self, name: &str) -> Vec<&str> {
+        self.nodes
+            .values()
+            .filter(|node| node.dependencies.iter().any(|d| d == name))
+            .map(|node| node.name.as_str())
+            .collect()
+    }
+
+    /// Packages required by more than one package. DUB selects a single
+    /// version of each, so these are where version constraints must agree.
+    pub fn duplicates(&self) -> Vec<&str> {
+        self.nodes
+            .keys()
+            .filter(|name| self.dependents(name).len() > 1)
+            .map(String::as_str)
+            .collect()
+    }
+
+    fn edges(&self, name: &str, invert: bool) -> Vec<&str> {
+        if invert {
+            self.dependents(name)
+        } else {
+            self.nodes.get(name).map_or_else(Vec::new, |node| {
+                node.dependencies.iter().map(String::as_str).collect()
+            })
+        }
+    }
+
+    /// Every dependency chain from the root to `name`, each starting with
+    /// the root and ending with `name`.
+    pub fn chains(&self, name: &str) -> Vec<Vec<&str>> {
+        fn visit<'a>(
+            graph: &'a Graph,
+            target: &str,
+            path: &mut Vec<&'a str>,
+            chains: &mut Vec<Vec<&'a str>>,
+        ) {
+            let last = *path.last().expect("paths start at the root");
+            if last == target {
+                chains.push(path.clone());
+                return;
+            }
+            for next in graph.edges(last, false) {
+                if !path.contains(&next) {
+                    path.push(next);
+                    visit(graph, target, path, chains);
+                    path.pop();
+                }
+            }
+        }
+        let mut chains = Vec::new();
+        if let Some((root, _)) = self.nodes.get_key_value(&self.root) {
+            visit(self, name, &mut vec![root.as_str()], &mut chains);
+        }
+        chains
+    }
+
+    /// An indented tree from `start`, following dependencies or, with
+    /// `invert`, dependents. Packages already shown are marked `(*)`
+    /// instead of being expanded again; `depth` 0 shows `start` only.
+    pub fn tree(
+        &self,
+        start: &str,
+        invert: bool,
+        depth: Option<usize>,
+        template: &Template,
+    ) -> String {
+        let mut out = String::new();
+        if let Some(node) = self.nodes.get(start) {
+            let _ = writeln!(out, "{}", template.render(node));
+            let mut walk = Walk {
+                graph: self,
+                invert,
+                depth,
+                template,
+                seen: BTreeSet::from([start]),
+                prefix: String::new(),
+                out,
+            };
+            walk.children(start, 1);
+            out = walk.out;
+        }
+        out
+    }
+
+    /// A Graphviz digraph with an edge from each package to its dependencies.
+    pub fn dot(&self) -> String {
+        let mut out = format!("digraph {} {{\n", quote(&self.root));
+        for node in self.nodes.values() {
+            let label = format!("{} {}", node.name, node.version);
+            let _ = writeln!(
+                out,
+                "    {} [label={}];",
+                quote(&node.name),
+                quote(label.trim_end())
+            );
+        }
+        for node in self.nodes.values() {
+            for dependency in &node.dependencies {
+                let _ = writeln!(out, "    {} -> {};", quote(&node.name), quote(dependency));
+            }
+        }
+        out.push_str("}\n");
+        out
+    }
+
+    pub fn to_json(&self) -> String {
+        let json = serde_json::json!({
+            "root": self.root,
+            "packages": self.nodes.values().collect::<Vec<_>>(),
+        });
+        serde_json::to_string_pretty(&json).expect("graphs serialize")
+    }
+}
+
+fn quote(id: &str) -> String {
+    format!("\"{}\"", id.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+struct Walk<'a> {
+    graph: &'a Graph,
+    invert: bool,
+    depth: Option<usize>,
+    template: &'a Template,
+    seen: BTreeSet<&'a str>,
+    prefix: String,
+    out: String,
+}
+
+impl<'a> Walk<'a> {
+    fn children(&mut self, name: &str, level: usize) {
+        if self.depth.is_some_and(|depth| level > depth) {
+            return;
+        }
+        let children = self.graph.edges(name, self.invert);
+        for (i, child) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let expand = self.seen.insert(child);
+            let repeated = !expand && !self.graph.edges(child, self.invert).is_empty();
+            let _ = writeln!(
+                self.out,
+                "{}{}{}{}",
+                self.prefix,
+                if last { "└── " } else { "├── " },
+                self.template.render(&self.graph.nodes[*child]),
+                if repeated { " (*)" } else { "" }
+            );
+            if expand {
+                let len = self.prefix.len();
+                self.prefix.push_str(if last { "    " } else { "│   " });
+                self.children(child, level + 1);
+                self.prefix.truncate(len);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph() -> Graph {
+        let description = Description::from_json(
+            r#"{"rootPackage": "app", "packages": [
+                {"name": "app", "version": "1.0.0", "path": "/work/app/", "active": true,
+                 "targetType": "executable", "dependencies": ["vibe-d:http", "mir-core", "dlib"]},
+                {"name": "vibe-d:http", "version": "0.9.8", "active": true,
+                 "targetType": "library", "dependencies": ["mir-core", "silly"]},
+                {"name": "mir-core", "version": "1.7.1", "active": true, "targetType": "library"},
+                {"name": "dlib", "version": "~master", "path": "/work/dlib/", "active": true,
+                 "targetType": "staticLibrary", "dependencies": ["mir-core"]},
+                {"name": "silly", "version": "1.1.1", "active": false, "targetType": "library"}
+            ]}"#,
+        )
+        .unwrap();
+        let selections = Selections::from_json_str(
+            r#"{"fileVersion": 1, "versions": {"vibe-d": "0.9.8", "mir-core": "1.7.1",
+                "dlib": {"path": "../dlib"}}}"#,
+        )
+        .unwrap();
+        Graph::new(&description, Some(&selections))
+    }
+
+    #[test]
+    fn test_tree() {
+        let graph = graph();
+        let template = Template::default();
+        assert_eq!(
+            graph.tree("app", false, None, &template),
+            "app v1.0.0 (/work/app/)\n\
+             ├── vibe-d:http v0.9.8\n\
+             │   └── mir-core v1.7.1\n\
+             ├── mir-core v1.7.1\n\
+             └── dlib ~master (/work/dlib/)\n    \
+                 └── mir-core v1.7.1\n"
+        );
+        assert_eq!(
+            graph.tree("mir-core", true, Some(1), &template),
+            "mir-core v1.7.1\n\
+             ├── app v1.0.0 (/work/app/)\n\
+             ├── dlib ~master (/work/dlib/)\n\
+             └── vibe-d:http v0.9.8\n"
+        );
+        assert_eq!(
+            graph.tree("mir-core", true, None, &"{n}".parse().unwrap()),
+            "mir-core\n├── app\n├── dlib\n│   └── app\n└── vibe-d:http\n    └── app\n"
+        );
+        assert_eq!(graph.duplicates(), ["mir-core"]);
+        assert_eq!(
+            graph.chains("mir-core"),
+            [
+                vec!["app", "vibe-d:http", "mir-core"],
+                vec!["app", "mir-core"],
+                vec!["app", "dlib", "mir-core"],
+            ]
+        );
+        assert!(graph.chains("silly").is_empty());
+    }
+
+    #[test]
+    fn test_repeated_subtree() {
+        let mut graph = graph();
+        graph.nodes.get_mut("mir-core").unwrap().dependencies = vec!["app".to_string()];
+        assert_eq!(
+            graph.tree("dlib", false, None, &"{n}".parse().unwrap()),
+            "dlib\n\
+             └── mir-core\n    \
+                 └── app\n        \
+                     ├── vibe-d:http\n        \
+                     │   └── mir-core (*)\n        \
+                     ├── mir-core (*)\n        \
+                     └── dlib (*)\n"
+        );
+    }
+
+    #[test]
+    fn test_template() {
+        let graph = graph();
+        let template: Template = "{n}@{v} [{t}] {s}".parse().unwrap();
+        assert_eq!(
+            template.render(&graph.nodes["dlib"]),
+            "dlib@~master [staticLibrary] (/work/dlib/)"
+        );
+        assert_eq!(
+            "{x}".parse::<Template>().unwrap_err(),
+            "unknown placeholder '{x}' (expected {p} package, {n} name, {v} version, \
+             {s} source, {t} target type)"
+        );
+        assert!("{p".parse::<Template>().is_err());
+    }
+
+    #[test]
+    fn test_dot_and_json() {
+        let graph = graph();
+        let dot = graph.dot();
+        assert!(dot.starts_with("digraph \"app\" {\n    \"app\" [label=\"app 1.0.0\"];\n"));
+        assert!(dot.contains("    \"vibe-d:http\" -> \"mir-core\";\n"));
+        assert!(!dot.contains("silly"));
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json()).unwrap();
+        assert_eq!(json["root"], "app");
+        assert_eq!(json["packages"][0]["name"], "app");
+        assert_eq!(json["packages"][1]["source"]["kind"], "path");
+        assert_eq!(json["packages"][1]["targetType"], "staticLibrary");
+    }
+}
@@ -0,0 +1,865 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Results of `dub test` runs, parsed from the output of druntime's default
+//! runner, [silly](https://code.dlang.org/packages/silly),
+//! [unit-threaded](https://code.dlang.org/packages/unit-threaded) or the
+//! per-block runner cargo-dub generates for packages using neither.
+
+use crate::{describe, Manifest};
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use std::{fs, io};
+
+/// The unittest runner a package uses.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Runner {
+    /// druntime's built-in runner, which reports per module.
+    #[default]
+    Default,
+    Silly,
+    UnitThreaded,
+    /// The runner from [`runner_source`], reporting every unittest block.
+    CargoDub,
+}
+
+impl Runner {
+    /// Picks the runner from the package's dependencies.
+    pub fn detect(manifest: &Manifest) -> Self {
+        let mut runner = Runner::Default;
+        for dependency in manifest.all_dependencies() {
+            match dependency.name.split(':').next() {
+                Some("silly") => runner = Runner::Silly,
+                Some("unit-threaded") => runner = Runner::UnitThreaded,
+                _ => {}
+            }
+        }
+        runner
+    }
+
+    /// Arguments passed to the test binary after `--` to select `filters`.
+    /// The default runner cannot filter; results are filtered afterwards.
+    pub fn filter_args(self, filters: &[String]) -> Vec<String> {
+        match self {
+            Runner::Default => Vec::new(),
+            Runner::Silly => {
+                let mut args = vec!["--no-colours".to_string()];
+                for filter in filters {
+                    args.push("--include".to_string());
+                    args.push(regex_escape(filter));
+                }
+                args
+            }
+            Runner::UnitThreaded | Runner::CargoDub => filters.to_vec(),
+        }
+    }
+}
+
+fn regex_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\.+*?()|[]{}^$".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestStatus {
+    Ok,
+    Failed,
+    Ignored,
+}
+
+/// One test, or one module for the default runner.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct TestCase {
+    pub name: String,
+    pub module: Option<String>,
+    pub status: TestStatus,
+    /// Assertion message of a failure.
+    pub message: Option<String>,
+    #[serde(serialize_with = "seconds")]
+    pub duration: Option<Duration>,
+}
+
+/// Durations are reported in (fractional) seconds, as libtest does.
+fn seconds<S: serde::Serializer>(duration: &Option<Duration>, s: S) -> Result<S::Ok, S::Error> {
+    match duration {
+        Some(duration) => s.serialize_f64(duration.as_secs_f64()),
+        None => s.serialize_none(),
+    }
+}
+
+impl TestCase {
+    fn new(name: impl Into<String>, module: Option<String>, status: TestStatus) -> Self {
+        Self {
+            name: name.into(),
+            module,
+            status,
+            message: None,
+            duration: None,
+        }
+    }
+}
+
+/// Everything known about a run. Counts may exceed `cases`: runners that
+/// only name failures still report how many tests passed.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Report {
+    pub runner: Runner,
+    pub cases: Vec<TestCase>,
+    pub passed: usize,
+    pub failed: usize,
+    pub ignored: usize,
+    pub filtered_out: usize,
+    #[serde(serialize_with = "seconds")]
+    pub duration: Option<Duration>,
+}
+
+/// Incremental parser for a test binary's output.
+#[derive(Debug)]
+pub struct Parser {
+    runner: Runner,
+    report: Report,
+    /// Index of the failed case collecting message lines, and whether its
+    /// message is complete so further indented lines (a stack trace) are dropped.
+    failing: Option<(usize, bool)>,
+    started: bool,
+    summarized: bool,
+}
+
+/// Whether a line of output was part of the test results.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineKind {
+    Result,
+    Other,
+}
+
+impl Parser {
+    pub fn new(runner: Runner) -> Self {
+        Self {
+            runner,
+            report: Report {
+                runner,
+                ..Report::default()
+            },
+            failing: None,
+            started: false,
+            summarized: false,
+        }
+    }
+
+    pub fn push(&mut self, line: &str) -> LineKind {
+        let line = strip_ansi(line);
+        let handled = match self.runner {
+            Runner::Default => self.push_default(&line),
+            Runner::Silly => self.push_silly(&line),
+            Runner::UnitThreaded => self.push_unit_threaded(&line),
+            Runner::CargoDub => self.push_cargo_dub(&line),
+        };
+        if handled {
+            LineKind::Result
+        } else {
+            self.failing = None;
+            LineKind::Other
+        }
+    }
+
+    /// `file(line): [unittest] message`, `Exception@file(line): message`,
+    /// `N modules passed unittests` and `F/N modules FAILED unittests`.
+    fn push_default(&mut self, line: &str) -> bool {
+        if let Some(count) = line.strip_suffix(" modules passed unittests") {
+            if let Ok(count) = count.trim().parse() {
+                self.report.passed = count;
+                self.summarized = true;
+                return true;
+            }
+        }
+        if let Some(counts) = line.strip_suffix(" modules FAILED unittests") {
+            if let Some((failed, total)) = counts.trim().split_once('/') {
+                if let (Ok(failed), Ok(total)) = (failed.parse::<usize>(), total.parse::<usize>()) {
+                    self.report.failed = failed;
+                    self.report.passed = total.saturating_sub(failed);
+                    self.summarized = true;
+                    return true;
+                }
+            }
+        }
+        let failure = line
+            .split_once("): [unittest] ")
+            .or_else(|| line.split_once('@')?.1.split_once("): "));
+        if let Some((location, message)) = failure {
+            if let Some((file, _)) = location.rsplit_once('(') {
+                let module = module_of(file);
+                // A module stops at its first failed assertion.
+                if !self.report.cases.iter().any(|c| c.name == module) {
+                    let mut case = TestCase::new(module.clone(), Some(module), TestStatus::Failed);
+                    case.message = Some(message.trim().to_string());
+                    self.report.cases.push(case);
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    /// ` ✓ module name`, ` ✗ module name` followed by indented details, and
+    /// `Summary: P passed, F failed in T ms`.
+    fn push_silly(&mut self, line: &str) -> bool {
+        let trimmed = line.trim_start();
+        for (mark, status) in [("✓ ", TestStatus::Ok), ("✗ ", TestStatus::Failed)] {
+            if let Some(rest) = trimmed.strip_prefix(mark) {
+                let (module, name) = rest.split_once(' ').unwrap_or((rest, rest));
+                self.report.cases.push(TestCase::new(
+                    format!("{module}.{name}"),
+                    Some(module.to_string()),
+                    status,
+                ));
+                self.failing =
+                    (status == TestStatus::Failed).then(|| (self.report.cases.len() - 1, false));
+                return true;
+            }
+        }
+        if let Some(summary) = trimmed.strip_prefix("Summary: ") {
+            let (counts, time) = summary.split_once(" in ").unwrap_or((summary, ""));
+            for part in counts.split(", ") {
+                match part.split_once(' ') {
+                    Some((n, "passed")) => self.report.passed = n.parse().unwrap_or(0),
+                    Some((n, "failed")) => self.report.failed = n.parse().unwrap_or(0),
+                    _ => {}
+                }
+            }
+            self.report.duration = parse_millis(time);
+            self.summarized = true;
+            return true;
+        }
+        self.collect_message(line, "--- Stack trace ---")
+    }
+
+    /// `Running tests in N threads`, failures as `name:` followed by indented
+    /// `file:line - message` lines, and `N test(s) run, F failed[, S skipped].`
+    fn push_unit_threaded(&mut self, line: &str) -> bool {
+        if line.starts_with("Running tests in ") {
+            self.started = true;
+            return true;
+        }
+        if !self.started {
+            return false;
+        }
+        if let Some(counts) = line
+            .strip_suffix('.')
+            .filter(|l| l.contains(" test(s) run"))
+        {
+            let mut run = 0;
+            for part in counts.split(", ") {
+                match part.split_once(' ') {
+                    Some((n, "test(s) run")) => run = n.parse().unwrap_or(0),
+                    Some((n, "failed")) => self.report.failed = n.parse().unwrap_or(0),
+                    Some((n, "skipped")) => self.report.ignored = n.parse().unwrap_or(0),
+                    _ => {}
+                }
+            }
+            self.report.passed = run - self.report.failed.min(run);
+            self.summarized = true;
+            return true;
+        }
+        if let Some(time) = line.strip_prefix("Time taken: ") {
+            self.report.duration = parse_millis(time.split(',').next().unwrap_or(""));
+            return true;
+        }
+        if matches!(line, "OK!" | "Tests failed!" | "") {
+            return true;
+        }
+        if let Some(name) = line
+            .strip_suffix(':')
+            .filter(|n| !n.contains(char::is_whitespace))
+        {
+            let module = name.rsplit_once('.').map(|(module, _)| module.to_string());
+            self.report
+                .cases
+                .push(TestCase::new(name, module, TestStatus::Failed));
+            self.failing = Some((self.report.cases.len() - 1, false));
+            return true;
+        }
+        self.collect_message(line, "")
+    }
+
+    /// `##cargo-dub-test` and `##cargo-dub-summary` lines from [`runner_source`].
+    fn push_cargo_dub(&mut self, line: &str) -> bool {
+        if let Some(fields) = line.strip_prefix("##cargo-dub-test\t") {
+            let fields: Vec<&str> = fields.splitn(5, '\t').collect();
+            let [status, module, name, micros, message] = fields[..] else {
+                return false;
+            };
+            let status = match status {
+                "ok" => TestStatus::Ok,
+                "failed" => TestStatus::Failed,
+                _ => TestStatus::Ignored,
+            };
+            let mut case = TestCase::new(format!("{module}.{name}"), Some(module.into()), status);
+            case.duration = micros.parse().ok().map(Duration::from_micros);
+            if !message.is_empty() {
+                case.message = Some(unescape(message));
+            }
+            self.report.cases.push(case);
+            return true;
+        }
+        if let Some(fields) = line.strip_prefix("##cargo-dub-summary\t") {
+            let counts: Vec<usize> = fields.split('\t').filter_map(|n| n.parse().ok()).collect();
+            if let [passed, failed, filtered_out] = counts[..] {
+                self.report.passed = passed;
+                self.report.failed = failed;
+                self.report.filtered_out = filtered_out;
+                self.summarized = true;
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Appends an indented line to the current failure's message.
+    fn collect_message(&mut self, line: &str, stop: &str) -> bool {
+        let Some((index, complete)) = self.failing else {
+            return false;
+        };
+        if !line.starts_with("    ") {
+            return false;
+        }
+        let text = line.trim();
+        if complete {
+            return true;
+        }
+        if !stop.is_empty() && text == stop {
+            self.failing = Some((index, true));
+            return true;
+        }
+        let message = self.report.cases[index]
+            .message
+            .get_or_insert_with(String::new);
+        if !message.is_empty() {
+            message.push('\n');
+        }
+        message.push_str(text);
+        true
+    }
+
+    /// Finishes the report, keeping only cases matching `filters` (by
+    /// substring, like libtest) when the runner could not filter itself.
+    pub fn finish(mut self, filters: &[String]) -> Report {
+        let report = &mut self.report;
+        if !self.summarized {
+            report.passed = count(&report.cases, TestStatus::Ok);
+            report.failed = count(&report.cases, TestStatus::Failed);
+        }
+        if self.runner == Runner::Default && !filters.is_empty() {
+            let before = report.cases.len();
+            report
+                .cases
+                .retain(|c| filters.iter().any(|f| c.name.contains(f.as_str())));
+            report.filtered_out = before - report.cases.len();
+            report.failed = count(&report.cases, TestStatus::Failed);
+        }
+        self.report
+    }
+}
+
+fn count(cases: &[TestCase], status: TestStatus) -> usize {
+    cases.iter().filter(|c| c.status == status).count()
+}
+
+/// `source/foo/bar.d` -> `foo.bar`, `source/foo/package.d` -> `foo`.
+pub fn module_of(file: &str) -> String {
+    let path = file.replace('\\', "/");
+    let path = path
+        .strip_prefix("source/")
+        .or_else(|| path.strip_prefix("src/"))
+        .unwrap_or(&path);
+    let path = path.strip_suffix(".d").unwrap_or(path);
+    let path = path.strip_suffix("/package").unwrap_or(path);
+    path.replace('/', ".")
+}
+
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            out.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') => out.push('\n'),
+            Some('t') => out.push('\t'),
+            Some(other) => out.push(other),
+            None => out.push('\\'),
+        }
+    }
+    out
+}
+
+/// D modules of `package`, as `dub describe` resolves it for the test
+/// configuration, whose unittests `dub test` runs: every source file except
+/// the main one, which DUB leaves out of test builds.
+pub fn package_modules(package: &describe::Package) -> io::Result<Vec<String>> {
+    let main_file =
+        (!package.main_source_file.is_empty()).then(|| package.resolve(&package.main_source_file));
+    let import_paths: Vec<PathBuf> = package
+        .import_paths
+        .iter()
+        .map(|path| package.resolve(path))
+        .collect();
+
+    let mut modules = Vec::new();
+    for file in package.files.iter().filter(|f| f.role == "source") {
+        let path = package.resolve(&file.path);
+        if main_file.as_ref() == Some(&path) || path.extension().is_none_or(|e| e != "d") {
+            continue;
+        }
+        let text = fs::read_to_string(&path)?;
+        let name = module_declaration(&text).unwrap_or_else(|| {
+            let relative = import_paths
+                .iter()
+                .find_map(|root| path.strip_prefix(root).ok())
+                .unwrap_or(Path::new(&file.path));
+            module_of(&relative.to_string_lossy())
+        });
+        modules.push(name);
+    }
+    modules.sort();
+    Ok(modules)
+}
+
+/// The name in a `module a.b;` declaration, skipping leading comments and
+/// attributes such as `deprecated`.
+pub fn module_declaration(source: &str) -> Option<String> {
+    let mut rest = source;
+    loop {
+        rest = rest.trim_start();
+        if let Some(after) = rest.strip_prefix("//") {
+            rest = after.split_once('\n').map_or("", |(_, r)| r);
+        } else if let Some(after) = rest.strip_prefix("/*") {
+            rest = after.split_once("*/")?.1;
+        } else if let Some(after) = rest.strip_prefix("/+") {
+            rest = after.split_once("+/")?.1;
+        } else {
+            break;
+        }
+    }
+    let (declaration, _) = rest.split_once(';')?;
+    let name = match declaration.strip_prefix("module") {
+        Some(name) => name,
+        None if declaration.starts_with("deprecated") || declaration.starts_with('@') => {
+            declaration.rsplit_once(" module")?.1
+        }
+        None => return None,
+    };
+    if !name.starts_with(char::is_whitespace) {
+        return None;
+    }
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '_' || c == '.');
+    valid.then(|| name.to_string())
+}
+
+/// D source of a test runner reporting each unittest block of `modules`,
+/// including those nested in aggregates and in template instances aliased
+/// there, with its duration and failure message, passed to
+/// `dub test --main-file`. Arguments after `--` select tests whose
+/// `module.name` contains them.
+pub fn runner_source(modules: &[String]) -> String {
+    let mut imports = String::new();
+    let mut calls = String::new();
+    for (i, module) in modules.iter().enumerate() {
+        let _ = writeln!(imports, "import m{i} = {module};");
+        let _ = writeln!(calls, "    runScope!m{i}(\"{module}\", null, totals);");
+    }
+    RUNNER_TEMPLATE
+        .replace("$IMPORTS", imports.trim_end())
+        .replace("$CALLS", calls.trim_end())
+}
+
+const RUNNER_TEMPLATE: &str = r###"// Generated by cargo-dub for `cargo dub test`; do not edit.
+module cargo_dub_test_runner;
+
+import core.runtime : Runtime, UnitTestResult;
+import core.time : MonoTime;
+import std.algorithm.searching : canFind;
+import std.array : replace;
+import std.conv : to;
+import std.stdio : stdout, writefln;
+
+$IMPORTS
+
+shared static this()
+{
+    Runtime.extendedModuleUnitTester = &runTests;
+}
+
+private string testName(alias test)()
+{
+    string name;
+    foreach (attr; __traits(getAttributes, test))
+        static if (is(typeof(attr) : string))
+            if (name is null)
+                name = attr;
+    return name is null ? __traits(identifier, test) : name;
+}
+
+private string escape(string text)
+{
+    return text.replace(`\`, `\\`).replace("\n", `\n`).replace("\t", `\t`);
+}
+
+private struct Totals
+{
+    string[] filters;
+    size_t passed, failed, filtered;
+    // Aggregates reachable through several aliases are visited once each.
+    bool[void function()] seen;
+}
+
+private enum isAggregate(alias sym) = is(sym == struct) || is(sym == class)
+    || is(sym == union) || is(sym == interface);
+
+// Whether `sym` is nested in `scope_` directly, or is an instance of a
+// template declared there such as `alias IntStack = Stack!int`. Both only
+// ever descend, so the recursion below terminates.
+private template isNestedIn(alias sym, alias scope_)
+{
+    static if (__traits(isSame, __traits(parent, sym), scope_))
+        enum isNestedIn = true;
+    else static if (__traits(compiles, __traits(parent, __traits(parent, sym))))
+        enum isNestedIn = __traits(isSame, __traits(parent, __traits(parent, sym)), scope_);
+    else
+        enum isNestedIn = false;
+}
+
+private void runScope(alias scope_)(string moduleName, string prefix, ref Totals totals)
+{
+    foreach (test; __traits(getUnitTests, scope_))
+    {
+        void function() fn = &test;
+        if (fn in totals.seen)
+            continue;
+        totals.seen[fn] = true;
+        immutable name = prefix ~ testName!test;
+        immutable full = moduleName ~ "." ~ name;
+        if (totals.filters.length && !totals.filters.canFind!(f => full.canFind(f)))
+        {
+            totals.filtered++;
+            continue;
+        }
+        string message;
+        immutable start = MonoTime.currTime;
+        try
+            test();
+        catch (Throwable t)
+            message = t.file ~ "(" ~ t.line.to!string ~ "): " ~ t.msg;
+        immutable micros = (MonoTime.currTime - start).total!"usecs";
+        if (message is null)
+            totals.passed++;
+        else
+            totals.failed++;
+        writefln("##cargo-dub-test\t%s\t%s\t%s\t%s\t%s", message is null ? "ok" : "failed",
+            moduleName, escape(name), micros, escape(message));
+    }
+
+    foreach (member; __traits(allMembers, scope_))
+    {
+        static if (__traits(compiles, { alias sym = __traits(getMember, scope_, member); }))
+        {
+            alias sym = __traits(getMember, scope_, member);
+            static if (isAggregate!sym)
+                static if (isNestedIn!(sym, scope_))
+                    runScope!sym(moduleName, prefix ~ member ~ ".", totals);
+        }
+    }
+}
+
+UnitTestResult runTests()
+{
+    Totals totals;
+    totals.filters = Runtime.args.length > 1 ? Runtime.args[1 .. $].dup : null;
+$CALLS
+    writefln("##cargo-dub-summary\t%s\t%s\t%s", totals.passed, totals.failed, totals.filtered);
+    stdout.flush();
+
+    UnitTestResult result;
+    result.executed = totals.passed + totals.failed;
+    result.passed = totals.passed;
+    result.runMain = false;
+    result.summarize = false;
+    return result;
+}
+
+void main()
+{
+}
+"###;
+
+fn parse_millis(text: &str) -> Option<Duration> {
+    let millis: u64 = text.trim().strip_suffix(" ms")?.trim().parse().ok()?;
+    Some(Duration::from_millis(millis))
+}
+
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // Skip `ESC [ ... letter`.
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+impl Report {
+    pub fn success(&self) -> bool {
+        self.failed == 0
+    }
+
+    /// libtest's human output: one line per known test, failure details
+    /// and the `test result:` line.
+    pub fn libtest(&self, elapsed: Duration) -> String {
+        let mut out = format!(
+            "\nrunning {} tests\n",
+            self.passed + self.failed + self.ignored
+        );
+        for case in &self.cases {
+            let status = match case.status {
+                TestStatus::Ok => "ok",
+                TestStatus::Failed => "FAILED",
+                TestStatus::Ignored => "ignored",
+            };
+            let _ = writeln!(out, "test {} ... {status}", case.name);
+        }
+        let failures: Vec<&TestCase> = self
+            .cases
+            .iter()
+            .filter(|c| c.status == TestStatus::Failed)
+            .collect();
+        if !failures.is_empty() {
+            out.push_str("\nfailures:\n");
+            for case in &failures {
+                let _ = writeln!(out, "\n---- {} ----", case.name);
+                if let Some(message) = &case.message {
+                    let _ = writeln!(out, "{message}");
+                }
+            }
+            out.push_str("\nfailures:\n");
+            for case in &failures {
+                let _ = writeln!(out, "    {}", case.name);
+            }
+        }
+        let _ = writeln!(
+            out,
+            "\ntest result: {}. {} passed; {} failed; {} ignored; 0 measured; {} filtered out; finished in {:.2}s",
+            if self.success() { "ok" } else { "FAILED" },
+            self.passed,
+            self.failed,
+            self.ignored,
+            self.filtered_out,
+            self.duration.unwrap_or(elapsed).as_secs_f64()
+        );
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(runner: Runner, output: &str, filters: &[String]) -> Report {
+        let mut parser = Parser::new(runner);
+        for line in output.lines() {
+            parser.push(line);
+        }
+        parser.finish(filters)
+    }
+
+    #[test]
+    fn test_default_runner() {
+        let output = "Running ./app-test-library\n\
+                      source/app/util.d(12): [unittest] 1 != 2\n\
+                      core.exception.AssertError@source/app/package.d(3): unittest failure\n\
+                      2/5 modules FAILED unittests";
+        let report = parse(Runner::Default, output, &[]);
+        assert_eq!((report.passed, report.failed), (3, 2));
+        assert_eq!(report.cases[0].name, "app.util");
+        assert_eq!(report.cases[0].message.as_deref(), Some("1 != 2"));
+        assert_eq!(report.cases[1].name, "app");
+
+        let report = parse(Runner::Default, output, &["util".to_string()]);
+        assert_eq!((report.failed, report.filtered_out), (1, 1));
+
+        let report = parse(Runner::Default, "4 modules passed unittests", &[]);
+        assert!(report.success());
+        assert_eq!(report.passed, 4);
+    }
+
+    #[test]
+    fn test_silly() {
+        let output = " \u{1b}[32m✓\u{1b}[0m app.math adds numbers\n \
+                      ✗ app.math divides\n    \
+                      core.exception.AssertError thrown from source/app/math.d on line 20: 1 != 0\n    \
+                      --- Stack trace ---\n    \
+                      ??:? _d_assert\n\
+                      \n\
+                      Summary: 1 passed, 1 failed in 7 ms";
+        let report = parse(Runner::Silly, output, &[]);
+        assert_eq!(report.cases.len(), 2);
+        assert_eq!(report.cases[0].name, "app.math.adds numbers");
+        assert_eq!(report.cases[0].module.as_deref(), Some("app.math"));
+        assert_eq!(
+            report.cases[1].message.as_deref(),
+            Some("core.exception.AssertError thrown from source/app/math.d on line 20: 1 != 0")
+        );
+        assert_eq!((report.passed, report.failed), (1, 1));
+        assert_eq!(report.duration, Some(Duration::from_millis(7)));
+        assert_eq!(
+            Runner::Silly.filter_args(&["math.add".to_string()]),
+            vec!["--no-colours", "--include", r"math\.add"]
+        );
+    }
+
+    #[test]
+    fn test_unit_threaded() {
+        let output = "Linking...\n\
+                      Running tests in 4 threads\n\
+                      tests.math.divides:\n    \
+                      tests/math.d:20 - Expected: 0\n    \
+                      tests/math.d:20 -      Got: 1\n\
+                      \n\
+                      Time taken: 12 ms, 4 μs, and 3 hnsecs\n\
+                      5 test(s) run, 1 failed, 1 skipped.\n\
+                      \n\
+                      Tests failed!";
+        let mut parser = Parser::new(Runner::UnitThreaded);
+        let kinds: Vec<LineKind> = output.lines().map(|l| parser.push(l)).collect();
+        assert_eq!(kinds[0], LineKind::Other);
+        let report = parser.finish(&[]);
+        assert_eq!(report.cases[0].name, "tests.math.divides");
+        assert_eq!(report.cases[0].module.as_deref(), Some("tests.math"));
+        assert_eq!(
+            report.cases[0].message.as_deref(),
+            Some("tests/math.d:20 - Expected: 0\ntests/math.d:20 -      Got: 1")
+        );
+        assert_eq!((report.passed, report.failed, report.ignored), (4, 1, 1));
+        assert_eq!(report.duration, Some(Duration::from_millis(12)));
+    }
+
+    #[test]
+    fn test_cargo_dub_runner() {
+        let output = "Running proj-test-library\n\
+                      ##cargo-dub-test\tok\tapp.math\tadds numbers\t1520\t\n\
+                      ##cargo-dub-test\tfailed\tapp.math\t__unittest_L20_C1\t87\t\
+                      source/app/math.d(21): 1 != 0\\n\\tat frame\n\
+                      ##cargo-dub-summary\t1\t1\t3";
+        let mut parser = Parser::new(Runner::CargoDub);
+        let kinds: Vec<LineKind> = output.lines().map(|l| parser.push(l)).collect();
+        assert_eq!(kinds[0], LineKind::Other);
+        let report = parser.finish(&["math".to_string()]);
+        assert_eq!(report.cases[0].name, "app.math.adds numbers");
+        assert_eq!(report.cases[0].duration, Some(Duration::from_micros(1520)));
+        assert_eq!(report.cases[1].status, TestStatus::Failed);
+        assert_eq!(
+            report.cases[1].message.as_deref(),
+            Some("source/app/math.d(21): 1 != 0\n\tat frame")
+        );
+        assert_eq!(
+            (report.passed, report.failed, report.filtered_out),
+            (1, 1, 3)
+        );
+    }
+
+    #[test]
+    fn test_package_modules() {
+        let temp = tempfile::TempDir::new().unwrap();
+        fs::create_dir_all(temp.path().join("source/app/math")).unwrap();
+        fs::write(temp.path().join("source/app.d"), "void main() {}\n").unwrap();
+        fs::write(
+            temp.path().join("source/app/util.d"),
+            "// helpers\n/* for\n tests */ module app.util;\n",
+        )
+        .unwrap();
+        fs::write(
+            temp.path().join("source/app/math/package.d"),
+            "void f() {}\n",
+        )
+        .unwrap();
+        let package = describe::Package {
+            name: "app".to_string(),
+            path: temp.path().to_path_buf(),
+            main_source_file: "source/app.d".to_string(),
+            import_paths: vec!["source/".to_string()],
+            files: [
+                "source/app.d",
+                "source/app/util.d",
+                "source/app/math/package.d",
+            ]
+            .into_iter()
+            .map(|path| describe::File {
+                role: "source".to_string(),
+                path: path.to_string(),
+            })
+            .chain([describe::File {
+                role: "stringImport".to_string(),
+                path: "views/index.d".to_string(),
+            }])
+            .collect(),
+            ..Default::default()
+        };
+        let modules = package_modules(&package).unwrap();
+        assert_eq!(modules, ["app.math", "app.util"]);
+
+        let source = runner_source(&modules);
+        assert!(source.contains("import m1 = app.util;\n"));
+        assert!(source.contains("runScope!m0(\"app.math\", null, totals);"));
+        // Unittests nested in aggregates are found through allMembers.
+        assert!(source.contains("runScope!sym(moduleName, prefix ~ member ~ \".\", totals);"));
+        assert_eq!(
+            module_declaration("deprecated module a.b;"),
+            Some("a.b".into())
+        );
+        assert_eq!(module_declaration("import std.stdio;"), None);
+    }
+
+    #[test]
+    fn test_libtest_output() {
+        let report = parse(
+            Runner::Silly,
+            " ✓ app first\n ✗ app second\n    boom\nSummary: 1 passed, 1 failed in 20 ms",
+            &[],
+        );
+        assert_eq!(
+            report.libtest(Duration::ZERO),
+            "\nrunning 2 tests\n\
+             test app.first ... ok\n\
+             test app.second ... FAILED\n\
+             \nfailures:\n\
+             \n---- app.second ----\n\
+             boom\n\
+             \nfailures:\n    \
+             app.second\n\
+             \ntest result: FAILED. 1 passed; 1 failed; 0 ignored; 0 measured; 0 filtered out; finished in 0.02s\n"
+        );
+        assert_eq!(module_of("src/foo/package.d"), "foo");
+    }
+}
@@ -0,0 +1,268 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Vendored package trees, as `cargo dub vendor` writes them.
+//!
+//! Each vendored package keeps a [`CHECKSUM_FILE`] recording the SHA-256 of
+//! every file, so later runs can tell whether the tree was edited.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::{fs, io};
+
+/// Name of the checksum file inside each vendored package.
+pub const CHECKSUM_FILE: &str = ".cargo-dub-checksum.json";
+
+/// Directories never copied into the vendor tree: VCS metadata and DUB's
+/// build cache.
+const SKIPPED: [&str; 2] = [".git", ".dub"];
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, io::Error),
+    Json(PathBuf, serde_json::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "Failed to access '{}': {e}", path.display()),
+            Error::Json(path, e) => write!(f, "Invalid '{}': {e}", path.display()),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+/// Contents of [`CHECKSUM_FILE`].
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Checksum {
+    pub package: String,
+    pub version: String,
+    /// SHA-256 by `/`-separated path relative to the package.
+    pub files: BTreeMap<String, String>,
+}
+
+impl Checksum {
+    /// Reads the checksum file of the vendored package in `dir`, or
+    /// returns `None` when there is none.
+    pub fn load(dir: &Path) -> Result<Option<Self>> {
+        let path = dir.join(CHECKSUM_FILE);
+        match fs::read_to_string(&path) {
+            Ok(text) => serde_json::from_str(&text)
+                .map(Some)
+                .map_err(|e| Error::Json(path, e)),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(Error::Io(path, e)),
+        }
+    }
+
+    fn save(&self, dir: &Path) -> Result<()> {
+        let path = dir.join(CHECKSUM_FILE);
+        let text = serde_json::to_string_pretty(self).expect("checksums serialize");
+        fs::write(&path, text + "\n").map_err(|e| Error::Io(path, e))
+    }
+}
+
+/// How a vendored file differs from its recorded checksum.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Difference {
+    Modified(String),
+    Missing(String),
+    Added(String),
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Difference::Modified(path) => write!(f, "modified: {path}"),
+            Difference::Missing(path) => write!(f, "missing:  {path}"),
+            Difference::Added(path) => write!(f, "added:    {path}"),
+        }
+    }
+}
+
+/// SHA-256 of every file below `dir`, except the checksum file itself.
+pub fn checksum_tree(dir: &Path) -> Result<BTreeMap<String, String>> {
+    let mut files = BTreeMap::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = fs::read_dir(&current).map_err(|e| Error::Io(current.clone(), e))?;
+        for entry in entries {
+            let path = entry.map_err(|e| Error::Io(current.clone(), e))?.path();
+            if path.is_dir() {
+                pending.push(path);
+                continue;
+            }
+            let relative = relative(dir, &path);
+            if relative == CHECKSUM_FILE {
+                continue;
+            }
+            let contents = fs::read(&path).map_err(|e| Error::Io(path.clone(), e))?;
+            let digest: String = Sha256::digest(&contents)
+                .iter()
+                .map(|byte| format!("{byte:02x}"))
+                .collect();
+            files.insert(relative, digest);
+        }
+    }
+    Ok(files)
+}
+
+fn relative(base: &Path, path: &Path) -> String {
+    let relative = path.strip_prefix(base).unwrap_or(path);
+    relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn copy_tree(source: &Path, dest: &Path) -> Result<()> {
+    fs::create_dir_all(dest).map_err(|e| Error::Io(dest.to_path_buf(), e))?;
+    let entries = fs::read_dir(source).map_err(|e| Error::Io(source.to_path_buf(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| Error::Io(source.to_path_buf(), e))?;
+        let (from, to) = (entry.path(), dest.join(entry.file_name()));
+        if from.is_dir() {
+            if !SKIPPED.iter().any(|skipped| entry.file_name() == *skipped) {
+                copy_tree(&from, &to)?;
+            }
+        } else {
+            fs::copy(&from, &to).map_err(|e| Error::Io(from.clone(), e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Replaces `dest` with a copy of the package in `source` and records the
+/// checksums of the copy.
+pub fn vendor(source: &Path, dest: &Path, package: &str, version: &str) -> Result<Checksum> {
+    if dest.exists() {
+        fs::remove_dir_all(dest).map_err(|e| Error::Io(dest.to_path_buf(), e))?;
+    }
+    copy_tree(source, dest)?;
+    let checksum = Checksum {
+        package: package.to_string(),
+        version: version.to_string(),
+        files: checksum_tree(dest)?,
+    };
+    checksum.save(dest)?;
+    Ok(checksum)
+}
+
+/// Compares the vendored package in `dir` against its recorded checksums.
+pub fn verify(dir: &Path, checksum: &Checksum) -> Result<Vec<Difference>> {
+    let actual = checksum_tree(dir)?;
+    let mut differences = Vec::new();
+    for (path, digest) in &checksum.files {
+        match actual.get(path) {
+            Some(found) if found == digest => {}
+            Some(_) => differences.push(Difference::Modified(path.clone())),
+            None => differences.push(Difference::Missing(path.clone())),
+        }
+    }
+    for path in actual.keys() {
+        if !checksum.files.contains_key(path) {
+            differences.push(Difference::Added(path.clone()));
+        }
+    }
+    differences.sort_by(|a, b| difference_path(a).cmp(difference_path(b)));
+    Ok(differences)
+}
+
+fn difference_path(difference: &Difference) -> &str {
+    match difference {
+        Difference::Modified(path) | Difference::Missing(path) | Difference::Added(path) => path,
+    }
+}
+
+/// A `local-packages.json` registering vendored packages with DUB, as
+/// `dub add-local` writes it.
+pub fn local_packages_json(packages: &[(String, String, PathBuf)]) -> String {
+    let entries: Vec<serde_json::Value> = packages
+        .iter()
+        .map(|(name, version, path)| {
+            serde_json::json!({
+                "name": name,
+                "version": version,
+                "path": path.display().to_string(),
+            })
+        })
+        .collect();
+    serde_json::to_string_pretty(&entries).expect("local packages serialize") + "\n"
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_vendor_and_verify() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("cache/mir-core");
+        fs::create_dir_all(source.join("source/mir")).unwrap();
+        fs::create_dir_all(source.join(".dub/build")).unwrap();
+        fs::write(source.join("dub.json"), r#"{"name": "mir-core"}"#).unwrap();
+        fs::write(source.join("source/mir/core.d"), "module mir.core;\n").unwrap();
+        fs::write(source.join(".dub/build/libmir.a"), "obj").unwrap();
+
+        let dest = temp.path().join("vendor/mir-core");
+        fs::create_dir_all(&dest).unwrap();
+        fs::write(dest.join("stale.d"), "").unwrap();
+        let checksum = vendor(&source, &dest, "mir-core", "1.7.1").unwrap();
+        assert_eq!(
+            checksum.files.keys().collect::<Vec<_>>(),
+            ["dub.json", "source/mir/core.d"]
+        );
+        assert_eq!(
+            checksum.files["source/mir/core.d"],
+            "87907a123d2f365c0abc06dd6f8968673656e31804937b00e2e2983f97a517c0"
+        );
+        assert_eq!(Checksum::load(&dest).unwrap(), Some(checksum.clone()));
+        assert!(verify(&dest, &checksum).unwrap().is_empty());
+
+        fs::write(
+            dest.join("source/mir/core.d"),
+            "module mir.core; // patched\n",
+        )
+        .unwrap();
+        fs::remove_file(dest.join("dub.json")).unwrap();
+        fs::write(dest.join("extra.d"), "").unwrap();
+        let differences: Vec<String> = verify(&dest, &checksum)
+            .unwrap()
+            .iter()
+            .map(|d| d.to_string())
+            .collect();
+        assert_eq!(
+            differences,
+            [
+                "missing:  dub.json",
+                "added:    extra.d",
+                "modified: source/mir/core.d"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_local_packages_json() {
+        let json = local_packages_json(&[(
+            "mir-core".to_string(),
+            "1.7.1".to_string(),
+            PathBuf::from("/work/app/vendor/mir-core"),
+        )]);
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!([
+                {"name": "mir-core", "version": "1.7.1", "path": "/work/app/vendor/mir-core"}
+            ])
+        );
+    }
+}
@@ -0,0 +1,369 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Semantic versions as used by DUB packages and D toolchains.
+
+use serde::{Deserialize, Serialize, Serializer};
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+    /// Dot-separated pre-release identifiers, e.g. `beta.1`.
+    pub pre: Vec<String>,
+    pub build: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError(pub String);
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid version '{}'", self.0)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl Version {
+    pub const fn new(major: u64, minor: u64, patch: u64) -> Self {
+        Self {
+            major,
+            minor,
+            patch,
+            pre: Vec::new(),
+            build: None,
+        }
+    }
+
+    pub fn is_prerelease(&self) -> bool {
+        !self.pre.is_empty()
+    }
+
+    /// Finds the first thing that looks like a version in free-form text, such
+    /// as compiler banners (`DMD64 D Compiler v2.106.0`).
+    pub fn find_in(text: &str) -> Option<Version> {
+        text.split(|c: char| c.is_whitespace() || matches!(c, '(' | ')' | ',' | ':'))
+            .filter(|word| {
+                word.trim_start_matches('v')
+                    .starts_with(|c: char| c.is_ascii_digit())
+                    && word.contains('.')
+            })
+            .find_map(|word| word.parse().ok())
+    }
+}
+
+impl FromStr for Version {
+    type Err = ParseError;
+
+    /// Parses `1.2.3`, `v1.2.3-beta.1+build` and the abbreviated `1.2` / `1`.
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let err = || ParseError(s.to_string());
+        let text = s.trim().strip_prefix('v').unwrap_or(s.trim());
+        let (text, build) = match text.split_once('+') {
+            Some((text, build)) => (text, Some(build.to_string())),
+            None => (text, None),
+        };
+        let (core, pre) = match text.split_once('-') {
+            Some((core, pre)) if !pre.is_empty() => {
+                (core, pre.split('.').map(str::to_string).collect())
+            }
+            Some(_) => return Err(err()),
+            None => (text, Vec::new()),
+        };
+        let mut parts = core.split('.');
+        let mut next = |required: bool| -> Result<u64, ParseError> {
+            match parts.next() {
+                Some(part) => part.parse().map_err(|_| err()),
+                None if required => Err(err()),
+                None => Ok(0),
+            }
+        };
+        let version = Version {
+            major: next(true)?,
+            minor: next(false)?,
+            patch: next(false)?,
+            pre,
+            build,
+        };
+        if parts.next().is_some() {
+            return Err(err());
+        }
+        Ok(version)
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)?;
+        if !self.pre.is_empty() {
+            write!(f, "-{}", self.pre.join("."))?;
+        }
+        if let Some(build) = &self.build {
+            write!(f, "+{build}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Serialize for Version {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(self)
+    }
+}
+
+impl Ord for Version {
+    /// SemVer precedence: pre-releases sort before the release, build metadata is ignored.
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.major, self.minor, self.patch)
+            .cmp(&(other.major, other.minor, other.patch))
+            .then_with(|| match (self.pre.is_empty(), other.pre.is_empty()) {
+                (true, true) => Ordering::Equal,
+                (true, false) => Ordering::Greater,
+                (false, true) => Ordering::Less,
+                (false, false) => compare_pre(&self.pre, &other.pre),
+            })
+    }
+}
+
+impl PartialOrd for Version {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+fn compare_pre(a: &[String], b: &[String]) -> Ordering {
+    for (x, y) in a.iter().zip(b) {
+        let ordering = match (x.parse::<u64>(), y.parse::<u64>()) {
+            (Ok(x), Ok(y)) => x.cmp(&y),
+            (Ok(_), Err(_)) => Ordering::Less,
+            (Err(_), Ok(_)) => Ordering::Greater,
+            (Err(_), Err(_)) => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// A DUB version specification such as `~>1.2.3`, `>=1.0.0 <2.0.0` or `~master`.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(try_from = "String")]
+pub enum VersionReq {
+    /// `*`: any version.
+    Any,
+    /// All comparators must hold.
+    Range(Vec<Comparator>),
+    /// A branch reference such as `~master`, matched by name only.
+    Branch(String),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Op {
+    Eq,
+    Gt,
+    Ge,
+    Lt,
+    Le,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct Comparator {
+    pub op: Op,
+    pub version: Version,
+}
+
+impl Comparator {
+    fn new(op: Op, version: Version) -> Self {
+        Self { op, version }
+    }
+
+    pub fn matches(&self, version: &Version) -> bool {
+        let ordering = version.cmp(&self.version);
+        match self.op {
+            Op::Eq => ordering == Ordering::Equal,
+            Op::Gt => ordering == Ordering::Greater,
+            Op::Ge => ordering != Ordering::Less,
+            Op::Lt => ordering == Ordering::Less,
+            Op::Le => ordering != Ordering::Greater,
+        }
+    }
+}
+
+impl VersionReq {
+    pub fn matches(&self, version: &Version) -> bool {
+        match self {
+            VersionReq::Any => true,
+            VersionReq::Range(comparators) => comparators.iter().all(|c| c.matches(version)),
+            VersionReq::Branch(_) => false,
+        }
+    }
+
+    /// Upper bound for `~>` with as many components as were written.
+    fn tilde_upper(text: &str, version: &Version) -> Version {
+        match text.split('.').count() {
+            1 | 2 => Version::new(version.major + 1, 0, 0),
+            _ => Version::new(version.major, version.minor + 1, 0),
+        }
+    }
+
+    /// Upper bound for `^`: the next release that may break compatibility.
+    fn caret_upper(version: &Version) -> Version {
+        match (version.major, version.minor) {
+            (0, 0) => Version::new(0, 0, version.patch + 1),
+            (0, minor) => Version::new(0, minor + 1, 0),
+            (major, _) => Version::new(major + 1, 0, 0),
+        }
+    }
+}
+
+impl FromStr for VersionReq {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, ParseError> {
+        let text = s.trim();
+        if text == "*" || text.is_empty() {
+            return Ok(VersionReq::Any);
+        }
+        if let Some(branch) = text.strip_prefix('~').filter(|b| !b.starts_with('>')) {
+            return Ok(VersionReq::Branch(branch.to_string()));
+        }
+        let err = || ParseError(s.to_string());
+
+        // Operators may be separated from their version by blanks (`>= 1.0`).
+        let mut tokens: Vec<String> = Vec::new();
+        for word in text.split_whitespace() {
+            match tokens.last_mut() {
+                Some(last) if last.chars().all(|c| "<>=~^".contains(c)) => last.push_str(word),
+                _ => tokens.push(word.to_string()),
+            }
+        }
+
+        let mut comparators = Vec::new();
+        for token in tokens {
+            let split = token.find(|c: char| !"<>=~^".contains(c)).ok_or_else(err)?;
+            let (op, version_text) = token.split_at(split);
+            let version: Version = version_text.parse().map_err(|_| err())?;
+            match op {
+                "" | "=" | "==" => comparators.push(Comparator::new(Op::Eq, version)),
+                ">" => comparators.push(Comparator::new(Op::Gt, version)),
+                ">=" => comparators.push(Comparator::new(Op::Ge, version)),
+                "<" => comparators.push(Comparator::new(Op::Lt, version)),
+                "<=" => comparators.push(Comparator::new(Op::Le, version)),
+                "~>" => {
+                    let upper = Self::tilde_upper(version_text, &version);
+                    comparators.push(Comparator::new(Op::Ge, version));
+                    comparators.push(Comparator::new(Op::Lt, upper));
+                }
+                "^" => {
+                    let upper = Self::caret_upper(&version);
+                    comparators.push(Comparator::new(Op::Ge, version));
+                    comparators.push(Comparator::new(Op::Lt, upper));
+                }
+                _ => return Err(err()),
+            }
+        }
+        Ok(VersionReq::Range(comparators))
+    }
+}
+
+impl TryFrom<String> for VersionReq {
+    type Error = ParseError;
+
+    fn try_from(s: String) -> Result<Self, ParseError> {
+        s.parse()
+    }
+}
+
+impl fmt::Display for VersionReq {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VersionReq::Any => f.write_str("*"),
+            VersionReq::Branch(branch) => write!(f, "~{branch}"),
+            VersionReq::Range(comparators) => {
+                for (i, c) in comparators.iter().enumerate() {
+                    if i > 0 {
+                        f.write_str(" ")?;
+                    }
+                    let op = match c.op {
+                        Op::Eq => "==",
+                        Op::Gt => ">",
+                        Op::Ge => ">=",
+                        Op::Lt => "<",
+                        Op::Le => "<=",
+                    };
+                    write!(f, "{op}{}", c.version)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn v(s: &str) -> Version {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn test_parse_and_display() {
+        assert_eq!(v("1.2.3"), Version::new(1, 2, 3));
+        assert_eq!(v("v2.106"), Version::new(2, 106, 0));
+        assert_eq!(v("1.0.0-beta.2+abc").to_string(), "1.0.0-beta.2+abc");
+        assert!("1.x".parse::<Version>().is_err());
+        assert!("1.2.3.4".parse::<Version>().is_err());
+        assert!("~master".parse::<Version>().is_err());
+    }
+
+    #[test]
+    fn test_ordering() {
+        assert!(v("1.30.0") > v("1.29.9"));
+        assert!(v("1.0.0-alpha") < v("1.0.0"));
+        assert!(v("1.0.0-alpha") < v("1.0.0-alpha.1"));
+        assert!(v("1.0.0-alpha.2") < v("1.0.0-alpha.10"));
+        assert!(v("1.0.0-1") < v("1.0.0-beta"));
+        assert_eq!(v("1.0.0+a").cmp(&v("1.0.0+b")), Ordering::Equal);
+    }
+
+    #[test]
+    fn test_version_req() {
+        let req = |s: &str| s.parse::<VersionReq>().unwrap();
+        assert!(req("*").matches(&v("0.0.1")));
+        assert!(req("1.2.3").matches(&v("1.2.3")));
+        assert!(!req("==1.2.3").matches(&v("1.2.4")));
+        assert!(req(">=1.0.0 <2.0.0").matches(&v("1.9.9")));
+        assert!(!req(">= 1.0.0 < 2.0.0").matches(&v("2.0.0")));
+        assert!(req("~>1.2.3").matches(&v("1.2.9")));
+        assert!(!req("~>1.2.3").matches(&v("1.3.0")));
+        assert!(req("~>1.2").matches(&v("1.9.0")));
+        assert!(!req("~>1.2").matches(&v("2.0.0")));
+        assert!(req("^0.2.3").matches(&v("0.2.9")));
+        assert!(!req("^0.2.3").matches(&v("0.3.0")));
+        assert_eq!(req("~master"), VersionReq::Branch("master".into()));
+        assert!(!req("~master").matches(&v("1.0.0")));
+        assert_eq!(req("~>1.2.3").to_string(), ">=1.2.3 <1.3.0");
+        assert!(">=x".parse::<VersionReq>().is_err());
+        assert!("=>1.0".parse::<VersionReq>().is_err());
+    }
+
+    #[test]
+    fn test_find_in() {
+        assert_eq!(
+            Version::find_in("DMD64 D Compiler v2.106.0\nCopyright"),
+            Some(v("2.106.0"))
+        );
+        assert_eq!(
+            Version::find_in("DUB version 1.35.1, built on Dec 30 2023"),
+            Some(v("1.35.1"))
+        );
+        assert_eq!(Version::find_in("no version here"), None);
+    }
+}
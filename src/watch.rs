@@ -0,0 +1,237 @@
+// SPDX-License-Identifier: MIT
+// Copyright (c) 2025 Matheus C. França
+
+//! Polling file watcher for `cargo dub watch`, scoped to the paths a DUB
+//! recipe compiles from.
+
+use crate::Manifest;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// Files and directories whose changes affect a build of the package in
+/// `dir`: its recipe and selections, every source, import and string import
+/// path of any configuration along with its `sourceFiles` and
+/// `mainSourceFile`, and the directories of path dependencies.
+pub fn watched_paths(dir: &Path, recipe: &Path, manifest: &Manifest) -> Vec<PathBuf> {
+    let mut paths = BTreeSet::from([recipe.to_path_buf(), dir.join("dub.selections.json")]);
+    let settings = std::iter::once(&manifest.settings)
+        .chain(manifest.configurations.iter().map(|c| &c.settings));
+
+    let (mut sources, mut views) = (false, false);
+    for settings in settings {
+        let source_paths = settings.source_paths();
+        let string_import_paths = settings.string_import_paths();
+        sources |= !source_paths.is_empty();
+        views |= !string_import_paths.is_empty();
+        for path in source_paths
+            .into_iter()
+            .chain(settings.import_paths())
+            .chain(string_import_paths)
+        {
+            paths.insert(dir.join(path));
+        }
+        // Files named directly may live outside every source path.
+        for file in settings
+            .list("sourceFiles")
+            .into_iter()
+            .chain(settings.main_source_file.as_deref())
+        {
+            paths.insert(dir.join(file));
+        }
+        for dependency in &settings.dependencies {
+            if let Some(path) = &dependency.path {
+                paths.insert(dir.join(path));
+            }
+        }
+    }
+    // DUB's defaults when the recipe names no paths.
+    if !sources {
+        paths.extend(["source", "src"].map(|d| dir.join(d)));
+    }
+    if !views {
+        paths.insert(dir.join("views"));
+    }
+    paths.into_iter().collect()
+}
+
+/// Modification time and size of every file under a set of roots.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Snapshot {
+    files: BTreeMap<PathBuf, (Option<SystemTime>, u64)>,
+}
+
+impl Snapshot {
+    /// Scans `roots`, which may be files or directories. Missing roots are
+    /// skipped so they are picked up once created; hidden directories such
+    /// as `.dub` and `.git` are not descended into.
+    pub fn scan(roots: &[PathBuf]) -> Self {
+        let mut snapshot = Self::default();
+        for root in roots {
+            snapshot.visit(root);
+        }
+        snapshot
+    }
+
+    fn visit(&mut self, path: &Path) {
+        let Ok(metadata) = fs::metadata(path) else {
+            return;
+        };
+        if metadata.is_dir() {
+            let Ok(entries) = fs::read_dir(path) else {
+                return;
+            };
+            for entry in entries.flatten() {
+                let hidden = entry.file_name().to_string_lossy().starts_with('.');
+                if !(hidden && entry.path().is_dir()) {
+                    self.visit(&entry.path());
+                }
+            }
+        } else {
+            let stamp = (metadata.modified().ok(), metadata.len());
+            self.files.insert(path.to_path_buf(), stamp);
+        }
+    }
+
+    /// Files added, removed or modified in `newer`.
+    pub fn changes(&self, newer: &Snapshot) -> Vec<PathBuf> {
+        let removed = self
+            .files
+            .keys()
+            .filter(|path| !newer.files.contains_key(*path));
+        let changed = newer
+            .files
+            .iter()
+            .filter(|(path, stamp)| self.files.get(*path) != Some(stamp))
+            .map(|(path, _)| path);
+        let mut paths: Vec<PathBuf> = removed.chain(changed).cloned().collect();
+        paths.sort();
+        paths
+    }
+}
+
+/// Reports changes once the watched files have been quiet for `debounce`,
+/// so that an editor saving several files triggers a single rebuild.
+#[derive(Debug)]
+pub struct Watcher {
+    roots: Vec<PathBuf>,
+    snapshot: Snapshot,
+    debounce: Duration,
+    pending: BTreeSet<PathBuf>,
+    last_change: Option<Instant>,
+}
+
+impl Watcher {
+    pub fn new(roots: Vec<PathBuf>, debounce: Duration) -> Self {
+        let snapshot = Snapshot::scan(&roots);
+        Self {
+            roots,
+            snapshot,
+            debounce,
+            pending: BTreeSet::new(),
+            last_change: None,
+        }
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Rescans the roots. Returns the files changed since the last report
+    /// when nothing changed during the last `debounce`.
+    pub fn poll(&mut self, now: Instant) -> Option<Vec<PathBuf>> {
+        let snapshot = Snapshot::scan(&self.roots);
+        let changes = self.snapshot.changes(&snapshot);
+        if !changes.is_empty() {
+            self.pending.extend(changes);
+            self.snapshot = snapshot;
+            self.last_change = Some(now);
+        }
+        let quiet = now.duration_since(self.last_change?) >= self.debounce;
+        if !quiet {
+            return None;
+        }
+        self.last_change = None;
+        Some(std::mem::take(&mut self.pending).into_iter().collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_watched_paths() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+        let recipe = dir.join("dub.sdl");
+        let manifest = Manifest::from_sdl_str(
+            "name \"app\"\n\
+             importPaths \"include\"\n\
+             dependency \"core\" path=\"../core\"\n\
+             sourceFiles \"tools/gen.d\"\n\
+             configuration \"gui\" {\n\tstringImportPaths \"assets\"\n\
+             \tmainSourceFile \"gui/main.d\"\n}\n",
+        )
+        .unwrap();
+        let paths = watched_paths(dir, &recipe, &manifest);
+        for expected in [
+            "dub.sdl",
+            "dub.selections.json",
+            "source",
+            "src",
+            "include",
+            "assets",
+            "../core",
+            "tools/gen.d",
+            "gui/main.d",
+        ] {
+            assert!(paths.contains(&dir.join(expected)), "{expected}");
+        }
+        assert!(!paths.contains(&dir.join("views")));
+    }
+
+    #[test]
+    fn test_snapshot_changes() {
+        let temp = TempDir::new().unwrap();
+        let source = temp.path().join("source");
+        fs::create_dir_all(source.join(".hidden")).unwrap();
+        fs::write(source.join("app.d"), "void main() {}").unwrap();
+        fs::write(source.join("old.d"), "").unwrap();
+        fs::write(source.join(".hidden/skip.d"), "").unwrap();
+        let roots = vec![source.clone(), temp.path().join("missing")];
+        let before = Snapshot::scan(&roots);
+        assert_eq!(before.files.len(), 2);
+
+        fs::write(source.join("app.d"), "void main() { run(); }").unwrap();
+        fs::remove_file(source.join("old.d")).unwrap();
+        fs::write(source.join("new.d"), "").unwrap();
+        fs::write(source.join(".hidden/skip.d"), "x").unwrap();
+        assert_eq!(
+            before.changes(&Snapshot::scan(&roots)),
+            ["app.d", "new.d", "old.d"].map(|f| source.join(f))
+        );
+    }
+
+    #[test]
+    fn test_debounce() {
+        let temp = TempDir::new().unwrap();
+        let file = temp.path().join("app.d");
+        fs::write(&file, "").unwrap();
+        let mut watcher = Watcher::new(vec![file.clone()], Duration::from_millis(100));
+        let start = Instant::now();
+        assert_eq!(watcher.poll(start), None);
+
+        fs::write(&file, "a").unwrap();
+        assert_eq!(watcher.poll(start), None);
+        fs::write(&file, "ab").unwrap();
+        assert_eq!(watcher.poll(start + Duration::from_millis(50)), None);
+        assert_eq!(
+            watcher.poll(start + Duration::from_millis(150)),
+            Some(vec![file])
+        );
+        assert_eq!(watcher.poll(start + Duration::from_millis(300)), None);
+    }
+}